@@ -0,0 +1,105 @@
+use std::f32::consts::TAU;
+use std::io::{self, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::Universe;
+
+
+pub const PLAYER_DEFAULT: &str = "aplay -q -t raw -f S16_LE -r 44100 -c 1";
+const SAMPLE_RATE: f32 = 44100.0;
+const BASE_FREQ: f32 = 110.0;
+const OCTAVES: f32 = 4.0;
+const VOLUME: f32 = 0.25;
+
+
+/// One generation's worth of sound.
+struct Tone {
+    population: usize,
+    births: usize,
+    deaths: usize,
+    cells: usize,
+    millis: u64,
+}
+
+
+/// Turns the simulation into sound: the pitch follows the population, a
+/// fifth above sounds for births and an octave below for deaths, so a
+/// stabilized board settles into a single steady tone.
+///
+/// Samples are piped as 16 bit mono PCM into an external player.
+pub struct Sonifier {
+    sender: Sender<Tone>,
+    player: Child,
+}
+
+
+impl Sonifier {
+    pub fn start(command: &str) -> io::Result<Sonifier> {
+        let mut parts = command.split_whitespace();
+        let program = parts.next().ok_or(io::Error::new(io::ErrorKind::InvalidInput, "empty audio command"))?;
+        let mut player = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let input = player.stdin.take().unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || synthesize(receiver, input));
+        Ok(Sonifier { sender, player })
+    }
+
+    pub fn play(&self, universe: &Universe, millis: u64) {
+        let (births, deaths) = universe.last_changes();
+        let _ = self.sender.send(Tone {
+            population: universe.live_cells().len(),
+            births,
+            deaths,
+            cells: universe.width * universe.height,
+            millis,
+        });
+    }
+}
+
+
+impl Drop for Sonifier {
+    fn drop(&mut self) {
+        let _ = self.player.kill();
+        let _ = self.player.wait();
+    }
+}
+
+
+fn synthesize(receiver: Receiver<Tone>, mut output: impl Write) {
+    let mut phases = [0.0f32; 3];
+
+    while let Ok(mut tone) = receiver.recv() {
+        // Skip generations the player could not keep up with.
+        while let Ok(newer) = receiver.try_recv() {tone = newer;}
+
+        let cells = tone.cells.max(1) as f32;
+        let freq = BASE_FREQ * 2f32.powf(OCTAVES * tone.population as f32 / cells);
+        let change = |n: usize| (n as f32 / (cells * 0.02)).min(1.0);
+        let voices = [
+            (freq, if tone.population > 0 {1.0} else {0.0}),
+            (freq * 1.5, change(tone.births)),
+            (freq * 0.5, change(tone.deaths)),
+        ];
+
+        let samples = (SAMPLE_RATE * tone.millis as f32 / 1000.0) as usize;
+        let mut buffer = Vec::with_capacity(samples * 2);
+        for _ in 0..samples {
+            let mut value = 0.0;
+            for (phase, (f, amplitude)) in phases.iter_mut().zip(voices) {
+                value += phase.sin() * amplitude;
+                *phase = (*phase + TAU * f / SAMPLE_RATE) % TAU;
+            }
+            let sample = (value / 3.0 * VOLUME * i16::MAX as f32) as i16;
+            buffer.extend_from_slice(&sample.to_le_bytes());
+        }
+        if output.write_all(&buffer).is_err() {return;}
+    }
+}
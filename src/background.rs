@@ -0,0 +1,60 @@
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+
+/// Counts the items a background job has finished.
+#[derive(Clone, Default)]
+pub struct Progress(Arc<AtomicUsize>);
+
+
+impl Progress {
+    pub fn add(&self, items: usize) {
+        self.0.fetch_add(items, Ordering::Relaxed);
+    }
+
+    fn done(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+
+/// A file being written on another thread so the game keeps running.
+pub struct Job {
+    pub label: String,
+    /// Shown when the job succeeds.
+    success: String,
+    total: usize,
+    progress: Progress,
+    handle: JoinHandle<io::Result<()>>,
+}
+
+
+impl Job {
+    /// Runs `work`, which reports how many of `total` items it has done.
+    pub fn spawn<F>(label: String, success: String, total: usize, work: F) -> Job
+        where F: FnOnce(&Progress) -> io::Result<()> + Send + 'static {
+        let progress = Progress::default();
+        let reporter = progress.clone();
+        let handle = thread::spawn(move || work(&reporter));
+        Job { label, success, total, progress, handle }
+    }
+
+    pub fn percent(&self) -> usize {
+        (self.progress.done() * 100).checked_div(self.total).unwrap_or(100).min(100)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Waits for the job and describes how it went.
+    pub fn finish(self) -> String {
+        match self.handle.join() {
+            Ok(Ok(())) => self.success,
+            Ok(Err(e)) => format!("Cannot write {}: {}", self.label, e),
+            Err(_) => format!("Writing {} failed", self.label),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use crate::parallel;
+use crate::rule::Rule;
+
+
+/// A board packed one bit per cell, 64 cells to a word, each row starting
+/// on a new word. The next generation is worked out a word at a time: the
+/// eight neighbours of 64 cells are added up together in four bit planes.
+pub struct Bitboard {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    /// Bits past `width` in the last word of a row are always clear.
+    words: Vec<u64>,
+}
+
+
+impl Bitboard {
+    pub fn from_cells(cells: &[bool], width: usize, height: usize) -> Bitboard {
+        let words_per_row = width.div_ceil(64);
+        let words = cells.chunks(width)
+            .flat_map(|row| row.chunks(64))
+            .map(|chunk| chunk.iter().rev().fold(0, |word, &alive| word << 1 | alive as u64))
+            .collect();
+        Bitboard { width, height, words_per_row, words }
+    }
+
+    pub fn to_cells(&self) -> Vec<bool> {
+        let mut cells = Vec::with_capacity(self.width * self.height);
+        for row in self.words.chunks(self.words_per_row) {
+            for col in 0..self.width {
+                cells.push(row[col / 64] >> (col % 64) & 1 == 1);
+            }
+        }
+        cells
+    }
+
+    fn row(&self, row: usize) -> &[u64] {
+        &self.words[row * self.words_per_row..(row + 1) * self.words_per_row]
+    }
+
+    /// The row `row` places along from `from`, or `None` past a bounded
+    /// edge.
+    fn row_at(&self, from: usize, by: isize, wrap: bool) -> Option<&[u64]> {
+        let row = from as isize + by;
+        if wrap {
+            Some(self.row(row.rem_euclid(self.height as isize) as usize))
+        } else if row >= 0 && (row as usize) < self.height {
+            Some(self.row(row as usize))
+        } else {None}
+    }
+
+    /// Word `k` of `row` with every cell moved one column east, so each
+    /// bit holds its western neighbour, and moved one column west.
+    fn shifted(&self, row: &[u64], k: usize, wrap: bool) -> (u64, u64) {
+        let last = self.words_per_row - 1;
+        let mut west = row[k] << 1 | if k > 0 {row[k - 1] >> 63} else {0};
+        let mut east = row[k] >> 1 | if k < last {row[k + 1] << 63} else {0};
+        if wrap {
+            let bit = |col: usize| row[col / 64] >> (col % 64) & 1;
+            if k == 0 {west |= bit(self.width - 1);}
+            if k == last {east |= bit(0) << ((self.width - 1) % 64);}
+        }
+        (west, east & self.valid_bits(k))
+    }
+
+    /// The bits of word `k` of a row that are on the board.
+    fn valid_bits(&self, k: usize) -> u64 {
+        let used = self.width - k * 64;
+        if used >= 64 {u64::MAX} else {(1 << used) - 1}
+    }
+
+    /// The next generation under `rule`. Cells outside the board are dead,
+    /// or with `wrap` the edges wrap around.
+    pub fn step(&self, rule: Rule, wrap: bool) -> Bitboard {
+        // Which neighbour counts leave a live or a dead cell alive.
+        let survives: Vec<usize> = (0..=8).filter(|&n| rule.next(true, n)).collect();
+        let born: Vec<usize> = (0..=8).filter(|&n| rule.next(false, n)).collect();
+        let mut words = vec![0; self.words.len()];
+        parallel::for_row_runs(&mut words, self.words_per_row, |first_row, run| {
+            for (i, next_row) in run.chunks_mut(self.words_per_row).enumerate() {
+                let row = first_row + i;
+                let above = self.row_at(row, -1, wrap);
+                let below = self.row_at(row, 1, wrap);
+                let current = self.row(row);
+                for k in 0..self.words_per_row {
+                    // Bit planes of the neighbour count of each cell.
+                    let mut count = [0u64; 4];
+                    let mut add = |bits: u64| {
+                        let mut carry = bits;
+                        for plane in count.iter_mut() {
+                            let next_carry = *plane & carry;
+                            *plane ^= carry;
+                            carry = next_carry;
+                        }
+                    };
+                    let (west, east) = self.shifted(current, k, wrap);
+                    add(west);
+                    add(east);
+                    for line in [above, below].into_iter().flatten() {
+                        let (west, east) = self.shifted(line, k, wrap);
+                        add(west);
+                        add(line[k]);
+                        add(east);
+                    }
+                    let with_count = |n: usize| (0..4).fold(u64::MAX, |mask, plane| {
+                        mask & if n >> plane & 1 == 1 {count[plane]} else {!count[plane]}
+                    });
+                    let cells = current[k];
+                    let mut next = 0;
+                    for &n in &survives {next |= with_count(n) & cells;}
+                    for &n in &born {next |= with_count(n) & !cells;}
+                    next_row[k] = next & self.valid_bits(k);
+                }
+            }
+        });
+        Bitboard { width: self.width, height: self.height, words_per_row: self.words_per_row, words }
+    }
+}
@@ -0,0 +1,81 @@
+use std::io::Write;
+
+use termion::event::Key;
+
+use crate::rule::Rule;
+use crate::undo::Snapshot;
+
+
+/// A board kept under a name, to come back to and carry on from.
+pub struct Bookmark {
+    pub name: String,
+    pub generation: u64,
+    pub rule: Rule,
+    pub snapshot: Snapshot,
+}
+
+
+impl Bookmark {
+    fn population(&self) -> usize {
+        self.snapshot.cells.iter().filter(|&&alive| alive).count()
+    }
+}
+
+
+pub enum BookmarksAction {
+    None,
+    Close,
+    Add,
+    Go(usize),
+    Remove(usize),
+}
+
+
+/// The list of bookmarks, one of them selected.
+pub struct BookmarksPanel {
+    selected: usize,
+}
+
+
+impl BookmarksPanel {
+    pub fn new() -> BookmarksPanel {
+        BookmarksPanel { selected: 0 }
+    }
+
+    pub fn handle_key(&mut self, key: Key, bookmarks: &[Bookmark]) -> BookmarksAction {
+        match key {
+            Key::Esc => BookmarksAction::Close,
+            Key::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                BookmarksAction::None
+            }
+            Key::Down if self.selected + 1 < bookmarks.len() => {
+                self.selected += 1;
+                BookmarksAction::None
+            }
+            Key::Char('a') => BookmarksAction::Add,
+            Key::Char('\n') if self.selected < bookmarks.len() => BookmarksAction::Go(self.selected),
+            Key::Char('d') if self.selected < bookmarks.len() => {
+                let at = self.selected;
+                // Stays on the last one when that is removed.
+                self.selected = self.selected.min(bookmarks.len().saturating_sub(2));
+                BookmarksAction::Remove(at)
+            }
+            _ => BookmarksAction::None,
+        }
+    }
+
+    pub fn render(&self, stdout: &mut dyn Write, bookmarks: &[Bookmark]) {
+        write!(stdout, "Bookmarks:\n\r").unwrap();
+        for (i, bookmark) in bookmarks.iter().enumerate() {
+            let marker = if i == self.selected {'>'} else {' '};
+            write!(stdout, "{} {} (generation {}, population {}, {}x{}, {})\n\r", marker, bookmark.name,
+                   bookmark.generation, bookmark.population(), bookmark.snapshot.width, bookmark.snapshot.height,
+                   bookmark.rule).unwrap();
+        }
+        if bookmarks.is_empty() {
+            write!(stdout, "  (none yet, A bookmarks the board)\n\r").unwrap();
+        }
+        write!(stdout, "[Up/Down] select  [Enter] go back to it  [A] add the board  [D] remove  [Esc] close\n\r").unwrap();
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::{HashSet, VecDeque};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::pattern::Pattern;
+use crate::Universe;
+
+
+const SURVIVE_GENERATIONS: u64 = 500;
+const MAX_SPACESHIP_PERIOD: usize = 40;
+const GUN_EMISSIONS: usize = 3;
+const BANNER_TIME: Duration = Duration::from_secs(5);
+
+type Shape = Vec<(usize, usize)>;
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Challenge {
+    Survive,
+    Spaceship,
+    GliderGun,
+}
+
+
+impl Challenge {
+    const ALL: [Challenge; 3] = [Challenge::Survive, Challenge::Spaceship, Challenge::GliderGun];
+
+    fn id(&self) -> &'static str {
+        match self {
+            Challenge::Survive => "survive-500",
+            Challenge::Spaceship => "spaceship",
+            Challenge::GliderGun => "glider-gun",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Challenge::Survive => "Keep a pattern alive for 500 generations",
+            Challenge::Spaceship => "Create a spaceship",
+            Challenge::GliderGun => "Build a working glider gun",
+        }
+    }
+}
+
+
+/// Watches the simulation for challenge goals and remembers completed
+/// ones in a progress file. Every edit of the board starts a new attempt.
+pub struct Challenges {
+    path: Option<PathBuf>,
+    completed: Vec<Challenge>,
+    alive_for: u64,
+    generation: u64,
+    shapes: VecDeque<(Shape, (usize, usize))>,
+    glider_shapes: HashSet<Shape>,
+    gliders: usize,
+    emissions: Vec<u64>,
+    banner: Option<(Challenge, Instant)>,
+}
+
+
+impl Challenges {
+    pub fn load() -> Challenges {
+        let path = progress_path();
+        let completed = path.as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|text| Challenge::ALL.iter()
+                 .filter(|c| text.lines().any(|l| l.trim() == c.id()))
+                 .copied()
+                 .collect())
+            .unwrap_or_default();
+
+        Challenges {
+            path,
+            completed,
+            alive_for: 0,
+            generation: 0,
+            shapes: VecDeque::new(),
+            glider_shapes: glider_shapes(),
+            gliders: 0,
+            emissions: Vec::new(),
+            banner: None,
+        }
+    }
+
+    pub fn completed(&self) -> &[Challenge] {
+        &self.completed
+    }
+
+    /// Starts a new attempt; called whenever the user changes the board.
+    pub fn reset(&mut self, universe: &Universe) {
+        self.alive_for = 0;
+        self.generation = 0;
+        self.shapes.clear();
+        self.emissions.clear();
+        self.gliders = self.count_gliders(universe);
+    }
+
+    /// Checks all goals after the universe advanced by one generation.
+    pub fn after_tick(&mut self, universe: &Universe) {
+        let cells = universe.live_cells();
+        self.generation += 1;
+
+        if cells.is_empty() {
+            self.alive_for = 0;
+        } else {
+            self.alive_for += 1;
+        }
+        if self.alive_for >= SURVIVE_GENERATIONS {
+            self.complete(Challenge::Survive);
+        }
+
+        if let Some((shape, origin)) = normalize(&cells) {
+            let moved = self.shapes.iter()
+                .any(|(s, o)| *s == shape && *o != origin);
+            if moved {self.complete(Challenge::Spaceship);}
+            if self.shapes.len() >= MAX_SPACESHIP_PERIOD {self.shapes.pop_front();}
+            self.shapes.push_back((shape, origin));
+        }
+
+        let gliders = self.count_gliders(universe);
+        if gliders > self.gliders {
+            self.emissions.push(self.generation);
+        }
+        self.gliders = gliders;
+        if self.is_gun(universe, gliders) {
+            self.complete(Challenge::GliderGun);
+        }
+    }
+
+    /// A gun emits gliders at a regular interval while something that is
+    /// not a glider stays behind.
+    fn is_gun(&self, universe: &Universe, gliders: usize) -> bool {
+        if self.emissions.len() < GUN_EMISSIONS || gliders == 0 {return false;}
+        let last = &self.emissions[self.emissions.len() - GUN_EMISSIONS..];
+        let period = last[1] - last[0];
+        let regular = last.windows(2).all(|w| w[1] - w[0] == period);
+        regular && components(&universe.live_cells()).len() > gliders
+    }
+
+    fn count_gliders(&self, universe: &Universe) -> usize {
+        components(&universe.live_cells()).iter()
+            .filter(|c| normalize(c).is_some_and(|(s, _)| self.glider_shapes.contains(&s)))
+            .count()
+    }
+
+    fn complete(&mut self, challenge: Challenge) {
+        if self.completed.contains(&challenge) {return;}
+        self.completed.push(challenge);
+        self.banner = Some((challenge, Instant::now()));
+        // Progress is best effort: failing to save must not stop the game.
+        let _ = self.save();
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let path = match &self.path {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        if let Some(dir) = path.parent() {fs::create_dir_all(dir)?;}
+        let text: String = self.completed.iter()
+            .map(|c| format!("{}\n", c.id()))
+            .collect();
+        fs::write(path, text)
+    }
+
+    /// Shows a short notice below the board after a challenge is completed.
+    pub fn render_banner(&self, stdout: &mut dyn Write) {
+        if let Some((challenge, since)) = self.banner {
+            if since.elapsed() < BANNER_TIME {
+                write!(stdout, "Challenge complete: {}!\n\r", challenge.description()).unwrap();
+            }
+        }
+    }
+
+    pub fn render(&self, stdout: &mut dyn Write) {
+        write!(stdout, "Challenges:\n\r").unwrap();
+        for challenge in Challenge::ALL {
+            let done = if self.completed.contains(&challenge) {'x'} else {' '};
+            write!(stdout, "[{}] {}", done, challenge.description()).unwrap();
+            if challenge == Challenge::Survive && done == ' ' {
+                write!(stdout, " ({}/{})", self.alive_for, SURVIVE_GENERATIONS).unwrap();
+            }
+            write!(stdout, "\n\r").unwrap();
+        }
+        write!(stdout, "Press any key to return\n\r").unwrap();
+    }
+}
+
+
+fn progress_path() -> Option<PathBuf> {
+    let data = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".local/share"),
+    };
+    Some(data.join("game-of-life-cli").join("challenges"))
+}
+
+
+/// Returns the cells relative to their bounding box and the box's origin.
+fn normalize(cells: &[(usize, usize)]) -> Option<(Shape, (usize, usize))> {
+    let min_r = cells.iter().map(|c| c.0).min()?;
+    let min_c = cells.iter().map(|c| c.1).min()?;
+    let mut shape: Shape = cells.iter().map(|(r, c)| (r - min_r, c - min_c)).collect();
+    shape.sort_unstable();
+    Some((shape, (min_r, min_c)))
+}
+
+
+/// Splits live cells into groups of touching (8-neighbour) cells.
+fn components(cells: &[(usize, usize)]) -> Vec<Vec<(usize, usize)>> {
+    let mut unvisited: HashSet<(usize, usize)> = cells.iter().copied().collect();
+    let mut result = Vec::new();
+
+    while let Some(&start) = unvisited.iter().next() {
+        unvisited.remove(&start);
+        let mut group = vec![start];
+        let mut i = 0;
+        while i < group.len() {
+            let (r, c) = group[i];
+            for dr in [-1, 0, 1] {
+                for dc in [-1, 0, 1] {
+                    let n = (r.wrapping_add_signed(dr), c.wrapping_add_signed(dc));
+                    if unvisited.remove(&n) {group.push(n);}
+                }
+            }
+            i += 1;
+        }
+        result.push(group);
+    }
+
+    result
+}
+
+
+/// All phases of the glider in all eight orientations, found by running
+/// a glider on a small board.
+fn glider_shapes() -> HashSet<Shape> {
+    let glider = Pattern::from_plaintext(".O.\n..O\nOOO");
+    let mut universe = Universe::new(8, 8);
+    universe.stamp(&glider, 1, 1);
+
+    let mut shapes = HashSet::new();
+    for _ in 0..4 {
+        let (shape, _) = normalize(&universe.live_cells()).unwrap();
+        for flip in [false, true] {
+            for turn in 0..4 {
+                let mut s: Shape = shape.iter()
+                    .map(|&(r, c)| if flip {(c, r)} else {(r, c)})
+                    .collect();
+                for _ in 0..turn {
+                    s = s.iter().map(|&(r, c)| (c, 2 - r)).collect();
+                }
+                s.sort_unstable();
+                shapes.insert(s);
+            }
+        }
+        universe.tick();
+    }
+
+    shapes
+}
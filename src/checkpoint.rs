@@ -0,0 +1,91 @@
+use std::fs;
+use std::io;
+
+use crate::diagnostic::Diagnostic;
+use crate::random::Rng;
+use crate::{share, Universe};
+
+
+const HEADER: &str = "game-of-life checkpoint";
+
+
+/// A headless run as it was when saved. The board keeps its generation.
+pub struct Checkpoint {
+    pub game: Universe,
+    pub rng: Rng,
+}
+
+
+/// Writes the state of a headless run to a file every `every` generations,
+/// so a long simulation survives a crash or reboot.
+pub struct Checkpointer {
+    path: String,
+    every: u64,
+}
+
+
+impl Checkpointer {
+    pub fn new(path: &str, every: u64) -> Checkpointer {
+        Checkpointer { path: path.to_string(), every: every.max(1) }
+    }
+
+    pub fn after_tick(&self, game: &Universe, rng: &Rng) -> Result<(), String> {
+        if !game.generation.is_multiple_of(self.every) {return Ok(());}
+        self.save(game, rng)
+    }
+
+    pub fn save(&self, game: &Universe, rng: &Rng) -> Result<(), String> {
+        save(&self.path, game, rng)
+            .map_err(|e| format!("Cannot write checkpoint {}: {}", self.path, e))
+    }
+}
+
+
+/// Saves the board (size, rule and cells as a board code), the generation
+/// counter and the random generator's state.
+///
+/// The file is written next to `path` first and then renamed over it, so a
+/// crash while saving leaves the previous checkpoint intact.
+pub fn save(path: &str, game: &Universe, rng: &Rng) -> io::Result<()> {
+    let text = format!("{}\ngeneration {}\nrng {}\nboard {}\n",
+                       HEADER,
+                       game.generation,
+                       rng.state(),
+                       share::encode(game));
+    let partial = format!("{}.partial", path);
+    fs::write(&partial, text)?;
+    fs::rename(&partial, path)
+}
+
+
+pub fn load(path: &str) -> Result<Checkpoint, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut lines = text.lines();
+    if lines.next() != Some(HEADER) {
+        return Err(format!("not a checkpoint file, expected `{}` on the first line", HEADER));
+    }
+
+    let (mut generation, mut rng, mut game) = (None, None, None);
+    for (n, line) in lines.enumerate() {
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        let error = |message: String| Diagnostic::new(n + 2, key.len() + 2, message).to_string();
+        let number = || value.parse::<u64>().map_err(|_| error(format!("expected a number, found `{}`", value)));
+        match key {
+            "generation" => {generation = Some(number()?);}
+            "rng" => {rng = Some(Rng::new(number()?));}
+            "board" => {game = Some(share::decode(value).map_err(error)?);}
+            _ => {
+                let message = format!("unknown key `{}`, expected generation, rng or board", key);
+                return Err(Diagnostic::new(n + 2, 1, message).to_string());
+            }
+        }
+    }
+
+    match (game, generation, rng) {
+        (Some(mut game), Some(generation), Some(rng)) => {
+            game.generation = generation;
+            Ok(Checkpoint { game, rng })
+        }
+        _ => Err("checkpoint is incomplete".to_string()),
+    }
+}
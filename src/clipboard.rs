@@ -0,0 +1,53 @@
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Asks the terminal for the text on the system clipboard. Terminals that
+/// allow it answer with `ESC ] 52 ; c ; <base64>` and BEL or `ESC \`.
+pub const QUERY: &str = "\x1b]52;c;?\x07";
+
+
+/// Asks the terminal to put `text` on the system clipboard (OSC 52). As
+/// the terminal does it, this works over SSH without X11 too, but some
+/// terminals ignore it unless allowed in their settings.
+pub fn copy(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", to_base64(text.as_bytes()))
+}
+
+
+/// The clipboard's text in the terminal's answer to `QUERY`, given what
+/// came between `ESC ]` and the end of the answer. `None` when the answer
+/// is not one or the clipboard holds no text.
+pub fn parse_answer(answer: &str) -> Option<String> {
+    let (_, data) = answer.strip_prefix("52;")?.split_once(';')?;
+    String::from_utf8(from_base64(data)?).ok().filter(|text| !text.is_empty())
+}
+
+
+/// Standard base64 with padding.
+fn to_base64(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+        for _ in chunk.len()..3 {out.push('=');}
+    }
+    out
+}
+
+
+fn from_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in text.trim_end_matches('=').bytes() {
+        acc = acc << 6 | BASE64.iter().position(|&b| b == c)? as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
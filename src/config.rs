@@ -0,0 +1,89 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::json::Json;
+use crate::toml;
+
+
+/// The keys of the configuration file, the option each stands for and the
+/// options that, given on the command line, set the same.
+const KEYS: [(&str, &str, &[&str]); 9] = [
+    ("width", "--width", &[]),
+    ("height", "--height", &[]),
+    ("tick_ms", "--tick-ms", &[]),
+    ("rule", "--rule", &[]),
+    ("topology", "--topology", &["--torus"]),
+    ("theme", "--theme", &[]),
+    ("glyphs", "--glyphs", &[]),
+    ("history", "--history", &[]),
+    ("history_mb", "--history-mb", &[]),
+];
+
+
+/// The directory the game's configuration files are in: under
+/// `$XDG_CONFIG_HOME`, or `~/.config` without it.
+pub fn dir() -> Option<PathBuf> {
+    let config = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config.join("game-of-life-cli"))
+}
+
+
+/// The configuration file to read: `path`, or `config.toml` in `dir` if
+/// there is one.
+pub fn path(path: Option<&str>) -> Option<PathBuf> {
+    match path {
+        Some(path) => Some(PathBuf::from(path)),
+        None => dir().map(|dir| dir.join("config.toml")).filter(|path| path.exists()),
+    }
+}
+
+
+/// The keys that options in `args` already set, which the file leaves
+/// alone.
+pub fn overridden(args: &[String]) -> Vec<&'static str> {
+    KEYS.iter()
+        .filter(|(_, option, others)| args.iter().any(|a| a == option || others.contains(&a.as_str())))
+        .map(|&(key, _, _)| key)
+        .collect()
+}
+
+
+/// The keys and values of the TOML file at `path`, numbers as text.
+pub fn read(path: &Path) -> Result<Vec<(String, String)>, String> {
+    let shown = path.display().to_string();
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", shown, e))?;
+    let Json::Object(fields) = toml::parse(&text).map_err(|d| d.in_file(&shown))? else {unreachable!()};
+    let mut values = Vec::new();
+    for (key, value) in fields {
+        if !KEYS.iter().any(|(k, _, _)| *k == key) {
+            let keys: Vec<&str> = KEYS.iter().map(|(k, _, _)| *k).collect();
+            return Err(format!("{}: unknown key `{}`, expected {}", shown, key, keys.join(", ")));
+        }
+        let value = match value {
+            Json::String(s) => s,
+            Json::Number(n) if n.fract() == 0.0 && n >= 0.0 => (n as u64).to_string(),
+            _ => return Err(format!("{}: `{}` must be a string or a whole number", shown, key)),
+        };
+        values.push((key, value));
+    }
+    Ok(values)
+}
+
+
+/// Adds the defaults of the TOML file at `path` to `args` as the options
+/// they stand for, so that `rule = "B36/S23"` reads as `--rule B36/S23`.
+/// Options already in `args` win over the file.
+pub fn add_defaults(args: &mut Vec<String>, path: &Path) -> Result<(), String> {
+    let overridden = overridden(args);
+    for (key, value) in read(path)? {
+        if overridden.contains(&key.as_str()) {continue;}
+        let &(_, option, _) = KEYS.iter().find(|(k, _, _)| *k == key).unwrap();
+        // After the rest, where neither the size nor `--help` is looked for.
+        args.extend([option.to_string(), value]);
+    }
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::diagnostic::Diagnostic;
+use crate::pattern::Pattern;
+use crate::rule::Rule;
+use crate::take_option;
+
+
+/// The pattern file formats `game-of-life convert` reads and writes.
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    /// Plaintext `.cells` files with `!` comments.
+    Cells,
+    /// ASCII art, where anything but whitespace and `.` is alive.
+    Text,
+    /// Run length encoded, as Golly writes them.
+    Rle,
+    /// Life 1.06 `.lif` files, one live cell per line.
+    Life106,
+}
+
+
+impl Format {
+    const ALL: [Format; 4] = [Format::Cells, Format::Text, Format::Rle, Format::Life106];
+
+    fn name(self) -> &'static str {
+        match self {
+            Format::Cells => "cells",
+            Format::Text => "txt",
+            Format::Rle => "rle",
+            Format::Life106 => "lif",
+        }
+    }
+
+    fn from_name(name: &str) -> Result<Format, String> {
+        Format::ALL.into_iter().find(|f| f.name() == name)
+            .ok_or(format!("unknown format `{}`, expected one of: {}", name, names()))
+    }
+
+    fn of_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        Format::ALL.into_iter().find(|f| f.name() == extension)
+    }
+
+    /// Reads a pattern along with warnings about anything that was
+    /// skipped, or the error that stopped it.
+    fn read(self, text: &str) -> Result<(Pattern, Vec<Diagnostic>), Diagnostic> {
+        match self {
+            Format::Cells => Ok(Pattern::parse_plaintext(text)),
+            Format::Text => Ok((Pattern::from_ascii(text), Vec::new())),
+            Format::Rle => Pattern::parse_rle(text),
+            Format::Life106 => Pattern::parse_life106(text),
+        }
+    }
+
+    fn write(self, pattern: &Pattern, name: &str) -> String {
+        match self {
+            Format::Cells => pattern.to_plaintext(Some(name)),
+            Format::Text => pattern.to_plaintext(None),
+            Format::Rle => pattern.to_rle(pattern.rule.unwrap_or(Rule::conway())),
+            Format::Life106 => pattern.to_life106(),
+        }
+    }
+}
+
+
+fn names() -> String {
+    Format::ALL.map(|f| f.name()).join(", ")
+}
+
+
+/// Runs `game-of-life convert`, either `<in> <out>` with formats taken
+/// from the extensions, or `--recursive <dir> --to <format> --out-dir <dir>`
+/// for a whole collection.
+pub fn run(args: &mut Vec<String>) -> Result<(), String> {
+    if let Some(dir) = take_option(args, "--recursive") {
+        let to = Format::from_name(&take_option(args, "--to").ok_or("--recursive needs --to <format>")?)?;
+        let out_dir = take_option(args, "--out-dir").ok_or("--recursive needs --out-dir <dir>")?;
+        return convert_tree(Path::new(&dir), to, Path::new(&out_dir));
+    }
+    match &args[1..] {
+        [input, output] => convert_file(Path::new(input), Path::new(output), None),
+        _ => Err("usage: game-of-life convert <in> <out>\n       \
+                  game-of-life convert --recursive <dir> --to <format> --out-dir <dir>".to_string()),
+    }
+}
+
+
+/// Converts `input` to `output`, in `to` or the format of its extension.
+fn convert_file(input: &Path, output: &Path, to: Option<Format>) -> Result<(), String> {
+    let from = Format::of_path(input)
+        .ok_or(format!("{}: unknown input format, expected one of: {}", input.display(), names()))?;
+    let to = match to.or(Format::of_path(output)) {
+        Some(f) => f,
+        None => return Err(format!("{}: unknown output format, expected one of: {}", output.display(), names())),
+    };
+
+    let text = fs::read_to_string(input).map_err(|e| format!("{}: {}", input.display(), e))?;
+    let file = input.display().to_string();
+    let (pattern, warnings) = from.read(&text).map_err(|d| d.in_file(&file))?;
+    for warning in warnings {eprintln!("warning: {}", warning.in_file(&file));}
+    let name = input.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    fs::write(output, to.write(&pattern, &name)).map_err(|e| format!("{}: {}", output.display(), e))
+}
+
+
+/// Converts every readable pattern below `dir` into `out_dir`, keeping the
+/// relative paths, and prints a summary.
+fn convert_tree(dir: &Path, to: Format, out_dir: &Path) -> Result<(), String> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    files.sort();
+
+    let (mut converted, mut skipped, mut failed) = (0, 0, 0);
+    for file in files {
+        if Format::of_path(&file).is_none() {
+            skipped += 1;
+            continue;
+        }
+        let relative = file.strip_prefix(dir).unwrap_or(&file);
+        let output = out_dir.join(relative).with_extension(to.name());
+        let result = match output.parent() {
+            Some(parent) => fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e)),
+            None => Ok(()),
+        };
+        match result.and_then(|_| convert_file(&file, &output, Some(to))) {
+            Ok(()) => {converted += 1;}
+            Err(e) => {
+                eprintln!("{}", e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("Converted {} files to {}, skipped {} in other formats, {} failed",
+             converted, to.name(), skipped, failed);
+    if failed > 0 {Err(format!("{} files could not be converted", failed))} else {Ok(())}
+}
+
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
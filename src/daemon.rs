@@ -0,0 +1,231 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::checkpoint::{Checkpoint, Checkpointer};
+use crate::random::Rng;
+use crate::rule::Rule;
+use crate::timer::{format_duration, RunTimer};
+use crate::{import_file, share, ImageOptions, Placement, Universe};
+
+
+const IDLE_SLEEP: Duration = Duration::from_millis(10);
+/// How long `step` holds the state at a time, so a long one lets the
+/// other clients and the running board in between.
+const STEP_SLICE: Duration = Duration::from_millis(20);
+const DENSITY_DEFAULT: f64 = 0.35;
+
+
+struct State {
+    game: Universe,
+    running: bool,
+    tick_millis: u64,
+    options: ImageOptions,
+    shutdown: bool,
+    rng: Rng,
+    checkpointer: Option<Checkpointer>,
+    timer: RunTimer,
+}
+
+
+impl State {
+    fn tick(&mut self) {
+        self.game.tick();
+        if let Some(c) = &self.checkpointer {
+            if let Err(e) = c.after_tick(&self.game, &self.rng) {eprintln!("{}", e);}
+        }
+    }
+}
+
+
+/// Runs the simulation without a terminal UI, driven by clients on a Unix
+/// domain socket. Each line sent is one command; the reply is any number of
+/// data lines followed by a line starting with `ok` or `error`.
+///
+/// Commands: `step [n]`, `run [millis]`, `stop`, `load <file|code>`, `dump`,
+/// `code`, `set-rule <rule>`, `random [density]`, `clear`, `timer [secs]`,
+/// `status`, `shutdown`.
+pub fn run(socket: &str,
+           start: Checkpoint,
+           options: ImageOptions,
+           checkpointer: Option<Checkpointer>,
+           tick_millis: u64,
+           max_run: Option<Duration>) -> io::Result<()> {
+    // A socket left over from a previous run would make bind fail.
+    let _ = fs::remove_file(socket);
+    let listener = UnixListener::bind(socket)?;
+    let state = Arc::new(Mutex::new(State {
+        running: start.game.is_running,
+        game: start.game,
+        tick_millis,
+        options,
+        shutdown: false,
+        rng: start.rng,
+        checkpointer,
+        timer: RunTimer::new(max_run),
+    }));
+
+    let accepting = Arc::clone(&state);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let state = Arc::clone(&accepting);
+            thread::spawn(move || serve(stream, state));
+        }
+    });
+
+    loop {
+        let wait = {
+            let mut s = state.lock().unwrap();
+            if s.shutdown {break;}
+            let running = s.running;
+            if s.timer.update(running) {
+                s.running = false;
+                eprintln!("Stopped after {} at generation {}", format_duration(s.timer.elapsed()), s.game.generation);
+            }
+            if s.running {
+                s.tick();
+                Duration::from_millis(s.tick_millis)
+            } else {
+                IDLE_SLEEP
+            }
+        };
+        thread::sleep(wait);
+    }
+
+    fs::remove_file(socket)
+}
+
+
+fn serve(stream: UnixStream, state: Arc<Mutex<State>>) {
+    let mut writer = match stream.try_clone() {
+        Ok(w) => w,
+        Err(_) => return,
+    };
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => return,
+        };
+        if line.trim().is_empty() {continue;}
+
+        let result = match split(line.trim()) {
+            ("step", arg) => step(&state, arg),
+            _ => execute(&mut state.lock().unwrap(), line.trim()),
+        };
+        let reply = result.unwrap_or_else(|e| format!("error {}\n", e));
+        if writer.write_all(reply.as_bytes()).is_err() {return;}
+    }
+}
+
+
+/// The command of `line` and its argument.
+fn split(line: &str) -> (&str, &str) {
+    match line.split_once(char::is_whitespace) {
+        Some((c, a)) => (c, a.trim()),
+        None => (line, ""),
+    }
+}
+
+
+fn number(arg: &str, default: u64) -> Result<u64, String> {
+    if arg.is_empty() {return Ok(default);}
+    arg.parse().map_err(|_| format!("`{}` is not a number", arg))
+}
+
+
+/// Carries out `step [n]`, taking the state for `STEP_SLICE` at a time.
+fn step(state: &Mutex<State>, arg: &str) -> Result<String, String> {
+    let mut left = number(arg, 1)?;
+    loop {
+        let mut s = state.lock().unwrap();
+        let slice = Instant::now();
+        while left > 0 && slice.elapsed() < STEP_SLICE {
+            s.tick();
+            left -= 1;
+        }
+        if left == 0 || s.shutdown {return Ok(format!("ok generation {}\n", s.game.generation));}
+        drop(s);
+        thread::yield_now();
+    }
+}
+
+
+fn execute(state: &mut State, line: &str) -> Result<String, String> {
+    let (command, arg) = split(line);
+    let number = |default: u64| number(arg, default);
+
+    match command {
+        "run" => {
+            state.tick_millis = number(state.tick_millis)?.max(1);
+            state.running = true;
+            Ok("ok running\n".to_string())
+        }
+        "stop" => {
+            state.running = false;
+            Ok(format!("ok stopped at generation {}\n", state.game.generation))
+        }
+        "load" if arg.starts_with("life:") => {
+            state.game = share::decode(arg)?;
+            Ok("ok loaded\n".to_string())
+        }
+        "load" => {
+            if arg.is_empty() {return Err("load needs a file or a board code".to_string());}
+            let warnings = import_file(&mut state.game, arg, &state.options, Placement::Center)?;
+            state.game.generation = 0;
+            let mut reply: String = warnings.iter().map(|w| format!("warning {}\n", w)).collect();
+            reply += "ok loaded\n";
+            Ok(reply)
+        }
+        "dump" => {
+            let game = &state.game;
+            let mut out = String::new();
+            for row in 0..game.height {
+                for col in 0..game.width {
+                    out.push(if game.get_cell(row, col) {'O'} else {'.'});
+                }
+                out.push('\n');
+            }
+            Ok(out + "ok\n")
+        }
+        "code" => Ok(format!("{}\nok\n", share::encode(&state.game))),
+        "set-rule" => {
+            state.game.rule = Rule::parse(arg)?;
+            Ok(format!("ok rule {}\n", state.game.rule))
+        }
+        "random" => {
+            let density = if arg.is_empty() {DENSITY_DEFAULT} else {
+                arg.parse().ok().filter(|d| (0.0..=1.0).contains(d))
+                    .ok_or(format!("`{}` is not a density from 0 to 1", arg))?
+            };
+            state.game.randomize(density, &mut state.rng);
+            Ok("ok randomized\n".to_string())
+        }
+        "clear" => {
+            state.game.clear();
+            Ok("ok cleared\n".to_string())
+        }
+        "timer" if arg.is_empty() || arg == "off" => {
+            state.timer.stop_after(None);
+            Ok("ok timer off\n".to_string())
+        }
+        "timer" => {
+            let secs = number(0)?;
+            state.timer.stop_after(Some(Duration::from_secs(secs)));
+            Ok(format!("ok stopping after {} more running time\n", format_duration(Duration::from_secs(secs))))
+        }
+        "status" => Ok(format!("ok generation {} population {} rule {} {} time {}\n",
+                               state.game.generation,
+                               state.game.population,
+                               state.game.rule,
+                               if state.running {"running"} else {"stopped"},
+                               format_duration(state.timer.elapsed()))),
+        "shutdown" => {
+            state.shutdown = true;
+            Ok("ok shutting down\n".to_string())
+        }
+        other => Err(format!("unknown command `{}`", other)),
+    }
+}
@@ -0,0 +1,30 @@
+use std::fmt;
+
+
+/// A problem found while parsing a text file, with the position it was
+/// found at. Positions count from 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+
+impl Diagnostic {
+    pub fn new(line: usize, column: usize, message: impl Into<String>) -> Diagnostic {
+        Diagnostic { line, column, message: message.into() }
+    }
+
+    /// Formats the diagnostic as `file:line:column: message`.
+    pub fn in_file(&self, path: &str) -> String {
+        format!("{}:{}:{}: {}", path, self.line, self.column, self.message)
+    }
+}
+
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
@@ -0,0 +1,57 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::background::Progress;
+use crate::json;
+
+
+#[derive(Clone)]
+struct Event {
+    /// Milliseconds since the Unix epoch.
+    time: u128,
+    generation: u64,
+    kind: &'static str,
+    detail: String,
+}
+
+
+/// Everything that happened in a session, in order: key presses, edits,
+/// loads, generations and triggers such as completed challenges.
+#[derive(Clone)]
+pub struct EventLog {
+    started: Instant,
+    started_at: SystemTime,
+    events: Vec<Event>,
+}
+
+
+impl EventLog {
+    pub fn new() -> EventLog {
+        EventLog { started: Instant::now(), started_at: SystemTime::now(), events: Vec::new() }
+    }
+
+    pub fn record(&mut self, generation: u64, kind: &'static str, detail: impl Into<String>) {
+        let now = self.started_at + self.started.elapsed();
+        let time = now.duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+        self.events.push(Event { time, generation, kind, detail: detail.into() });
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Writes one JSON object per line with the fields `time` (Unix
+    /// milliseconds), `generation`, `kind` and `detail`, counting the
+    /// events written in `progress`.
+    pub fn save(&self, path: &str, progress: &Progress) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        for e in &self.events {
+            writeln!(out, "{{\"time\":{},\"generation\":{},\"kind\":{},\"detail\":{}}}",
+                     e.time, e.generation, json::string(e.kind), json::string(&e.detail))?;
+            progress.add(1);
+        }
+        out.flush()
+    }
+}
+
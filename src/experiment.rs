@@ -0,0 +1,156 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::random::Rng;
+use crate::rule::Rule;
+use crate::{take_option, Universe};
+
+
+const GENERATIONS_DEFAULT: u64 = 1000;
+
+
+/// The parameter grid of `game-of-life experiment`: every combination of
+/// density, rule and seed is one run.
+pub struct Sweep {
+    densities: Vec<f64>,
+    rules: Vec<Rule>,
+    seeds: Vec<u64>,
+    generations: u64,
+    pub out: Option<String>,
+}
+
+
+struct Run {
+    density: f64,
+    rule: Rule,
+    seed: u64,
+}
+
+
+struct Metrics {
+    population: usize,
+    /// The first generation of the cycle the board ended up in.
+    stabilized_at: Option<u64>,
+    period: Option<u64>,
+}
+
+
+impl Sweep {
+    /// Takes `--densities`, `--rules`, `--seeds`, `--generations` and
+    /// `--out` from `args`.
+    pub fn from_args(args: &mut Vec<String>) -> Result<Sweep, String> {
+        let densities = take_option(args, "--densities").unwrap_or("0.35".to_string());
+        let rules = take_option(args, "--rules").unwrap_or("B3/S23".to_string());
+        let seeds = take_option(args, "--seeds").unwrap_or("1".to_string());
+        let generations = match take_option(args, "--generations") {
+            Some(n) => n.parse().map_err(|_| format!("`{}` is not a number of generations", n))?,
+            None => GENERATIONS_DEFAULT,
+        };
+
+        let densities = densities.split(',')
+            .map(|d| match d.trim().parse() {
+                Ok(d) if (0.0..=1.0).contains(&d) => Ok(d),
+                _ => Err(format!("`{}` is not a density from 0 to 1", d)),
+            })
+            .collect::<Result<_, _>>()?;
+        let rules = rules.split(',').map(|r| Rule::parse(r.trim())).collect::<Result<_, _>>()?;
+        let mut seed_list = Vec::new();
+        for part in seeds.split(',') {
+            let number = |s: &str| s.trim().parse::<u64>().map_err(|_| format!("`{}` is not a seed", s));
+            match part.split_once('-') {
+                Some((from, to)) => seed_list.extend(number(from)?..=number(to)?),
+                None => seed_list.push(number(part)?),
+            }
+        }
+
+        Ok(Sweep {
+            densities,
+            rules,
+            seeds: seed_list,
+            generations,
+            out: take_option(args, "--out"),
+        })
+    }
+
+    fn runs(&self) -> Vec<Run> {
+        let mut runs = Vec::new();
+        for &density in &self.densities {
+            for &rule in &self.rules {
+                for &seed in &self.seeds {
+                    runs.push(Run { density, rule, seed });
+                }
+            }
+        }
+        runs
+    }
+}
+
+
+/// Runs every combination of the sweep on a copy of `board` and writes
+/// one CSV line per run, in grid order, using all available cores.
+pub fn run(sweep: &Sweep, board: &Universe, out: &mut dyn Write) -> io::Result<()> {
+    let runs = sweep.runs();
+    let results: Mutex<Vec<Option<Metrics>>> = Mutex::new(runs.iter().map(|_| None).collect());
+    let next = AtomicUsize::new(0);
+    let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+
+    thread::scope(|scope| {
+        for _ in 0..threads.min(runs.len()) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(run) = runs.get(i) else {break};
+                let metrics = simulate(board, run, sweep.generations);
+                results.lock().unwrap()[i] = Some(metrics);
+            });
+        }
+    });
+
+    writeln!(out, "density,rule,seed,generations,final_population,stabilized_at,period")?;
+    for (run, metrics) in runs.iter().zip(results.into_inner().unwrap()) {
+        let metrics = metrics.unwrap();
+        let optional = |n: Option<u64>| n.map(|n| n.to_string()).unwrap_or_default();
+        writeln!(out, "{},{},{},{},{},{},{}",
+                 run.density,
+                 run.rule,
+                 run.seed,
+                 sweep.generations,
+                 metrics.population,
+                 optional(metrics.stabilized_at),
+                 optional(metrics.period))?;
+    }
+    out.flush()
+}
+
+
+fn simulate(board: &Universe, run: &Run, generations: u64) -> Metrics {
+    let mut game = board.clone();
+    game.rule = run.rule;
+    game.randomize(run.density, &mut Rng::new(run.seed));
+
+    // Generation each board was first seen at, to notice when it repeats.
+    let mut seen: HashMap<u64, u64> = HashMap::new();
+    let mut stabilized_at = None;
+    let mut period = None;
+    for generation in 0..=generations {
+        let mut hasher = DefaultHasher::new();
+        game.cells.hash(&mut hasher);
+        if let Some(first) = seen.insert(hasher.finish(), generation) {
+            stabilized_at = Some(first);
+            period = Some(generation - first);
+            break;
+        }
+        if generation < generations {game.tick();}
+    }
+
+    // The board is back in the state of `start`, so the rest of the run
+    // only goes round the cycle; step to the phase of the last generation.
+    if let (Some(start), Some(length)) = (stabilized_at, period) {
+        for _ in 0..(generations - start) % length {game.tick();}
+    }
+    Metrics { population: game.live_cells().len(), stabilized_at, period }
+}
@@ -0,0 +1,72 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::background::Progress;
+use crate::Universe;
+
+
+const HTML_TEMPLATE: &str = include_str!("player.html");
+
+
+/// Generations captured while the simulation runs, for exporting.
+pub struct Recording {
+    width: usize,
+    height: usize,
+    frames: Vec<Vec<bool>>,
+}
+
+
+impl Recording {
+    /// Starts a recording with the current board as its first frame.
+    pub fn start(universe: &Universe) -> Recording {
+        let mut recording = Recording {
+            width: universe.width,
+            height: universe.height,
+            frames: Vec::new(),
+        };
+        recording.capture(universe);
+        recording
+    }
+
+    pub fn capture(&mut self, universe: &Universe) {
+        if (universe.width, universe.height) == (self.width, self.height) {
+            self.frames.push(universe.cells.clone());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Writes a standalone web page that replays the recording, counting
+    /// the frames written in `progress`.
+    pub fn save_html(&self, path: &str, progress: &Progress) -> io::Result<()> {
+        let page = HTML_TEMPLATE
+            .replace("{{WIDTH}}", &self.width.to_string())
+            .replace("{{HEIGHT}}", &self.height.to_string());
+        let (head, tail) = page.split_once("{{FRAMES}}").unwrap();
+
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(head.as_bytes())?;
+        for (i, frame) in self.frames.iter().enumerate() {
+            if i > 0 {out.write_all(b",\n")?;}
+            write!(out, "\"{}\"", encode_frame(frame))?;
+            progress.add(1);
+        }
+        out.write_all(tail.as_bytes())?;
+        out.flush()
+    }
+}
+
+
+/// Packs cells four to a hex digit, most significant bit first.
+fn encode_frame(cells: &[bool]) -> String {
+    cells.chunks(4)
+        .map(|chunk| {
+            let nibble = chunk.iter()
+                .enumerate()
+                .fold(0, |acc, (i, &alive)| acc | ((alive as u32) << (3 - i)));
+            std::char::from_digit(nibble, 16).unwrap()
+        })
+        .collect()
+}
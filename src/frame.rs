@@ -0,0 +1,68 @@
+use std::io::Write;
+
+
+/// The lines last drawn on the terminal, so that the next frame only
+/// rewrites the lines that changed instead of clearing the screen. Over a
+/// slow connection a generation on a large board then costs the rows that
+/// changed rather than the whole screen, and nothing flickers.
+pub struct Frame {
+    lines: Vec<String>,
+    /// The terminal size the lines were drawn for.
+    size: Option<(u16, u16)>,
+}
+
+
+impl Frame {
+    pub fn new() -> Frame {
+        Frame { lines: Vec::new(), size: None }
+    }
+
+    /// Draws `text`, the whole screen from the top left with lines ended
+    /// by `\n\r`. The screen is cleared and drawn in full the first time,
+    /// after `size` changed and when the lines do not fit the terminal, as
+    /// wrapped lines and scrolling would move the ones below.
+    pub fn draw(&mut self, out: &mut dyn Write, text: &str, size: Option<(u16, u16)>) {
+        let lines: Vec<String> = text.split("\n\r").map(str::to_string).collect();
+        let overflows = size.is_some_and(|(cols, rows)| {
+            lines.len() > rows as usize || lines.iter().any(|line| display_width(line) > cols as usize)
+        });
+        if self.lines.is_empty() || size != self.size || overflows {
+            write!(out, "{}{}{}{}", termion::cursor::Goto(1, 1), termion::clear::All, termion::cursor::Hide, text).unwrap();
+        } else {
+            for (i, line) in lines.iter().enumerate() {
+                if self.lines.get(i) == Some(line) {continue;}
+                write!(out, "{}{}{}{}{}", termion::cursor::Goto(1, i as u16 + 1), termion::style::Reset,
+                       line, termion::style::Reset, termion::clear::UntilNewline).unwrap();
+            }
+            if lines.len() < self.lines.len() {
+                write!(out, "{}{}", termion::cursor::Goto(1, lines.len() as u16 + 1), termion::clear::AfterCursor).unwrap();
+            }
+        }
+        // What scrolled off is not where the lines say, the next frame is
+        // drawn in full again.
+        self.lines = if overflows {Vec::new()} else {lines};
+        self.size = size;
+    }
+}
+
+
+/// The number of columns `line` takes up, leaving out escape sequences.
+fn display_width(line: &str) -> usize {
+    let mut width = 0;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // Control sequences end with a letter, `@` or `~`.
+            '\x1b' => {
+                if chars.next() == Some('[') {
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {break;}
+                    }
+                }
+            }
+            '\r' | '\n' => {}
+            _ => width += 1,
+        }
+    }
+    width
+}
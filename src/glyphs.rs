@@ -0,0 +1,128 @@
+/// The characters cells are drawn with, all as wide: two columns per
+/// cell so cells come out roughly square, or one to fit more of the board
+/// on a narrow terminal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Glyphs {
+    pub name: String,
+    pub alive: String,
+    pub dead: String,
+    pub selected_alive: String,
+    pub selected_dead: String,
+    pub wall: String,
+}
+
+
+/// The built-in sets: the name, then the glyphs for live, dead, selected
+/// live and selected dead cells and walls.
+const GLYPH_SETS: [[&str; 6]; 3] = [
+    ["blocks", "██", "  ", "▒▒", "░░", "▓▓"],
+    ["dots", "● ", "· ", "◉ ", "○ ", "■ "],
+    ["ascii", "##", ". ", "[]", "<>", "XX"],
+];
+
+
+impl Glyphs {
+    fn new(name: &str, [alive, dead, selected_alive, selected_dead, wall]: [&str; 5]) -> Glyphs {
+        Glyphs {
+            name: name.to_string(),
+            alive: alive.to_string(),
+            dead: dead.to_string(),
+            selected_alive: selected_alive.to_string(),
+            selected_dead: selected_dead.to_string(),
+            wall: wall.to_string(),
+        }
+    }
+
+    /// The built-in set named `text`, or a set given as five glyphs
+    /// separated by commas, in the order of `GLYPH_SETS`, e.g. `#,.,@,_,X`.
+    /// Such a set is named by the text, so that it can be read back. The
+    /// glyphs must all be one or all two characters, each a column wide.
+    pub fn parse(text: &str) -> Result<Glyphs, String> {
+        if let Some(glyphs) = builtin().into_iter().find(|g| g.name == text) {return Ok(glyphs);}
+        let Ok(glyphs) = <[&str; 5]>::try_from(text.split(',').collect::<Vec<&str>>()) else {
+            let names: Vec<String> = builtin().into_iter().map(|g| g.name).collect();
+            return Err(format!("unknown glyphs `{}`, expected {} or five glyphs separated by commas",
+                               text, names.join(", ")));
+        };
+        let width = glyphs[0].chars().count();
+        if !(1..=2).contains(&width) || glyphs.iter().any(|g| g.chars().count() != width) {
+            return Err(format!("the glyphs of `{}` must all be one or all two characters", text));
+        }
+        Ok(Glyphs::new(text, glyphs))
+    }
+
+    /// The built-in set `by` places further along `GLYPH_SETS`, wrapping
+    /// around; from a set of the user's the first is one along.
+    pub fn cycle(&self, by: isize) -> Glyphs {
+        let sets = builtin();
+        let at = sets.iter().position(|g| g == self).unwrap_or(0) as isize;
+        sets[(at + by).rem_euclid(sets.len() as isize) as usize].clone()
+    }
+
+    /// How many terminal columns a cell takes up.
+    pub fn width(&self) -> usize {
+        self.alive.chars().count()
+    }
+
+    /// Whether every glyph of the set is ASCII, for `--ascii`.
+    pub fn is_ascii(&self) -> bool {
+        [&self.alive, &self.dead, &self.selected_alive, &self.selected_dead, &self.wall].iter().all(|g| g.is_ascii())
+    }
+}
+
+
+impl Default for Glyphs {
+    fn default() -> Glyphs {
+        builtin().swap_remove(0)
+    }
+}
+
+
+fn builtin() -> Vec<Glyphs> {
+    GLYPH_SETS.iter().map(|[name, glyphs @ ..]| Glyphs::new(name, *glyphs)).collect()
+}
+
+
+/// The characters the border around a pane is drawn with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Borders {
+    /// Top left, top right, bottom right and bottom left.
+    pub corners: [char; 4],
+    pub horizontal: char,
+    pub vertical: char,
+}
+
+
+/// Around the focused pane, or the only one.
+pub const DOUBLE: Borders = Borders { corners: ['╔', '╗', '╝', '╚'], horizontal: '═', vertical: '║' };
+/// Around the other pane of a split screen.
+pub const SINGLE: Borders = Borders { corners: ['┌', '┐', '┘', '└'], horizontal: '─', vertical: '│' };
+/// What `--ascii` draws instead of the two.
+pub const ASCII: Borders = Borders { corners: ['+', '+', '+', '+'], horizontal: '-', vertical: '|' };
+pub const ASCII_DOTTED: Borders = Borders { corners: ['.', '.', '\'', '\''], horizontal: '.', vertical: ':' };
+
+
+impl Borders {
+    /// Borders written as six characters, the corners clockwise from the
+    /// top left and then the horizontal and vertical lines, e.g. `++++-|`,
+    /// for the focused pane and the other alike; or as twelve, the second
+    /// six for the other pane.
+    pub fn parse(text: &str) -> Result<(Borders, Borders), String> {
+        let chars: Vec<char> = text.chars().collect();
+        let borders = |c: &[char]| Borders { corners: [c[0], c[1], c[2], c[3]], horizontal: c[4], vertical: c[5] };
+        match chars.len() {
+            6 => Ok((borders(&chars), borders(&chars))),
+            12 => Ok((borders(&chars[..6]), borders(&chars[6..]))),
+            _ => Err(format!("borders `{}` must be six characters, or twelve with the other pane's", text)),
+        }
+    }
+
+    /// The characters in the order `parse` reads them.
+    pub fn spec(self) -> String {
+        self.corners.iter().chain([&self.horizontal, &self.vertical]).collect()
+    }
+
+    pub fn is_ascii(self) -> bool {
+        self.spec().is_ascii()
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+use crate::rule::Rule;
+use crate::sparse::Cell;
+
+
+/// Nodes are kept until this many exist, then the cache starts over
+/// before the next jump.
+const NODES_MAX: usize = 1 << 21;
+
+
+type Id = u32;
+
+const DEAD: Id = 0;
+const ALIVE: Id = 1;
+
+
+/// A square of 2^level by 2^level cells, made of four squares half its
+/// size. Cells are the squares of level 0.
+#[derive(Clone, Copy)]
+struct Node {
+    nw: Id,
+    ne: Id,
+    sw: Id,
+    se: Id,
+    level: u8,
+    population: u64,
+}
+
+
+/// Gosper's HashLife: the plane is a quadtree in which equal squares are
+/// the same node, and the centre of every node some generations later is
+/// worked out once and remembered. Patterns that repeat in space or time,
+/// like a gun and the stream of gliders it fires, then run millions of
+/// generations in a moment.
+///
+/// Single steps run on the board, cells that leave it dropped when it is
+/// read back. Jumps run on the whole unbounded plane.
+pub struct HashLife {
+    pub rule: Rule,
+    nodes: Vec<Node>,
+    ids: HashMap<[Id; 4], Id>,
+    /// The centre of a node 2^j generations on, by node and j.
+    results: HashMap<(Id, u8), Id>,
+    /// The empty node of each level.
+    empty: Vec<Id>,
+}
+
+
+impl HashLife {
+    pub fn new(rule: Rule) -> HashLife {
+        let mut life = HashLife { rule, nodes: Vec::new(), ids: HashMap::new(), results: HashMap::new(), empty: Vec::new() };
+        life.reset();
+        life
+    }
+
+    fn reset(&mut self) {
+        let cell = |population| Node { nw: DEAD, ne: DEAD, sw: DEAD, se: DEAD, level: 0, population };
+        self.nodes = vec![cell(0), cell(1)];
+        self.ids.clear();
+        self.results.clear();
+        self.empty = vec![DEAD];
+    }
+
+    /// The next `generations` of a `width` by `height` board stored row by
+    /// row, cells that end up off the board left out.
+    pub fn advance(&mut self, cells: &[bool], width: usize, height: usize, generations: u64) -> Vec<bool> {
+        if self.nodes.len() > NODES_MAX {self.reset();}
+        // The board is placed with its top left corner at the origin of
+        // the plane, the root square is centred on the origin.
+        let side = width.max(height).next_power_of_two().max(4);
+        let level = side.trailing_zeros() as u8 + 1;
+        let half = 1i64 << (level - 1);
+        let mut root = self.build(cells, width, height, level, -half, -half);
+        for j in (0..64).rev().filter(|j| generations >> j & 1 == 1) {
+            // The pattern must keep to the centre of the root for the
+            // whole jump: make room for it to spread by 2^j cells.
+            while self.node(root).level < j + 2 || !self.is_centred(root) {root = self.expand(root);}
+            root = self.expand(root);
+            root = self.step(root, j);
+        }
+        let mut next = vec![false; width * height];
+        let half = 1i64 << (self.node(root).level - 1);
+        self.read(root, -half, -half, width, height, &mut next);
+        next
+    }
+
+    /// The live cells of the plane `generations` on, from the live cells
+    /// `live`, wherever they are.
+    pub fn advance_plane(&mut self, live: &[Cell], generations: u64) -> Vec<Cell> {
+        if self.nodes.len() > NODES_MAX {self.reset();}
+        let cells: Vec<(i64, i64)> = live.iter().map(|&(row, col)| (col as i64, row as i64)).collect();
+        // The root square is centred on the origin and holds every cell.
+        let reach = cells.iter().map(|&(x, y)| x.abs().max(y.abs()) + 1).max().unwrap_or(0);
+        let level = (reach as u64).next_power_of_two().max(2).trailing_zeros() as u8 + 1;
+        let half = 1i64 << (level - 1);
+        let mut root = self.build_sparse(cells, level, -half, -half);
+        for j in (0..64).rev().filter(|j| generations >> j & 1 == 1) {
+            while self.node(root).level < j + 2 || !self.is_centred(root) {root = self.expand(root);}
+            root = self.expand(root);
+            root = self.step(root, j);
+        }
+        let mut next = Vec::new();
+        let half = 1i64 << (self.node(root).level - 1);
+        self.collect(root, -half, -half, &mut next);
+        next.into_iter().map(|(x, y)| (y as isize, x as isize)).collect()
+    }
+
+    fn node(&self, id: Id) -> Node {
+        self.nodes[id as usize]
+    }
+
+    fn join(&mut self, nw: Id, ne: Id, sw: Id, se: Id) -> Id {
+        if let Some(&id) = self.ids.get(&[nw, ne, sw, se]) {return id;}
+        let level = self.node(nw).level + 1;
+        let population = [nw, ne, sw, se].iter().map(|&c| self.node(c).population).fold(0, u64::saturating_add);
+        let id = self.nodes.len() as Id;
+        self.nodes.push(Node { nw, ne, sw, se, level, population });
+        self.ids.insert([nw, ne, sw, se], id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> Id {
+        while self.empty.len() <= level as usize {
+            let e = *self.empty.last().unwrap();
+            let id = self.join(e, e, e, e);
+            self.empty.push(id);
+        }
+        self.empty[level as usize]
+    }
+
+    /// The node of `level` whose top left cell is at `x`, `y` on the
+    /// plane.
+    fn build(&mut self, cells: &[bool], width: usize, height: usize, level: u8, x: i64, y: i64) -> Id {
+        let size = 1i64 << level;
+        if x + size <= 0 || y + size <= 0 || x >= width as i64 || y >= height as i64 {
+            return self.empty(level);
+        }
+        if level == 0 {
+            return if cells[y as usize * width + x as usize] {ALIVE} else {DEAD};
+        }
+        let h = size / 2;
+        let nw = self.build(cells, width, height, level - 1, x, y);
+        let ne = self.build(cells, width, height, level - 1, x + h, y);
+        let sw = self.build(cells, width, height, level - 1, x, y + h);
+        let se = self.build(cells, width, height, level - 1, x + h, y + h);
+        self.join(nw, ne, sw, se)
+    }
+
+    /// The node of `level` whose top left cell is at `x`, `y` on the
+    /// plane, from live cells all within it.
+    fn build_sparse(&mut self, cells: Vec<(i64, i64)>, level: u8, x: i64, y: i64) -> Id {
+        if cells.is_empty() {return self.empty(level);}
+        if level == 0 {return ALIVE;}
+        let h = 1i64 << (level - 1);
+        let mut quarters: [Vec<(i64, i64)>; 4] = Default::default();
+        for (cx, cy) in cells {
+            quarters[(cx >= x + h) as usize + 2 * (cy >= y + h) as usize].push((cx, cy));
+        }
+        let [nw, ne, sw, se] = quarters;
+        let nw = self.build_sparse(nw, level - 1, x, y);
+        let ne = self.build_sparse(ne, level - 1, x + h, y);
+        let sw = self.build_sparse(sw, level - 1, x, y + h);
+        let se = self.build_sparse(se, level - 1, x + h, y + h);
+        self.join(nw, ne, sw, se)
+    }
+
+    /// Adds the live cells of `id`, with its top left cell at `x`, `y`, to
+    /// `out`.
+    fn collect(&self, id: Id, x: i64, y: i64, out: &mut Vec<(i64, i64)>) {
+        let node = self.node(id);
+        if node.population == 0 {return;}
+        if node.level == 0 {
+            out.push((x, y));
+            return;
+        }
+        let h = 1i64 << (node.level - 1);
+        self.collect(node.nw, x, y, out);
+        self.collect(node.ne, x + h, y, out);
+        self.collect(node.sw, x, y + h, out);
+        self.collect(node.se, x + h, y + h, out);
+    }
+
+    /// Copies the live cells of `id`, with its top left cell at `x`, `y`,
+    /// that are on the board into `out`.
+    fn read(&self, id: Id, x: i64, y: i64, width: usize, height: usize, out: &mut [bool]) {
+        let node = self.node(id);
+        let size = 1i64 << node.level;
+        if node.population == 0 || x + size <= 0 || y + size <= 0 || x >= width as i64 || y >= height as i64 {
+            return;
+        }
+        if node.level == 0 {
+            out[y as usize * width + x as usize] = true;
+            return;
+        }
+        let h = size / 2;
+        self.read(node.nw, x, y, width, height, out);
+        self.read(node.ne, x + h, y, width, height, out);
+        self.read(node.sw, x, y + h, width, height, out);
+        self.read(node.se, x + h, y + h, width, height, out);
+    }
+
+    /// A node twice the size with `id` in its centre.
+    fn expand(&mut self, id: Id) -> Id {
+        let n = self.node(id);
+        let e = self.empty(n.level - 1);
+        let nw = self.join(e, e, e, n.nw);
+        let ne = self.join(e, e, n.ne, e);
+        let sw = self.join(e, n.sw, e, e);
+        let se = self.join(n.se, e, e, e);
+        self.join(nw, ne, sw, se)
+    }
+
+    /// Whether all live cells are in the centre half of the node.
+    fn is_centred(&self, id: Id) -> bool {
+        let n = self.node(id);
+        let [nw, ne, sw, se] = [n.nw, n.ne, n.sw, n.se].map(|c| self.node(c));
+        [nw.nw, nw.ne, nw.sw, ne.nw, ne.ne, ne.se, sw.nw, sw.sw, sw.se, se.ne, se.sw, se.se]
+            .iter()
+            .all(|&c| self.node(c).population == 0)
+    }
+
+    /// The centre half of a node.
+    fn centre(&mut self, id: Id) -> Id {
+        let n = self.node(id);
+        let (nw, ne, sw, se) = (self.node(n.nw), self.node(n.ne), self.node(n.sw), self.node(n.se));
+        self.join(nw.se, ne.sw, sw.ne, se.nw)
+    }
+
+    /// The centre half of a node of level `k` 2^j generations on, for j
+    /// up to k - 2.
+    fn step(&mut self, id: Id, j: u8) -> Id {
+        let n = self.node(id);
+        if n.population == 0 {return self.empty(n.level - 1);}
+        if let Some(&result) = self.results.get(&(id, j)) {return result;}
+        let result = if n.level == 2 {
+            self.step_cells(n)
+        } else {
+            let (nw, ne, sw, se) = (self.node(n.nw), self.node(n.ne), self.node(n.sw), self.node(n.se));
+            // Nine overlapping squares half the size of the node.
+            let squares = [
+                n.nw, self.join(nw.ne, ne.nw, nw.se, ne.sw), n.ne,
+                self.join(nw.sw, nw.se, sw.nw, sw.ne), self.centre(id), self.join(ne.sw, ne.se, se.nw, se.ne),
+                n.sw, self.join(sw.ne, se.nw, sw.se, se.sw), n.se,
+            ];
+            // At full speed both halves of the time are spent stepping,
+            // otherwise the first half only takes the centres.
+            let full = j == n.level - 2;
+            let mut inner = [DEAD; 9];
+            for (i, &square) in squares.iter().enumerate() {
+                inner[i] = if full {self.step(square, j - 1)} else {self.centre(square)};
+            }
+            let next = if full {j - 1} else {j};
+            let quarters = [(0, 1, 3, 4), (1, 2, 4, 5), (3, 4, 6, 7), (4, 5, 7, 8)].map(|(a, b, c, d)| {
+                self.join(inner[a], inner[b], inner[c], inner[d])
+            });
+            let [nw, ne, sw, se] = quarters.map(|q| self.step(q, next));
+            self.join(nw, ne, sw, se)
+        };
+        self.results.insert((id, j), result);
+        result
+    }
+
+    /// The centre 2x2 cells of a 4x4 node one generation on.
+    fn step_cells(&mut self, n: Node) -> Id {
+        let mut alive = [[false; 4]; 4];
+        for (quarter, (row, col)) in [(n.nw, (0, 0)), (n.ne, (0, 2)), (n.sw, (2, 0)), (n.se, (2, 2))] {
+            let q = self.node(quarter);
+            for (cell, (r, c)) in [(q.nw, (0, 0)), (q.ne, (0, 1)), (q.sw, (1, 0)), (q.se, (1, 1))] {
+                alive[row + r][col + c] = cell == ALIVE;
+            }
+        }
+        let next = |row: usize, col: usize| {
+            let neighbours = (row - 1..=row + 1)
+                .flat_map(|r| (col - 1..=col + 1).map(move |c| (r, c)))
+                .filter(|&(r, c)| (r, c) != (row, col) && alive[r][c])
+                .count();
+            if self.rule.next(alive[row][col], neighbours) {ALIVE} else {DEAD}
+        };
+        let cells = [next(1, 1), next(1, 2), next(2, 1), next(2, 2)];
+        self.join(cells[0], cells[1], cells[2], cells[3])
+    }
+}
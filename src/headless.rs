@@ -0,0 +1,127 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::checkpoint::{Checkpoint, Checkpointer};
+use crate::session::JUMP_MAX;
+use crate::state;
+use crate::{take_option, Engine, Topology};
+
+
+const GENERATIONS_DEFAULT: u64 = 1000;
+
+
+/// What the board is written as at the end of a run.
+#[derive(Clone, Copy)]
+enum Format {
+    Rle,
+    Cells,
+    /// The whole state, as `:save` writes it.
+    Json,
+}
+
+
+impl Format {
+    const ALL: [Format; 3] = [Format::Rle, Format::Cells, Format::Json];
+
+    fn name(self) -> &'static str {
+        match self {
+            Format::Rle => "rle",
+            Format::Cells => "cells",
+            Format::Json => "json",
+        }
+    }
+
+    fn from_name(name: &str) -> Result<Format, String> {
+        Format::ALL.into_iter().find(|f| f.name() == name)
+            .ok_or(format!("unknown format `{}`, expected rle, cells or json", name))
+    }
+
+    /// The format of the file at `path`, by its extension.
+    fn of_path(path: &str) -> Result<Format, String> {
+        let extension = Path::new(path).extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        Format::ALL.into_iter().find(|f| f.name() == extension)
+            .ok_or(format!("cannot tell the format of {}: expected a .rle, .cells or .json file, or --format", path))
+    }
+}
+
+
+/// A simulation run by `game-of-life run`, without the terminal.
+pub struct Run {
+    generations: u64,
+    /// The file to write the board to, `-` for standard output.
+    out: String,
+    format: Format,
+}
+
+
+impl Run {
+    /// Takes `--generations`, `--out` and `--format` from `args`. Without
+    /// `--format` files are written in the format of their extension and
+    /// standard output gets RLE.
+    pub fn from_args(args: &mut Vec<String>) -> Result<Run, String> {
+        let generations = match take_option(args, "--generations").map(|n| (n.parse(), n)) {
+            Some((Ok(n @ 1..=JUMP_MAX), _)) => n,
+            Some((_, n)) => return Err(format!("`{}` is not a number of generations from 1 to {}", n, JUMP_MAX)),
+            None => GENERATIONS_DEFAULT,
+        };
+        let out = take_option(args, "--out").ok_or("run needs --out <file> to write the board to, or --out -")?;
+        let format = match take_option(args, "--format") {
+            Some(name) => Format::from_name(&name)?,
+            None if out == "-" => Format::Rle,
+            None => Format::of_path(&out)?,
+        };
+        Ok(Run { generations, out, format })
+    }
+
+    /// Runs the board of `start` the run's generations on, or until
+    /// `max_run` is up, and writes the board where it stopped. Saved states
+    /// get `tick_millis`. The `checkpointer` saves the run as it goes and
+    /// where it stopped, for `--resume` to carry on from.
+    pub fn run(&self, start: Checkpoint, checkpointer: Option<Checkpointer>, tick_millis: u64, max_run: Option<Duration>)
+        -> Result<(), String> {
+        let Checkpoint { mut game, rng } = start;
+        // Nothing steps back through a headless run.
+        let history_len = game.history.len_max.replace(0);
+        let started = Instant::now();
+        let end = game.generation.checked_add(self.generations)
+            .ok_or(format!("cannot run {} generations on from generation {}", self.generations, game.generation))?;
+        // Jumps run the unbounded plane; other boards step to stay exact.
+        let jumped = game.engine == Engine::HashLife && game.topology == Topology::Unbounded
+            && game.jump(self.generations).is_ok();
+        while !jumped && game.generation < end {
+            if max_run.is_some_and(|max| started.elapsed() >= max) {
+                eprintln!("warning: stopped at generation {} after --max-seconds", game.generation);
+                break;
+            }
+            game.tick();
+            if let Some(c) = &checkpointer {
+                if let Err(e) = c.after_tick(&game, &rng) {eprintln!("{}", e);}
+            }
+        }
+        game.history.len_max = history_len;
+        if let Some(c) = &checkpointer {
+            if let Err(e) = c.save(&game, &rng) {eprintln!("{}", e);}
+        }
+
+        let written = if self.out == "-" {
+            let text = match self.format {
+                Format::Rle => game.to_rle(),
+                Format::Cells => game.to_pattern().to_plaintext(None),
+                Format::Json => state::to_json(&game, tick_millis),
+            };
+            io::stdout().write_all(text.as_bytes()).and_then(|_| io::stdout().flush())
+        } else {
+            match self.format {
+                Format::Rle => fs::write(&self.out, game.to_rle()),
+                Format::Cells => fs::write(&self.out, game.to_pattern().to_plaintext(None)),
+                Format::Json => state::save(&self.out, &game, tick_millis),
+            }
+        };
+        written.map_err(|e| format!("cannot write {}: {}", self.out, e))
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::{HashSet, VecDeque};
+use std::mem;
+
+use crate::sparse;
+
+
+/// How much memory the history of a board takes at most unless told
+/// otherwise, in bytes.
+pub const BUDGET_DEFAULT: usize = 64 << 20;
+
+
+/// What changed from one generation to the next, or back.
+#[derive(Clone)]
+struct Step {
+    /// The cells that were born or died, by index, ascending.
+    flipped: Vec<u32>,
+    /// The paint of the cells whose paint changed, in the other
+    /// generation.
+    colors: Vec<(u32, Option<u8>)>,
+    /// The live cells beyond the board's edges in the other generation.
+    beyond: HashSet<sparse::Cell>,
+}
+
+
+impl Step {
+    /// Roughly how much memory the step takes.
+    fn bytes(&self) -> usize {
+        mem::size_of::<Step>()
+            + self.flipped.len() * mem::size_of::<u32>()
+            + self.colors.len() * mem::size_of::<(u32, Option<u8>)>()
+            + self.beyond.len() * mem::size_of::<sparse::Cell>()
+    }
+
+    /// Turns the board into the other generation and the step into the
+    /// one back.
+    fn apply(&mut self, cells: &mut [bool], colors: &mut [Option<u8>], beyond: &mut HashSet<sparse::Cell>) {
+        for &i in &self.flipped {cells[i as usize] = !cells[i as usize];}
+        for (i, color) in &mut self.colors {mem::swap(&mut colors[*i as usize], color);}
+        mem::swap(&mut self.beyond, beyond);
+    }
+}
+
+
+/// The generations before the current one, to step back through, kept as
+/// the cells that changed between them rather than whole boards. The
+/// oldest are forgotten once the steps take more than `budget` bytes or
+/// there are more than `len_max` of them. The generations stepped back
+/// from are kept to step forward through again, until the board moves on.
+#[derive(Clone)]
+pub struct History {
+    steps: VecDeque<Step>,
+    /// From the generation one later onwards, the latest last.
+    later: Vec<Step>,
+    /// The whole board one generation back, to compare the current one
+    /// with.
+    previous: Option<Vec<bool>>,
+    bytes: usize,
+    pub budget: usize,
+    pub len_max: Option<usize>,
+}
+
+
+impl History {
+    pub fn new() -> History {
+        History { steps: VecDeque::new(), later: Vec::new(), previous: None, bytes: 0, budget: BUDGET_DEFAULT, len_max: None }
+    }
+
+    /// How many generations back can be stepped.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// How many generations forward can be stepped again.
+    pub fn later_len(&self) -> usize {
+        self.later.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.steps.clear();
+        self.later.clear();
+        self.previous = None;
+        self.bytes = 0;
+    }
+
+    /// The board one generation back, if it is kept.
+    pub fn previous(&self) -> Option<&[bool]> {
+        self.previous.as_deref()
+    }
+
+    /// The paint the cell at `ind` had one generation back, given the
+    /// current `colors`.
+    pub fn previous_color(&self, colors: &[Option<u8>], ind: usize) -> Option<u8> {
+        let changed = &self.steps.back()?.colors;
+        match changed.binary_search_by_key(&(ind as u32), |&(i, _)| i) {
+            Ok(at) => changed[at].1,
+            Err(_) => colors[ind],
+        }
+    }
+
+    /// Remembers the generation `previous`, with its paint and the live
+    /// cells beyond the edges, as the one before `cells`. The generations
+    /// stepped back from are forgotten.
+    pub fn push(&mut self, previous: Vec<bool>, cells: &[bool], previous_colors: &[Option<u8>], colors: &[Option<u8>],
+                beyond: HashSet<sparse::Cell>) {
+        let flipped = (0..cells.len()).filter(|&i| previous[i] != cells[i]).map(|i| i as u32).collect();
+        let colors = (0..colors.len())
+            .filter(|&i| previous_colors[i] != colors[i])
+            .map(|i| (i as u32, previous_colors[i]))
+            .collect();
+        let step = Step { flipped, colors, beyond };
+        self.clear_later();
+        self.bytes += step.bytes();
+        self.steps.push_back(step);
+        self.previous = Some(previous);
+        while self.bytes > self.budget || self.len_max.is_some_and(|max| self.steps.len() > max) {
+            let Some(oldest) = self.steps.pop_front() else {break};
+            self.bytes -= oldest.bytes();
+        }
+        if self.steps.is_empty() {self.previous = None;}
+    }
+
+    /// Forgets the generations stepped back from.
+    pub fn clear_later(&mut self) {
+        self.bytes -= self.later.drain(..).map(|step| step.bytes()).sum::<usize>();
+    }
+
+    /// Takes the board back one generation. Returns `false` when no
+    /// earlier generation is kept.
+    pub fn pop(&mut self, cells: &mut [bool], colors: &mut [Option<u8>], beyond: &mut HashSet<sparse::Cell>) -> bool {
+        let Some(mut step) = self.steps.pop_back() else {return false};
+        self.bytes -= step.bytes();
+        step.apply(cells, colors, beyond);
+        self.bytes += step.bytes();
+        self.later.push(step);
+        self.find_previous(cells);
+        true
+    }
+
+    /// Takes the board forward again to the generation it was stepped
+    /// back from. Returns `false` when there is none.
+    pub fn forward(&mut self, cells: &mut [bool], colors: &mut [Option<u8>], beyond: &mut HashSet<sparse::Cell>) -> bool {
+        let Some(mut step) = self.later.pop() else {return false};
+        self.bytes -= step.bytes();
+        step.apply(cells, colors, beyond);
+        self.bytes += step.bytes();
+        self.steps.push_back(step);
+        self.find_previous(cells);
+        true
+    }
+
+    fn find_previous(&mut self, cells: &[bool]) {
+        self.previous = self.steps.back().map(|earlier| {
+            let mut previous = cells.to_vec();
+            for &i in &earlier.flipped {previous[i as usize] = !previous[i as usize];}
+            previous
+        });
+    }
+
+    /// The boards of the latest `limit` generations kept, oldest first,
+    /// given the current `cells`.
+    pub fn boards(&self, cells: &[bool], limit: usize) -> Vec<Vec<bool>> {
+        let mut board = cells.to_vec();
+        let mut boards: Vec<Vec<bool>> = self.steps.iter().rev().take(limit).map(|step| {
+            for &i in &step.flipped {board[i as usize] = !board[i as usize];}
+            board.clone()
+        }).collect();
+        boards.reverse();
+        boards
+    }
+}
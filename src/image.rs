@@ -0,0 +1,445 @@
+use std::fs;
+
+use crate::pattern::Pattern;
+
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const JPEG_SIGNATURE: &[u8] = b"\xff\xd8";
+/// Larger images are refused before anything is allocated for them.
+const PIXELS_MAX: usize = 1 << 26;
+
+
+/// A greyscale picture used to seed a board.
+pub struct Image {
+    width: usize,
+    height: usize,
+    luma: Vec<u8>,
+}
+
+
+impl Image {
+    /// Loads a PNG or a netpbm (PBM/PGM/PPM) file.
+    pub fn load(path: &str) -> Result<Image, String> {
+        let data = fs::read(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        if data.starts_with(PNG_SIGNATURE) {
+            decode_png(&data)
+        } else if data.starts_with(b"P") {
+            decode_pnm(&data)
+        } else if data.starts_with(JPEG_SIGNATURE) {
+            Err("JPEG images are not supported, convert the image to PNG first".to_string())
+        } else {
+            Err(format!("{}: unknown image format, expected PNG or PBM/PGM/PPM", path))
+        }
+    }
+
+    /// Scales the image to `width` x `height` cells by averaging the pixels
+    /// under each cell; cells darker than `threshold` become alive, or
+    /// lighter ones when `invert` is set.
+    pub fn to_pattern(&self, width: usize, height: usize, threshold: u8, invert: bool) -> Pattern {
+        let mut cells = Vec::new();
+        for row in 0..height {
+            let (y0, y1) = span(row, height, self.height);
+            for col in 0..width {
+                let (x0, x1) = span(col, width, self.width);
+                let mut sum = 0;
+                for y in y0..y1 {
+                    for x in x0..x1 {
+                        sum += self.luma[y * self.width + x] as usize;
+                    }
+                }
+                let average = sum / ((y1 - y0) * (x1 - x0));
+                if (average < threshold as usize) != invert {
+                    cells.push((row, col));
+                }
+            }
+        }
+        Pattern { width, height, cells, rule: None }
+    }
+}
+
+
+/// The range of source pixels covered by cell `i` of `cells`, never empty.
+fn span(i: usize, cells: usize, pixels: usize) -> (usize, usize) {
+    let start = i * pixels / cells;
+    let end = ((i + 1) * pixels / cells).max(start + 1).min(pixels);
+    (start.min(end - 1), end)
+}
+
+
+fn luminance(r: u8, g: u8, b: u8) -> u8 {
+    ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8
+}
+
+
+/// Transparent pixels are treated as white paper.
+fn over_white(luma: u8, alpha: u8) -> u8 {
+    ((luma as u32 * alpha as u32 + 255 * (255 - alpha as u32)) / 255) as u8
+}
+
+
+fn decode_pnm(data: &[u8]) -> Result<Image, String> {
+    let mut pos = 0;
+    let mut token = || -> Result<String, String> {
+        loop {
+            match data.get(pos) {
+                Some(b'#') => {
+                    while data.get(pos).is_some_and(|&b| b != b'\n') {pos += 1;}
+                }
+                Some(b) if b.is_ascii_whitespace() => {pos += 1;}
+                Some(_) => break,
+                None => return Err("netpbm image is truncated".to_string()),
+            }
+        }
+        let start = pos;
+        while data.get(pos).is_some_and(|b| !b.is_ascii_whitespace()) {pos += 1;}
+        Ok(String::from_utf8_lossy(&data[start..pos]).into_owned())
+    };
+
+    let magic = token()?;
+    let number = |t: String| t.parse::<usize>().map_err(|_| format!("invalid number `{}` in netpbm header", t));
+    let width = number(token()?)?;
+    let height = number(token()?)?;
+    let max = if magic == "P1" || magic == "P4" {1} else {number(token()?)?.max(1)};
+    let pixels = pixel_count(width, height)?;
+
+    let mut luma = Vec::with_capacity(pixels);
+    match magic.as_str() {
+        "P1" | "P2" | "P3" => {
+            let channels = if magic == "P3" {3} else {1};
+            let mut values = Vec::with_capacity(pixels * channels);
+            while values.len() < pixels * channels {
+                let v = if magic == "P1" {
+                    // Plain PBM allows the digits to be written without spaces.
+                    let t = token()?;
+                    t.bytes().map(|b| match b {
+                        b'0' | b'1' => Ok((b - b'0') as usize),
+                        _ => Err(format!("invalid pixel `{}` in PBM image, expected 0 or 1", t)),
+                    }).collect::<Result<Vec<_>, _>>()?
+                } else {
+                    vec![number(token()?)?]
+                };
+                values.extend(v);
+            }
+            for px in values.chunks(channels).take(pixels) {
+                let scale = |v: usize| (v.min(max) * 255 / max) as u8;
+                luma.push(match magic.as_str() {
+                    "P1" => if px[0] == 1 {0} else {255},
+                    "P2" => scale(px[0]),
+                    _ => luminance(scale(px[0]), scale(px[1]), scale(px[2])),
+                });
+            }
+        }
+        "P4" | "P5" | "P6" => {
+            let body = &data[(pos + 1).min(data.len())..];
+            match magic.as_str() {
+                "P4" => {
+                    let stride = width.div_ceil(8);
+                    for y in 0..height {
+                        for x in 0..width {
+                            let byte = *body.get(y * stride + x / 8).ok_or("PBM image is truncated")?;
+                            luma.push(if byte >> (7 - x % 8) & 1 == 1 {0} else {255});
+                        }
+                    }
+                }
+                _ => {
+                    let channels = if magic == "P6" {3} else {1};
+                    let depth = if max > 255 {2} else {1};
+                    let sample = |i: usize| -> Result<u8, String> {
+                        let v = if depth == 2 {
+                            (*body.get(i * 2).ok_or("image is truncated")? as usize) << 8 |
+                                *body.get(i * 2 + 1).ok_or("image is truncated")? as usize
+                        } else {
+                            *body.get(i).ok_or("image is truncated")? as usize
+                        };
+                        Ok((v.min(max) * 255 / max) as u8)
+                    };
+                    for p in 0..pixels {
+                        luma.push(if channels == 3 {
+                            luminance(sample(p * 3)?, sample(p * 3 + 1)?, sample(p * 3 + 2)?)
+                        } else {
+                            sample(p)?
+                        });
+                    }
+                }
+            }
+        }
+        other => return Err(format!("unsupported netpbm type `{}`", other)),
+    }
+
+    if width == 0 || height == 0 {return Err("image is empty".to_string());}
+    Ok(Image { width, height, luma })
+}
+
+
+/// The number of pixels in a `width` by `height` image, if it is not too
+/// large to load.
+fn pixel_count(width: usize, height: usize) -> Result<usize, String> {
+    width.checked_mul(height).filter(|&pixels| pixels <= PIXELS_MAX)
+        .ok_or(format!("image is {}x{}, larger than {} pixels", width, height, PIXELS_MAX))
+}
+
+
+fn decode_png(data: &[u8]) -> Result<Image, String> {
+    let mut pos = PNG_SIGNATURE.len();
+    let mut header = None;
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let mut transparency: Vec<u8> = Vec::new();
+    let mut compressed = Vec::new();
+
+    while pos + 8 <= data.len() {
+        let len = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let body = data.get(pos + 8..pos + 8 + len).ok_or("PNG chunk is truncated")?;
+        match kind {
+            b"IHDR" => {
+                if body.len() < 13 {return Err("PNG header is truncated".to_string());}
+                let width = u32::from_be_bytes(body[0..4].try_into().unwrap()) as usize;
+                let height = u32::from_be_bytes(body[4..8].try_into().unwrap()) as usize;
+                if body[12] != 0 {return Err("interlaced PNG images are not supported".to_string());}
+                header = Some((width, height, body[8], body[9]));
+            }
+            b"PLTE" if body.len() % 3 != 0 => return Err("bad PLTE chunk".to_string()),
+            b"PLTE" => palette = body.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect(),
+            b"tRNS" => transparency = body.to_vec(),
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += 12 + len;
+    }
+
+    let (width, height, depth, color) = header.ok_or("PNG image has no header")?;
+    let channels = match color {
+        0 => 1,
+        2 => 3,
+        3 => 1,
+        4 => 2,
+        6 => 4,
+        _ => return Err(format!("unsupported PNG color type {}", color)),
+    };
+    if ![1, 2, 4, 8, 16].contains(&depth) {
+        return Err(format!("unsupported PNG bit depth {}", depth));
+    }
+    if width == 0 || height == 0 {return Err("image is empty".to_string());}
+    pixel_count(width, height)?;
+
+    // Skip the two byte zlib header; the checksum at the end is ignored.
+    let raw = inflate(compressed.get(2..).ok_or("PNG image data is truncated")?)?;
+    let bits_per_pixel = channels * depth as usize;
+    let stride = (width * bits_per_pixel).div_ceil(8);
+    let step = bits_per_pixel.div_ceil(8);
+    let pixels = unfilter(&raw, stride, height, step)?;
+
+    let max = (1u32 << depth.min(8)) - 1;
+    let mut luma = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let line = &pixels[y * stride..(y + 1) * stride];
+        let sample = |i: usize| -> u8 {
+            match depth {
+                16 => line[i * 2],
+                8 => line[i],
+                d => {
+                    let bit = i * d as usize;
+                    let v = (line[bit / 8] >> (8 - d as usize - bit % 8)) as u32 & max;
+                    if color == 3 {v as u8} else {(v * 255 / max) as u8}
+                }
+            }
+        };
+        for x in 0..width {
+            let i = x * channels;
+            luma.push(match color {
+                0 => sample(i),
+                2 => luminance(sample(i), sample(i + 1), sample(i + 2)),
+                3 => {
+                    let idx = sample(i) as usize;
+                    let [r, g, b] = *palette.get(idx).ok_or("PNG palette index out of range")?;
+                    over_white(luminance(r, g, b), *transparency.get(idx).unwrap_or(&255))
+                }
+                4 => over_white(sample(i), sample(i + 1)),
+                _ => over_white(luminance(sample(i), sample(i + 1), sample(i + 2)), sample(i + 3)),
+            });
+        }
+    }
+
+    Ok(Image { width, height, luma })
+}
+
+
+/// Reverses the per-scanline PNG filters.
+fn unfilter(raw: &[u8], stride: usize, height: usize, step: usize) -> Result<Vec<u8>, String> {
+    let mut out = vec![0u8; stride * height];
+    for y in 0..height {
+        let line = raw.get(y * (stride + 1)..(y + 1) * (stride + 1)).ok_or("PNG image data is truncated")?;
+        let (filter, line) = (line[0], &line[1..]);
+        for x in 0..stride {
+            let a = if x >= step {out[y * stride + x - step]} else {0};
+            let b = if y > 0 {out[(y - 1) * stride + x]} else {0};
+            let c = if x >= step && y > 0 {out[(y - 1) * stride + x - step]} else {0};
+            let predictor = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err(format!("invalid PNG filter {}", filter)),
+            };
+            out[y * stride + x] = line[x].wrapping_add(predictor);
+        }
+    }
+    Ok(out)
+}
+
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {a} else if pb <= pc {b} else {c}
+}
+
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+                                35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+                                3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+                              257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+                              8193, 12289, 16385, 24577];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+                              7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u32,
+}
+
+
+impl Bits<'_> {
+    fn read(&mut self, count: u32) -> Result<u32, String> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = *self.data.get(self.pos).ok_or("compressed PNG data is truncated")?;
+            value |= ((byte >> self.bit) as u32 & 1) << i;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+        Ok(value)
+    }
+}
+
+
+/// A canonical Huffman code stored as the number of codes of each length
+/// and the symbols ordered by code.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; 16];
+        for &l in lengths {counts[l as usize] += 1;}
+        counts[0] = 0;
+        let mut symbols: Vec<u16> = (0..lengths.len() as u16).filter(|&s| lengths[s as usize] != 0).collect();
+        symbols.sort_by_key(|&s| lengths[s as usize]);
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= bits.read(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code in PNG data".to_string())
+    }
+}
+
+
+/// Decompresses raw DEFLATE data (RFC 1951).
+fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut bits = Bits { data, pos: 0, bit: 0 };
+    let mut out = Vec::new();
+
+    loop {
+        let last = bits.read(1)? == 1;
+        match bits.read(2)? {
+            0 => {
+                if bits.bit != 0 {
+                    bits.bit = 0;
+                    bits.pos += 1;
+                }
+                let header = data.get(bits.pos..bits.pos + 4).ok_or("compressed PNG data is truncated")?;
+                let len = u16::from_le_bytes([header[0], header[1]]) as usize;
+                let block = data.get(bits.pos + 4..bits.pos + 4 + len).ok_or("compressed PNG data is truncated")?;
+                out.extend_from_slice(block);
+                bits.pos += 4 + len;
+            }
+            1 => {
+                let mut lengths = [8u8; 288];
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                inflate_block(&mut bits, &mut out, &Huffman::new(&lengths), &Huffman::new(&[5; 30]))?;
+            }
+            2 => {
+                let literals = bits.read(5)? as usize + 257;
+                let distances = bits.read(5)? as usize + 1;
+                let code_lengths = bits.read(4)? as usize + 4;
+                let mut lengths = [0u8; 19];
+                for &i in CODE_LENGTH_ORDER.iter().take(code_lengths) {
+                    lengths[i] = bits.read(3)? as u8;
+                }
+                let code = Huffman::new(&lengths);
+
+                let mut lengths = Vec::with_capacity(literals + distances);
+                while lengths.len() < literals + distances {
+                    let (value, repeat) = match code.decode(&mut bits)? {
+                        s @ 0..=15 => (s as u8, 1),
+                        16 => (*lengths.last().ok_or("invalid PNG code lengths")?, 3 + bits.read(2)?),
+                        17 => (0, 3 + bits.read(3)?),
+                        _ => (0, 11 + bits.read(7)?),
+                    };
+                    lengths.extend(std::iter::repeat_n(value, repeat as usize));
+                }
+                let (lit, dist) = lengths.split_at(literals);
+                inflate_block(&mut bits, &mut out, &Huffman::new(lit), &Huffman::new(dist))?;
+            }
+            _ => return Err("invalid block type in PNG data".to_string()),
+        }
+        if last {return Ok(out);}
+    }
+}
+
+
+fn inflate_block(bits: &mut Bits, out: &mut Vec<u8>, literals: &Huffman, distances: &Huffman) -> Result<(), String> {
+    loop {
+        let symbol = literals.decode(bits)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let i = symbol - 257;
+                if i >= LENGTH_BASE.len() {return Err("invalid length in PNG data".to_string());}
+                let len = LENGTH_BASE[i] as usize + bits.read(LENGTH_EXTRA[i] as u32)? as usize;
+                let d = distances.decode(bits)? as usize;
+                if d >= DIST_BASE.len() {return Err("invalid distance in PNG data".to_string());}
+                let dist = DIST_BASE[d] as usize + bits.read(DIST_EXTRA[d] as u32)? as usize;
+                if dist > out.len() {return Err("invalid distance in PNG data".to_string());}
+                let start = out.len() - dist;
+                for k in 0..len {out.push(out[start + k]);}
+            }
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt::Write;
+
+use crate::render::Picture;
+
+
+/// The most base64 the protocol takes in one escape sequence.
+const CHUNK_MAX: usize = 4096;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Asks whether the terminal takes images, without showing one: a
+/// terminal that does answers with an `OK` for image 31.
+pub const QUERY: &str = "\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\";
+
+/// Removes every image shown on the screen and frees its data.
+pub const DELETE_ALL: &str = "\x1b_Ga=d,d=A,q=2\x1b\\";
+
+
+/// Encodes `picture` as kitty graphics shown at the cursor under `id`.
+/// The terminal is asked not to answer and to leave the cursor where it
+/// is.
+pub fn encode(picture: &Picture, id: u32) -> String {
+    let Picture { pixels, width, height, palette } = picture;
+    let rgb: Vec<u8> = pixels.iter().flat_map(|&color| {
+        let (r, g, b) = palette[color as usize];
+        [r, g, b]
+    }).collect();
+    let data = to_base64(&rgb);
+    let mut out = String::new();
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(CHUNK_MAX).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        // The image's keys go with the first chunk only, `m` tells whether
+        // more follow.
+        let more = (i + 1 < chunks.len()) as u8;
+        if i == 0 {
+            write!(out, "\x1b_Ga=T,f=24,s={},v={},i={},q=2,C=1,m={}", width, height, id, more).unwrap();
+        } else {
+            write!(out, "\x1b_Gm={}", more).unwrap();
+        }
+        write!(out, ";{}\x1b\\", std::str::from_utf8(chunk).unwrap()).unwrap();
+    }
+    out
+}
+
+
+/// Standard base64 with padding.
+fn to_base64(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+        for _ in chunk.len()..3 {out.push('=');}
+    }
+    out
+}
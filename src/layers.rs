@@ -0,0 +1,191 @@
+use std::fmt;
+use std::mem;
+
+use crate::Universe;
+
+
+/// Neighbour counts go up to 26 in three dimensions.
+const NEIGHBOURS_MAX: usize = 26;
+
+
+/// A rule for 3D Life in B/S notation with comma separated counts and
+/// ranges, e.g. `B5/S4-5`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rule3d {
+    birth: [bool; NEIGHBOURS_MAX + 1],
+    survival: [bool; NEIGHBOURS_MAX + 1],
+}
+
+
+impl Rule3d {
+    /// Carter Bays' 4555 rule, whose gliders make it the 3D counterpart
+    /// of Conway's Life.
+    pub fn bays() -> Rule3d {
+        Rule3d::parse("B5/S4-5").unwrap()
+    }
+
+    pub fn parse(text: &str) -> Result<Rule3d, String> {
+        let err = |what: &str| format!("invalid 3D rule `{}`: {}", text, what);
+        let mut birth = None;
+        let mut survival = None;
+
+        for part in text.trim().split('/') {
+            let target = match part.get(..1).map(|c| c.to_ascii_uppercase()).as_deref() {
+                Some("B") => &mut birth,
+                Some("S") => &mut survival,
+                _ => return Err(err("expected B.../S...")),
+            };
+            let mut counts = [false; NEIGHBOURS_MAX + 1];
+            for item in part[1..].split(',').filter(|i| !i.is_empty()) {
+                let (from, to) = item.split_once('-').unwrap_or((item, item));
+                let parse = |n: &str| match n.trim().parse::<usize>() {
+                    Ok(n) if n <= NEIGHBOURS_MAX => Ok(n),
+                    _ => Err(err(&format!("`{}` is not a neighbour count from 0 to 26", n))),
+                };
+                let (from, to) = (parse(from)?, parse(to)?);
+                if from > to {return Err(err(&format!("the range `{}` is backwards", item)));}
+                counts[from..=to].fill(true);
+            }
+            *target = Some(counts);
+        }
+
+        match (birth, survival) {
+            (Some(birth), Some(survival)) => Ok(Rule3d { birth, survival }),
+            _ => Err(err("expected B.../S...")),
+        }
+    }
+
+    pub fn next(&self, alive: bool, live_neighbours: usize) -> bool {
+        if alive {self.survival[live_neighbours]} else {self.birth[live_neighbours]}
+    }
+}
+
+
+impl fmt::Display for Rule3d {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Runs of counts are written as ranges, e.g. `4-5`.
+        let list = |counts: &[bool]| {
+            let mut items = Vec::new();
+            let mut n = 0;
+            while n < counts.len() {
+                if !counts[n] {n += 1; continue;}
+                let end = (n..counts.len()).take_while(|&m| counts[m]).last().unwrap();
+                items.push(if end > n {format!("{}-{}", n, end)} else {n.to_string()});
+                n = end + 1;
+            }
+            items.join(",")
+        };
+        write!(f, "B{}/S{}", list(&self.birth), list(&self.survival))
+    }
+}
+
+
+/// An experimental 3D Life: a stack of boards of the same size where each
+/// cell also sees the cells above and below it. One layer is shown and
+/// edited at a time as an ordinary board.
+pub struct Layers {
+    /// The whole stack. The shown layer's slot holds a placeholder while
+    /// the board is lent out.
+    slices: Vec<Universe>,
+    current: usize,
+    pub rule: Rule3d,
+}
+
+
+impl Layers {
+    /// Makes `shown` the bottom layer of a stack of `depth` layers; the
+    /// others start empty.
+    pub fn new(shown: &Universe, depth: usize, rule: Rule3d) -> Layers {
+        let empty = Universe::new(shown.width, shown.height);
+        Layers { slices: vec![empty; depth.max(1)], current: 0, rule }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.slices.len()
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Shows another layer, keeping the cursor and view where they are.
+    /// Returns `false` at the top or bottom of the stack.
+    pub fn switch(&mut self, shown: &mut Universe, by: isize) -> bool {
+        let Some(next) = self.current.checked_add_signed(by).filter(|&l| l < self.depth()) else {return false};
+        let mut layer = mem::replace(&mut self.slices[next], Universe::new(0, 0));
+        layer.selected_cell = shown.selected_cell;
+        layer.show_cursor = shown.show_cursor;
+        layer.is_running = shown.is_running;
+        layer.view = shown.view;
+        layer.view_size = shown.view_size;
+        layer.zoom = shown.zoom;
+        layer.render = shown.render;
+        layer.coloring = shown.coloring;
+        layer.trail_len = shown.trail_len;
+        layer.theme = shown.theme.clone();
+        layer.topology = shown.topology;
+        layer.glyphs = shown.glyphs.clone();
+        layer.borders = shown.borders;
+        layer.pane_borders = shown.pane_borders;
+        layer.ascii = shown.ascii;
+        layer.generation = shown.generation;
+        self.slices[self.current] = mem::replace(shown, layer);
+        self.current = next;
+        true
+    }
+
+    /// Advances every layer by one generation.
+    pub fn tick(&mut self, shown: &mut Universe) {
+        mem::swap(shown, &mut self.slices[self.current]);
+        let next: Vec<Vec<bool>> = (0..self.depth()).map(|z| self.next_layer(z)).collect();
+        for (slice, cells) in self.slices.iter_mut().zip(next) {
+            // Survivors keep their paint, newborn cells start unpainted.
+            let colors = cells.iter().zip(&slice.colors).map(|(&alive, &c)| if alive {c} else {None}).collect();
+            slice.advance(cells, colors);
+        }
+        mem::swap(shown, &mut self.slices[self.current]);
+    }
+
+    /// Steps every layer back one generation.
+    pub fn tick_back(&mut self, shown: &mut Universe) -> Result<(), &'static str> {
+        if shown.history.is_empty() {return Err("No more moves in history!");}
+        // All layers advance together, so their histories are equally long.
+        let _ = shown.tick_back();
+        for (z, slice) in self.slices.iter_mut().enumerate() {
+            if z != self.current {let _ = slice.tick_back();}
+        }
+        Ok(())
+    }
+
+    fn next_layer(&self, z: usize) -> Vec<bool> {
+        let layer = &self.slices[z];
+        let (width, height) = (layer.width, layer.height);
+        let mut next = vec![false; width * height];
+        for row in 0..height {
+            for col in 0..width {
+                let idx = layer.get_index(row, col);
+                if layer.walls[idx] {continue;}
+                next[idx] = self.rule.next(layer.cells[idx], self.live_neighbour_count(z, row, col));
+            }
+        }
+        next
+    }
+
+    /// Layers wrap around within their plane like the shown board does,
+    /// but the top and bottom of the stack never meet.
+    fn live_neighbour_count(&self, z: usize, row: usize, col: usize) -> usize {
+        let plane = &self.slices[self.current];
+        let (row, col) = (row as isize, col as isize);
+        let mut count = 0;
+        for slice in &self.slices[z.saturating_sub(1)..(z + 2).min(self.depth())] {
+            for r in (row - 1)..=(row + 1) {
+                for c in (col - 1)..=(col + 1) {
+                    if let Some((r, c)) = plane.neighbour_at(r, c) {
+                        count += slice.get_cell(r, c) as usize;
+                    }
+                }
+            }
+        }
+        count - self.slices[z].get_cell(row as usize, col as usize) as usize
+    }
+}
@@ -0,0 +1,241 @@
+use std::fs;
+use std::io::{self, Write};
+
+use termion::event::Key;
+
+use crate::pattern::{Pattern, Transform};
+
+
+const BUNDLED: &str = include_str!("lexicon.txt");
+const WRAP_WIDTH: usize = 60;
+const RESULTS_SHOWN: usize = 8;
+const PREVIEW_ROWS: usize = 8;
+const PREVIEW_COLS: usize = 40;
+
+
+#[derive(Clone)]
+pub struct Entry {
+    pub term: String,
+    pub definition: String,
+    pub pattern: Option<Pattern>,
+}
+
+
+#[derive(Clone)]
+pub struct Lexicon {
+    entries: Vec<Entry>,
+}
+
+
+impl Lexicon {
+    /// Parses text in the Life Lexicon format: each entry starts with
+    /// `:term:` and its first tab-indented diagram becomes the pattern.
+    pub fn parse(text: &str) -> Lexicon {
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut diagram = String::new();
+
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix(':') {
+                if let Some((term, definition)) = rest.split_once(':') {
+                    finish_diagram(&mut entries, &mut diagram);
+                    entries.push(Entry {
+                        term: term.to_string(),
+                        definition: definition.trim().to_string(),
+                        pattern: None,
+                    });
+                    continue;
+                }
+            }
+
+            if entries.is_empty() {continue;}
+
+            if let Some(row) = line.strip_prefix('\t') {
+                if row.chars().all(|c| c == '.' || c == 'O') {
+                    diagram.push_str(row);
+                    diagram.push('\n');
+                    continue;
+                }
+            }
+
+            finish_diagram(&mut entries, &mut diagram);
+            let line = line.trim();
+            if !line.is_empty() {
+                let entry = entries.last_mut().unwrap();
+                if !entry.definition.is_empty() {entry.definition.push(' ');}
+                entry.definition.push_str(line);
+            }
+        }
+        finish_diagram(&mut entries, &mut diagram);
+
+        Lexicon { entries }
+    }
+
+    pub fn bundled() -> Lexicon {
+        Lexicon::parse(BUNDLED)
+    }
+
+    pub fn from_file(path: &str) -> io::Result<Lexicon> {
+        Ok(Lexicon::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn entry(&self, idx: usize) -> &Entry {
+        &self.entries[idx]
+    }
+
+    /// Returns indices of matching entries: terms starting with the query
+    /// first, then terms containing it, then definitions mentioning it.
+    pub fn search(&self, query: &str) -> Vec<usize> {
+        let query = query.to_lowercase();
+        let mut prefix = Vec::new();
+        let mut term = Vec::new();
+        let mut definition = Vec::new();
+
+        for (i, e) in self.entries.iter().enumerate() {
+            let t = e.term.to_lowercase();
+            if t.starts_with(&query) {
+                prefix.push(i);
+            } else if t.contains(&query) {
+                term.push(i);
+            } else if !query.is_empty() && e.definition.to_lowercase().contains(&query) {
+                definition.push(i);
+            }
+        }
+
+        prefix.extend(term);
+        prefix.extend(definition);
+        prefix
+    }
+}
+
+
+fn finish_diagram(entries: &mut [Entry], diagram: &mut String) {
+    if diagram.is_empty() {return;}
+    if let Some(e) = entries.last_mut() {
+        if e.pattern.is_none() {
+            e.pattern = Some(Pattern::from_plaintext(diagram));
+        }
+    }
+    diagram.clear();
+}
+
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        if !current.is_empty() && current.len() + word.len() + 1 > width {
+            lines.push(current);
+            current = String::new();
+        }
+        if !current.is_empty() {current.push(' ');}
+        current.push_str(word);
+    }
+    if !current.is_empty() {lines.push(current);}
+    lines
+}
+
+
+pub enum BrowserAction {
+    None,
+    Close,
+    Stamp(Pattern),
+}
+
+
+/// Interactive search over a `Lexicon`, shown below the board.
+pub struct LexiconBrowser {
+    query: String,
+    results: Vec<usize>,
+    selected: usize,
+    message: Option<&'static str>,
+    /// The turns and flips the pattern is stamped with, in order.
+    transforms: Vec<Transform>,
+}
+
+
+impl LexiconBrowser {
+    pub fn new(lexicon: &Lexicon) -> LexiconBrowser {
+        LexiconBrowser {
+            query: String::new(),
+            results: lexicon.search(""),
+            selected: 0,
+            message: None,
+            transforms: Vec::new(),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: Key, lexicon: &Lexicon) -> BrowserAction {
+        self.message = None;
+        match key {
+            Key::Esc => return BrowserAction::Close,
+            Key::Up => {self.selected = self.selected.saturating_sub(1);}
+            Key::Down if self.selected + 1 < self.results.len() => {self.selected += 1;}
+            Key::Char('\n') => {
+                match self.results.get(self.selected) {
+                    Some(&idx) => match &lexicon.entry(idx).pattern {
+                        Some(p) => return BrowserAction::Stamp(self.aimed(p)),
+                        None => {self.message = Some("This entry has no pattern to stamp");}
+                    },
+                    None => {self.message = Some("Nothing selected");}
+                }
+            }
+            Key::Right => self.transforms.push(Transform::RotateRight),
+            Key::Left => self.transforms.push(Transform::RotateLeft),
+            Key::Char('\t') => self.transforms.push(Transform::FlipHorizontal),
+            Key::BackTab => self.transforms.push(Transform::FlipVertical),
+            Key::Backspace => {
+                self.query.pop();
+                self.refresh(lexicon);
+            }
+            Key::Char(c) if !c.is_control() => {
+                self.query.push(c);
+                self.refresh(lexicon);
+            }
+            _ => {}
+        }
+        BrowserAction::None
+    }
+
+    /// `pattern` with the turns and flips applied.
+    fn aimed(&self, pattern: &Pattern) -> Pattern {
+        self.transforms.iter().fold(pattern.clone(), |p, &t| p.transformed(t))
+    }
+
+    fn refresh(&mut self, lexicon: &Lexicon) {
+        self.results = lexicon.search(&self.query);
+        self.selected = 0;
+    }
+
+    pub fn render(&self, stdout: &mut dyn Write, lexicon: &Lexicon, ascii: bool) {
+        write!(stdout, "Lexicon search: {}_\n\r", self.query).unwrap();
+
+        let first = self.selected.saturating_sub(RESULTS_SHOWN - 1);
+        for (i, &idx) in self.results.iter().enumerate().skip(first).take(RESULTS_SHOWN) {
+            let entry = lexicon.entry(idx);
+            let marker = if i == self.selected {'>'} else {' '};
+            let stamp = if entry.pattern.is_some() {" *"} else {""};
+            write!(stdout, "{} {}{}\n\r", marker, entry.term, stamp).unwrap();
+        }
+        if self.results.is_empty() {
+            write!(stdout, "  (no matches)\n\r").unwrap();
+        }
+
+        write!(stdout, "------------\n\r").unwrap();
+        if let Some(&idx) = self.results.get(self.selected) {
+            let entry = lexicon.entry(idx);
+            write!(stdout, "{}:\n\r", entry.term).unwrap();
+            for line in wrap(&entry.definition, WRAP_WIDTH) {
+                write!(stdout, "  {}\n\r", line).unwrap();
+            }
+            if let Some(pattern) = &entry.pattern {
+                let pattern = self.aimed(pattern);
+                write!(stdout, "Stamped with its top left at the cursor, {}x{}:\n\r", pattern.width, pattern.height).unwrap();
+                pattern.render_preview(stdout, PREVIEW_ROWS, PREVIEW_COLS, ascii);
+            }
+        }
+        write!(stdout, "[Up/Down] select  [Left/Right] rotate  [Tab/Shift+Tab] flip  [Enter] stamp (*)  [Esc] close\n\r").unwrap();
+        if let Some(msg) = self.message {
+            write!(stdout, "{}\n\r", msg).unwrap();
+        }
+    }
+}
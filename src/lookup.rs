@@ -0,0 +1,65 @@
+use crate::rule::Rule;
+
+
+/// Steps a board two by two cells at a time: the next state of a 2x2
+/// block depends only on the 4x4 block around it, so all 65536 such blocks
+/// are worked out once per rule and looked up afterwards.
+pub struct LookupTable {
+    pub rule: Rule,
+    /// The next 2x2 block for every 4x4 block, both as bits in row order.
+    table: Vec<u8>,
+}
+
+
+impl LookupTable {
+    pub fn new(rule: Rule) -> LookupTable {
+        let table = (0..=u16::MAX).map(|block| {
+            let alive = |row: usize, col: usize| block >> (row * 4 + col) & 1 == 1;
+            let mut next = 0;
+            for (bit, (row, col)) in [(1, 1), (1, 2), (2, 1), (2, 2)].into_iter().enumerate() {
+                let neighbours = (row - 1..=row + 1)
+                    .flat_map(|r| (col - 1..=col + 1).map(move |c| (r, c)))
+                    .filter(|&(r, c)| (r, c) != (row, col) && alive(r, c))
+                    .count();
+                next |= (rule.next(alive(row, col), neighbours) as u8) << bit;
+            }
+            next
+        }).collect();
+        LookupTable { rule, table }
+    }
+
+    /// The next generation of a `width` by `height` board stored row by
+    /// row. Cells outside the board are dead, or with `wrap` the edges
+    /// wrap around.
+    pub fn step(&self, cells: &[bool], width: usize, height: usize, wrap: bool) -> Vec<bool> {
+        let alive = |row: isize, col: isize| {
+            let (row, col) = if wrap {
+                (row.rem_euclid(height as isize), col.rem_euclid(width as isize))
+            } else {
+                (row, col)
+            };
+            row >= 0 && col >= 0 && (row as usize) < height && (col as usize) < width
+                && cells[row as usize * width + col as usize]
+        };
+        let mut next = vec![false; width * height];
+        for top in (0..height).step_by(2) {
+            for left in (0..width).step_by(2) {
+                let mut block = 0u16;
+                for r in 0..4 {
+                    for c in 0..4 {
+                        let cell = alive(top as isize + r - 1, left as isize + c - 1);
+                        block |= (cell as u16) << (r * 4 + c);
+                    }
+                }
+                let result = self.table[block as usize];
+                for (bit, (r, c)) in [(0, 0), (0, 1), (1, 0), (1, 1)].into_iter().enumerate() {
+                    let (row, col) = (top + r, left + c);
+                    if row < height && col < width {
+                        next[row * width + col] = result >> bit & 1 == 1;
+                    }
+                }
+            }
+        }
+        next
+    }
+}
@@ -1,77 +1,549 @@
 extern crate termion;
 
-use termion::event::Key;
+#[cfg(feature = "audio")]
+mod audio;
+mod background;
+mod bitboard;
+mod bookmarks;
+mod challenges;
+mod checkpoint;
+mod clipboard;
+mod config;
+mod convert;
+mod diagnostic;
+mod daemon;
+mod events;
+mod experiment;
+mod export;
+mod frame;
+mod glyphs;
+mod hashlife;
+mod headless;
+mod history;
+mod image;
+mod json;
+mod kitty;
+mod layers;
+mod lexicon;
+mod lookup;
+mod midi;
+mod parallel;
+mod pattern;
+mod prompt;
+mod puzzle;
+mod random;
+mod render;
+mod rule;
+mod server;
+mod session;
+mod settings;
+mod shapes;
+mod share;
+mod sixel;
+mod sparse;
+mod state;
+mod symmetry;
+mod terminal;
+mod theme;
+mod timer;
+mod toml;
+mod undo;
+
+use termion::color;
+use termion::style;
 use termion::input::TermRead;
 use termion::async_stdin;
+use termion::input::MouseTerminal;
 use termion::raw::IntoRawMode;
-use std::io::{Write, stdout};
-use std::thread::sleep;
+use termion::screen::AlternateScreen;
+use std::io::{self, Read, Write, stdout};
 use std::time::Duration;
-use std::cmp::max;
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, HashSet};
 use std::env;
+use std::fs;
+use std::path::Path;
+use std::mem;
+use std::ops::Range;
 use std::process::exit;
+use std::sync::{Arc, Mutex};
+
+use bitboard::Bitboard;
+use challenges::Challenges;
+use checkpoint::{Checkpoint, Checkpointer};
+use diagnostic::Diagnostic;
+use glyphs::{Borders, Glyphs};
+use history::History;
+use hashlife::HashLife;
+use image::Image;
+use layers::Rule3d;
+use lexicon::Lexicon;
+use lookup::LookupTable;
+use midi::MidiOut;
+use pattern::{Pattern, Transform};
+use puzzle::Level;
+use random::Rng;
+use render::{Graphics, Picture, RenderMode};
+use rule::{Region, Rule};
+use session::{Panels, QuickSave, Session};
+use shapes::Shape;
+use symmetry::Symmetry;
+use theme::Theme;
+use undo::Snapshot;
 
 
-const DEAD: &str = "  ";
-const ALIVE: &str = "██";
-const CORNERS: [char; 4] = ['╔', '╗', '╝', '╚'];
-const BORDER_H: &str = "══";
-const BORDER_V: char = '║';
-const SELECTED_DEAD: &str = "░░";
-const SELECTED_ALIVE: &str = "▒▒";
-const HISTORY_LEN_DEFAULT: usize = 20;
+const REGION_EDGE: &str = "··";
+const MARKER: &str = "++";
+const NOTE: &str = "¶ ";
+// What `--ascii` draws instead, for terminals without Unicode fonts.
+const ASCII_REGION_EDGE: &str = ": ";
+const ASCII_NOTE: &str = "* ";
+const REFERENCE: (u8, u8, u8) = (60, 70, 120);
+// The pattern about to be stamped, where it would land.
+const GHOST: (u8, u8, u8) = (70, 150, 100);
+// Live cells across the opposite edge of a torus, drawn in the border.
+const WRAPPED: (u8, u8, u8) = (90, 90, 90);
+const FADE_ALIVE: (u8, u8, u8) = (230, 230, 230);
+const FADE_DEAD: (u8, u8, u8) = (40, 40, 40);
+// Colours of the cursor and of walls in the render modes that draw with
+// colours rather than glyphs.
+const CURSOR_COLOR: (u8, u8, u8) = (240, 190, 40);
+const WALL_COLOR: (u8, u8, u8) = (110, 110, 110);
+// The background of the selected cells.
+const SELECTION_COLOR: (u8, u8, u8) = (45, 60, 95);
+// Colouring by age runs from newborn to old cells over this many
+// generations, in as many shades.
+const AGE_YOUNG: (u8, u8, u8) = (250, 245, 200);
+const AGE_OLD: (u8, u8, u8) = (50, 80, 190);
+const AGE_OLD_AFTER: u32 = 32;
+// Colouring by changes shows cells born and cells that died in the last
+// generation in these.
+const BORN_COLOR: (u8, u8, u8) = (80, 220, 90);
+const DIED_COLOR: (u8, u8, u8) = (230, 60, 50);
+// Trails of cells that died fade from this colour to the dead one.
+const TRAIL_COLOR: (u8, u8, u8) = (90, 130, 210);
+const TRAIL_LEN_DEFAULT: u32 = 8;
+// The most pixels across and down a cell takes up in an image.
+const IMAGE_SCALE_MAX: usize = 8;
+// The size of a character in pixels when the terminal does not tell.
+const CHAR_PIXELS_DEFAULT: (usize, usize) = (10, 20);
+const PAINT_COLORS: [(&str, (u8, u8, u8)); 6] = [
+    ("red", (230, 70, 60)),
+    ("green", (80, 200, 90)),
+    ("yellow", (230, 200, 60)),
+    ("blue", (70, 130, 240)),
+    ("magenta", (210, 90, 210)),
+    ("cyan", (70, 210, 220)),
+];
 const SIZE_ROWS_DEFAULT: usize = 10;
 const SIZE_COLS_DEFAULT: usize = 10;
+const IMAGE_THRESHOLD_DEFAULT: u8 = 128;
+const DAEMON_SOCKET_DEFAULT: &str = "/tmp/game-of-life.sock";
+const SERVER_PORT_DEFAULT: &str = "2323";
+/// Where the telnet server listens without `--bind`: this machine only.
+const SERVER_BIND_DEFAULT: &str = "127.0.0.1";
+const CHECKPOINT_EVERY_DEFAULT: u64 = 1000;
+// Borders, view and status lines around the board.
+const VIEW_MARGIN_ROWS: usize = 5;
+// Lines below a board that fits the terminal: status bar, run time, hint
+// bar and a message.
+const FIT_STATUS_ROWS: usize = 4;
+// Each zoom level out doubles the cells per character, up to this many.
+const ZOOM_MAX: usize = 16;
+const IMAGE_EXTENSIONS: [&str; 7] = ["png", "pbm", "pgm", "ppm", "pnm", "jpg", "jpeg"];
+
+
+/// Starts or ends the background of the selected cells.
+fn write_selection_bg(stdout: &mut dyn Write, start: bool) {
+    let (r, g, b) = SELECTION_COLOR;
+    if start {
+        write!(stdout, "{}", color::Bg(color::Rgb(r, g, b))).unwrap();
+    } else {write!(stdout, "{}", color::Bg(color::Reset)).unwrap();}
+}
+
+
+/// Writes `text` in `color`, or in the terminal's colour for `None`.
+fn write_in(stdout: &mut dyn Write, color: Option<(u8, u8, u8)>, text: &str) {
+    match color {
+        Some((r, g, b)) => write!(stdout, "{}{}{}", color::Fg(color::Rgb(r, g, b)), text, color::Fg(color::Reset)).unwrap(),
+        None => write!(stdout, "{}", text).unwrap(),
+    }
+}
 
 
 fn write_title(stdout: &mut dyn Write, write_help: bool) {
-    write!(stdout, "\r{}\n\r", "Game Of Life");
-    write!(stdout, "{}\n\r", "------------");
+    write!(stdout, "\rGame Of Life\n\r").unwrap();
+    write!(stdout, "------------\n\r").unwrap();
     if write_help {
-        write!(stdout, "{}\n\r", "Controls:");
-        write!(stdout, "{}\n\r", "* Arrow keys - move cursor");
-        write!(stdout, "{}\n\r", "* Space - toggle cell");
-        write!(stdout, "{}\n\r", "* R/S - [R]un / [S]top");
-        write!(stdout, "{}\n\r", "* P/N - [P]rev/[N]ext");
-        write!(stdout, "{}\n\r", "        (Single Step)");
-        write!(stdout, "{}\n\r", "* C - [C]lear");
-        write!(stdout, "{}\n\r", "* T - [T]oggle cursor");
-        write!(stdout, "{}\n\r", "------------");
+        write!(stdout, "Controls:\n\r").unwrap();
+        write!(stdout, "* Arrow keys or H/J/K/L - move cursor, Shift+arrows or Alt+W/A/S/D - pan the view\n\r").unwrap();
+        write!(stdout, "* 0-9 - count for the next move, N or P, e.g. 10L or 25N\n\r").unwrap();
+        write!(stdout, "* Alt+-/Alt++ - zoom out/in (several cells per character)\n\r").unwrap();
+        write!(stdout, "* Shift+G - switch between cells, half blocks, quadrants, Braille dots and images\n\r").unwrap();
+        write!(stdout, "* Shift+A - colour cells by paint, by [A]ge or by births and deaths\n\r").unwrap();
+        write!(stdout, "* Shift+H - fading trails behind cells that died\n\r").unwrap();
+        write!(stdout, "* Shift+P - switch colour theme\n\r").unwrap();
+        write!(stdout, "* F - [F]ind activity, Shift+F - camera follows cells\n\r").unwrap();
+        write!(stdout, "* Space or left click - toggle cell, drag to draw\n\r").unwrap();
+        write!(stdout, "* R/S - [R]un / [S]top\n\r").unwrap();
+        write!(stdout, "* P/N - [P]rev/[N]ext\n\r").unwrap();
+        write!(stdout, "        (Single Step)\n\r").unwrap();
+        write!(stdout, "* C - [C]lear, Shift+C - clear walls\n\r").unwrap();
+        write!(stdout, "* D - ran[D]om soup\n\r").unwrap();
+        write!(stdout, "* ? - hide or show this help\n\r").unwrap();
+        write!(stdout, "* T - [T]oggle cursor, Shift+T - [T]imer to pause after\n\r").unwrap();
+        write!(stdout, "* Shift+J - [J]ump ahead (HashLife engine)\n\r").unwrap();
+        write!(stdout, "* : - command: save, load, rule, resize, random, invert, shift, symmetry, step, goto <n>, q\n\r").unwrap();
+        write!(stdout, "* Shift+I - t[I]meline, scrub through past generations with the arrows\n\r").unwrap();
+        write!(stdout, "* Ctrl+B - [B]ookmarked boards, to go back to\n\r").unwrap();
+        write!(stdout, "* V - record, V again - export as HTML\n\r").unwrap();
+        write!(stdout, "* Shift+E - [E]xport event log (JSON lines)\n\r").unwrap();
+        write!(stdout, "* I - [I]mport image (PNG, PBM/PGM/PPM) or text\n\r").unwrap();
+        write!(stdout, "* B - share [B]oard code, Shift+U - load code\n\r").unwrap();
+        write!(stdout, "* U - [U]ndo an edit, Ctrl+R - redo it\n\r").unwrap();
+        write!(stdout, "* X - e[X]port the live cells as RLE\n\r").unwrap();
+        write!(stdout, "* Ctrl+V - select cells, then Y copies (C as RLE), X cuts, M moves, S randomizes, I inverts, R/F turn; Ctrl+P - paste\n\r").unwrap();
+        write!(stdout, "* Ctrl+D - [D]raw a line, rectangle or circle from the cursor\n\r").unwrap();
+        write!(stdout, "* F5/F9 - quicksave/quickload\n\r").unwrap();
+        write!(stdout, "* Shift+S - [S]ave everything as JSON, Shift+D - loa[D] it\n\r").unwrap();
+        write!(stdout, "* O - ph[O]to mode (only cells)\n\r").unwrap();
+        write!(stdout, "* Shift+R - [R]eference pattern to rebuild, shown dimmed\n\r").unwrap();
+        write!(stdout, "* M - drop [M]arker to measure from, Shift+M - remove it\n\r").unwrap();
+        write!(stdout, "* Shift+N - [N]ote on cell, Shift+L - [L]ist notes\n\r").unwrap();
+        write!(stdout, "* Shift+V - save view by name, ' - go to a saved view\n\r").unwrap();
+        write!(stdout, "* Shift+B - change the rule (B/S, e.g. B36/S23)\n\r").unwrap();
+        write!(stdout, "* G - rule re[G]ion (corner, then G again)\n\r").unwrap();
+        write!(stdout, "* W - toggle [W]all, Shift+W - walls count as alive\n\r").unwrap();
+        write!(stdout, "* Shift+O - t[O]rus, edges wrap around\n\r").unwrap();
+        write!(stdout, "* Y - d[Y]e colour for new cells, Shift+Y - paint cell\n\r").unwrap();
+        write!(stdout, "* E - [E]dit settings (speed, rule, edges, size, glyphs)\n\r").unwrap();
+        write!(stdout, "* Shift+X - le[X]icon search\n\r").unwrap();
+        write!(stdout, "* A - ch[A]llenges\n\r").unwrap();
+        write!(stdout, "* Z - pu[Z]zles\n\r").unwrap();
+        write!(stdout, "* [/] - previous/next layer (3D mode)\n\r").unwrap();
+        write!(stdout, "* | - split into two panes, Tab - switch pane\n\r").unwrap();
+        write!(stdout, "------------\n\r").unwrap();
+    }
+}
+
+
+/// What a cell, or the block of cells the zoom makes of it, shows in the
+/// render modes that draw with colours.
+#[derive(Clone, Copy, PartialEq)]
+enum Dot {
+    Dead,
+    /// Dead, but drawn in the colour the colouring gives it.
+    Marked((u8, u8, u8)),
+    /// Alive, with the colour the colouring gives it, if any.
+    Alive(Option<(u8, u8, u8)>),
+    Cursor,
+    Wall,
+}
+
+
+/// A view position with the cursor position at the time it was saved.
+#[derive(Clone, Copy)]
+struct SavedView {
+    view: (usize, usize),
+    cursor: (usize, usize),
+}
+
+
+/// How the next generation is computed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Engine {
+    /// Counts the neighbours of every cell.
+    Naive,
+    /// Looks up 2x2 blocks in a table, see `LookupTable`. Boards with walls
+    /// or regions fall back to `Naive`.
+    Lookup,
+    /// Steps 64 cells at a time on a packed copy of the board, see
+    /// `Bitboard`. Boards with walls or regions fall back to `Naive`.
+    Packed,
+    /// Steps a quadtree with memoized generations, see `HashLife`, and can
+    /// jump far ahead. Boards with walls or regions, a torus and rules
+    /// with B0 fall back to `Naive`.
+    HashLife,
+}
+
+
+impl Engine {
+    fn parse(name: &str) -> Result<Engine, String> {
+        match name {
+            "naive" => Ok(Engine::Naive),
+            "lookup" => Ok(Engine::Lookup),
+            "packed" => Ok(Engine::Packed),
+            "hashlife" => Ok(Engine::HashLife),
+            _ => Err(format!("unknown engine `{}`, expected naive, lookup, packed or hashlife", name)),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Engine::Naive => "naive",
+            Engine::Lookup => "lookup",
+            Engine::Packed => "packed",
+            Engine::HashLife => "hashlife",
+        }
+    }
+}
+
+
+/// What lies beyond the edges of the board.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Topology {
+    /// Cells outside the board are dead.
+    Bounded,
+    /// The edges wrap around, so a glider leaving on the right comes back
+    /// on the left.
+    Torus,
+    /// The board is a window onto an unbounded plane: live cells that
+    /// leave it are kept in `Universe::beyond` and carry on off screen.
+    Unbounded,
+}
+
+
+impl Topology {
+    fn parse(name: &str) -> Result<Topology, String> {
+        match name {
+            "bounded" => Ok(Topology::Bounded),
+            "torus" => Ok(Topology::Torus),
+            "unbounded" => Ok(Topology::Unbounded),
+            _ => Err(format!("unknown topology `{}`, expected bounded, torus or unbounded", name)),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Topology::Bounded => "bounded",
+            Topology::Torus => "torus",
+            Topology::Unbounded => "unbounded",
+        }
+    }
+}
+
+
+/// What decides the colour live cells are drawn in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Coloring {
+    /// The colour they were painted with, the terminal's otherwise.
+    Paint,
+    /// How many generations they have been alive in a row, from bright
+    /// for newborn cells to dim blue for old ones.
+    Age,
+    /// Whether they were born in the last generation, in green; cells
+    /// that died in it show in red until the next.
+    Changes,
+}
+
+
+const COLORINGS: [Coloring; 3] = [Coloring::Paint, Coloring::Age, Coloring::Changes];
+
+
+impl Coloring {
+    fn parse(name: &str) -> Result<Coloring, String> {
+        COLORINGS.iter().copied().find(|coloring| coloring.name() == name).ok_or_else(|| {
+            let names: Vec<&str> = COLORINGS.iter().map(|coloring| coloring.name()).collect();
+            format!("unknown colouring `{}`, expected {}", name, names.join(", "))
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Coloring::Paint => "paint",
+            Coloring::Age => "age",
+            Coloring::Changes => "changes",
+        }
+    }
+
+    /// The colouring `by` places further along `COLORINGS`, wrapping around.
+    fn cycle(self, by: isize) -> Coloring {
+        let at = COLORINGS.iter().position(|&coloring| coloring == self).unwrap() as isize;
+        COLORINGS[(at + by).rem_euclid(COLORINGS.len() as isize) as usize]
     }
 }
 
 
+#[derive(Clone)]
 pub struct Universe {
     width: usize,
     height: usize,
     cells: Vec<bool>,
+    /// Generations advanced since the board was started or loaded.
+    generation: u64,
+    /// The number of live cells, kept up to date by every change.
+    population: usize,
     selected_cell: (usize, usize),
     show_cursor: bool,
     is_running: bool,
-    history: VecDeque<Vec<bool>>,
+    history: History,
+    /// Paint colours of the live cells, as indices into `PAINT_COLORS`.
+    colors: Vec<Option<u8>>,
+    /// The colour cells made alive by hand are painted with.
+    brush: Option<u8>,
+    /// Where cells toggled by hand are mirrored to.
+    symmetry: Symmetry,
+    /// Whether a torus shows the live cells across the opposite edges in
+    /// the border.
+    edge_preview: bool,
+    /// How many generations each live cell has been alive in a row, 0 for
+    /// cells born in the last one or made alive since.
+    ages: Vec<u32>,
+    coloring: Coloring,
+    theme: Theme,
+    /// How many generations ago each dead cell was last alive, `u32::MAX`
+    /// when it has not been since it was cleared or set up.
+    dead_for: Vec<u32>,
+    /// Cells that died up to this many generations ago leave a fading
+    /// trail; 0 for none.
+    trail_len: u32,
     should_write_help: bool,
-    history_len: usize,
+    walls: Vec<bool>,
+    walls_alive: bool,
+    rule: Rule,
+    regions: Vec<Region>,
+    photo_mode: bool,
+    /// The top left cell of the part of the board that is drawn.
+    view: (usize, usize),
+    /// How many rows and columns fit on screen; `None` draws everything.
+    view_size: Option<(usize, usize)>,
+    /// How many cells across and down one character on screen stands for,
+    /// or each dot of it in the modes that pack cells into characters.
+    zoom: usize,
+    render: RenderMode,
+    /// The size of a character on the terminal in pixels, as width and
+    /// height, for the modes that draw an image.
+    char_pixels: (usize, usize),
+    /// A cell to measure the cursor's distance from.
+    marker: Option<(usize, usize)>,
+    /// The corner of the selection opposite the cursor, while selecting.
+    selection: Option<(usize, usize)>,
+    /// The live cells last copied or cut, to paste.
+    clipboard: Option<Pattern>,
+    /// A pattern shown with its top left corner at the cursor, its cells
+    /// sorted, before it is stamped there.
+    ghost: Option<Pattern>,
+    /// Text notes attached to cells, by row and column.
+    notes: BTreeMap<(usize, usize), String>,
+    saved_views: BTreeMap<String, SavedView>,
+    /// The second pane of a split screen; the focused pane uses `view`
+    /// and `selected_cell`.
+    other_pane: Option<SavedView>,
+    focus_right: bool,
+    /// How far the cells that changed in the last generation have faded
+    /// from their old to their new state, from 0 to 1.
+    fade: Option<f32>,
+    /// A target board drawn under the live cells, not simulated.
+    reference: Option<Vec<bool>>,
+    engine: Engine,
+    topology: Topology,
+    /// Live cells off the board on an unbounded plane, by row and column
+    /// counted from the board's top left cell.
+    beyond: HashSet<sparse::Cell>,
+    /// Where the board's top left cell is on an unbounded plane, moved by
+    /// moving the cursor off the board.
+    origin: sparse::Cell,
+    glyphs: Glyphs,
+    /// The border around the focused pane, or the only one.
+    borders: Borders,
+    /// The border around the other pane of a split screen.
+    pane_borders: Borders,
+    /// Only ASCII is drawn, for terminals without Unicode fonts.
+    ascii: bool,
+    /// The table for `Engine::Lookup`, built for the rule when first needed.
+    lookup: Option<Arc<LookupTable>>,
+    /// The quadtree cache for `Engine::HashLife`, kept between generations.
+    hashlife: Option<Arc<Mutex<HashLife>>>,
 }
 
 
 impl Universe {
     pub fn new(width: usize, height: usize) -> Universe {
         Universe {
-            width: width,
-            height: height,
+            width,
+            height,
             cells: vec![false; width * height],
+            generation: 0,
+            population: 0,
             selected_cell: (0, 0),
             show_cursor: false,
             is_running: false,
-            history: VecDeque::new(),
+            history: History::new(),
+            colors: vec![None; width * height],
+            brush: None,
+            ages: vec![0; width * height],
+            coloring: Coloring::Paint,
+            symmetry: Symmetry::None,
+            edge_preview: false,
+            theme: Theme::default(),
+            dead_for: vec![u32::MAX; width * height],
+            trail_len: 0,
             should_write_help: true,
-            history_len: HISTORY_LEN_DEFAULT,
+            walls: vec![false; width * height],
+            walls_alive: false,
+            rule: Rule::conway(),
+            regions: Vec::new(),
+            photo_mode: false,
+            view: (0, 0),
+            view_size: None,
+            zoom: 1,
+            render: RenderMode::Cells,
+            char_pixels: CHAR_PIXELS_DEFAULT,
+            marker: None,
+            selection: None,
+            clipboard: None,
+            ghost: None,
+            notes: BTreeMap::new(),
+            saved_views: BTreeMap::new(),
+            other_pane: None,
+            focus_right: false,
+            fade: None,
+            reference: None,
+            engine: Engine::Naive,
+            topology: Topology::Bounded,
+            beyond: HashSet::new(),
+            origin: (0, 0),
+            glyphs: Glyphs::default(),
+            borders: glyphs::DOUBLE,
+            pane_borders: glyphs::SINGLE,
+            ascii: false,
+            lookup: None,
+            hashlife: None,
+        }
+    }
+
+    /// A board just large enough for an RLE pattern, with its rule. See
+    /// `Pattern::parse_rle` for the format; what it warns about is an error.
+    pub fn from_rle(text: &str) -> Result<Universe, Diagnostic> {
+        let (pattern, warnings) = Pattern::parse_rle(text)?;
+        if let Some(warning) = warnings.into_iter().next() {return Err(warning);}
+        let mut universe = Universe::new(pattern.width.max(1), pattern.height.max(1));
+        universe.stamp(&pattern, 0, 0);
+        if let Some(rule) = pattern.rule {universe.rule = rule;}
+        Ok(universe)
+    }
+
+    /// The live cells cut to their bounding box.
+    pub fn to_pattern(&self) -> Pattern {
+        let cells = self.live_cells();
+        let top = cells.iter().map(|&(r, _)| r).min().unwrap_or(0);
+        let left = cells.iter().map(|&(_, c)| c).min().unwrap_or(0);
+        let bottom = cells.iter().map(|&(r, _)| r + 1).max().unwrap_or(0);
+        let right = cells.iter().map(|&(_, c)| c + 1).max().unwrap_or(0);
+        Pattern {
+            width: right - left,
+            height: bottom - top,
+            cells: cells.iter().map(|&(r, c)| (r - top, c - left)).collect(),
+            rule: Some(self.rule),
         }
     }
 
+    /// The live cells as an RLE pattern for Golly and other programs.
+    pub fn to_rle(&self) -> String {
+        self.to_pattern().to_rle(self.rule)
+    }
+
     fn get_index(&self, row: usize, column: usize) -> usize {
-        (row * self.width + column) as usize
+        row * self.width + column
     }
 
     pub fn set_cells(&mut self, cells: &[(usize, usize)]) {
@@ -79,248 +551,2446 @@ impl Universe {
             let idx = self.get_index(*row, *col);
             self.cells[idx] = true;
         }
+        self.count_population();
+    }
+
+    /// Counts the live cells again after the board was changed wholesale.
+    fn count_population(&mut self) {
+        self.population = self.cells.iter().filter(|&&alive| alive).count();
     }
 
+    /// Writes the title, the board and the help as lines ended by `\n\r`;
+    /// the caller puts them on screen.
     pub fn render(&self, stdout: &mut dyn Write) {
-        write!(stdout,
-           "{}{}{}",
-           termion::cursor::Goto(1, 1),
-           termion::clear::All,
-           termion::cursor::Hide);
+        if self.photo_mode {
+            self.render_photo(stdout);
+            return;
+        }
         write_title(stdout, self.should_write_help);
 
-        write!(stdout, "{}", CORNERS[0]);
-        for i in 0..self.width {write!(stdout, "{}", BORDER_H);}
-        write!(stdout, "{}\n\r", CORNERS[1]);
+        let (rows, cols) = self.visible_size();
+        let (screen_rows, screen_cols) = self.screen_size();
+        let (_, down) = self.cells_per_char();
+        let border_width = screen_cols * self.char_width(self.drawn_as());
+        let (top, left) = self.view;
+        // The focused pane is drawn with its own border.
+        let panes = match self.other_pane {
+            Some(other) if self.focus_right => vec![(other.view, false), (self.view, true)],
+            Some(other) => vec![(self.view, true), (other.view, false)],
+            None => vec![(self.view, true)],
+        };
+        let border = |focused: bool| if focused {self.borders} else {self.pane_borders};
 
-        for i in 0..self.height {
-            write!(stdout, "{}", BORDER_V);
-            for j in 0..self.width {
-                let ind = self.get_index(i, j);
+        for &(view, focused) in &panes {
+            let Borders { corners, horizontal, .. } = border(focused);
+            write_in(stdout, self.theme.border, &corners[0].to_string());
+            match self.wrapped_edges(view)[0] {
+                Some(row) => self.write_wrapped_row(stdout, row, view.1, screen_cols, horizontal),
+                None => write_in(stdout, self.theme.border, &horizontal.to_string().repeat(border_width)),
+            }
+            write_in(stdout, self.theme.border, &corners[1].to_string());
+        }
+        write!(stdout, "\n\r").unwrap();
 
-                if self.cells[ind] {
-                    if ((i, j) == self.selected_cell) && self.show_cursor {
-                        write!(stdout, "{}", SELECTED_ALIVE);
-                    } else {write!(stdout, "{}", ALIVE);}
-                } else {
-                    if ((i, j) == self.selected_cell) && self.show_cursor {
-                        write!(stdout, "{}", SELECTED_DEAD);
-                    } else {write!(stdout, "{}", DEAD);}
-                }
+        for row in 0..screen_rows {
+            for &((top, left), focused) in &panes {
+                let vertical = border(focused).vertical;
+                let [_, _, wrapped_left, wrapped_right] = self.wrapped_edges((top, left));
+                self.write_wrapped_cell(stdout, top + row * down, wrapped_left, vertical);
+                self.write_screen_row(stdout, top + row * down, left, screen_cols, self.show_cursor && focused);
+                self.write_wrapped_cell(stdout, top + row * down, wrapped_right, vertical);
+            }
+            write!(stdout, "\n\r").unwrap();
+        }
+
+        for &(view, focused) in &panes {
+            let Borders { corners, horizontal, .. } = border(focused);
+            write_in(stdout, self.theme.border, &corners[3].to_string());
+            match self.wrapped_edges(view)[1] {
+                Some(row) => self.write_wrapped_row(stdout, row, view.1, screen_cols, horizontal),
+                None => write_in(stdout, self.theme.border, &horizontal.to_string().repeat(border_width)),
+            }
+            write_in(stdout, self.theme.border, &corners[2].to_string());
+        }
+        write!(stdout, "\n\r").unwrap();
+
+        let (across, down) = self.cells_per_char();
+        let image = self.drawn_as().is_image();
+        if (rows, cols) != (self.height, self.width) || (across, down) != (1, 1) || image {
+            write!(stdout, "View: rows {}-{}, columns {}-{} of {}x{}",
+                   top, top + rows - 1, left, left + cols - 1, self.height, self.width).unwrap();
+            if image && self.zoom > 1 {
+                write!(stdout, ", {0}x{0} cells per pixel", self.zoom).unwrap();
+            } else if image {
+                write!(stdout, ", {0}x{0} pixels per cell", self.image_scale()).unwrap();
+            } else if (across, down) != (1, 1) {
+                write!(stdout, ", {}x{} cells per character", across, down).unwrap();
             }
-            write!(stdout, "{}\n\r", BORDER_V);
+            write!(stdout, "\n\r").unwrap();
         }
+        self.render_status(stdout);
+    }
 
-        write!(stdout, "{}", CORNERS[3]);
-        for i in 0..self.width {write!(stdout, "{}", BORDER_H);}
-        write!(stdout, "{}\n\r", CORNERS[2]);
+    /// The rows above and below and the columns left and right of the view
+    /// from `top`, `left` that the board wraps round to across its
+    /// opposite edges, where the view reaches an edge. Only shown with the
+    /// edge preview on a torus drawn one cell per character.
+    fn wrapped_edges(&self, (top, left): (usize, usize)) -> [Option<usize>; 4] {
+        let shown = self.edge_preview && self.topology == Topology::Torus && self.zoom == 1
+            && matches!(self.drawn_as(), RenderMode::Cells | RenderMode::Auto);
+        if !shown {return [None; 4];}
+        let (rows, cols) = self.visible_size();
+        [
+            (top == 0).then_some(self.height - 1),
+            (top + rows == self.height).then_some(0),
+            (left == 0).then_some(self.width - 1),
+            (left + cols == self.width).then_some(0),
+        ]
     }
 
-    pub fn move_cursor(&mut self, r: isize, c: isize) {
-        if r < 0 {
-            if r.abs() as usize > self.selected_cell.0 {
-                self.selected_cell.0 = self.height - ((r.abs() as usize - self.selected_cell.0) % self.height);
-            } else {self.selected_cell.0 -= r.abs() as usize;}
-        } else {
-            self.selected_cell.0 = (self.selected_cell.0 + r as usize) % self.height;
+    /// Draws the live cells of row `i` from column `left` on, dimmed, in
+    /// place of the border line, the border where cells are dead.
+    fn write_wrapped_row(&self, stdout: &mut dyn Write, i: usize, left: usize, cols: usize, horizontal: char) {
+        let dead = horizontal.to_string().repeat(self.glyphs.width());
+        for j in left..left + cols {
+            if self.cells[self.get_index(i, j)] {
+                write_in(stdout, Some(WRAPPED), &self.glyphs.alive);
+            } else {write_in(stdout, self.theme.border, &dead);}
         }
+    }
 
-        if c < 0 {
-            if c.abs() as usize > self.selected_cell.1 {
-                self.selected_cell.1 = self.width - ((c.abs() as usize - self.selected_cell.1) % self.width);
-            } else {self.selected_cell.1 -= c.abs() as usize;}
-        } else {
-            self.selected_cell.1 = (self.selected_cell.1 + c as usize) % self.width;
+    /// Draws the border beside row `i`, or the first character of a live
+    /// cell, dimmed, when the cell in `col` across the edge is alive.
+    fn write_wrapped_cell(&self, stdout: &mut dyn Write, i: usize, col: Option<usize>, vertical: char) {
+        match col {
+            Some(j) if self.cells[self.get_index(i, j)] => {
+                write_in(stdout, Some(WRAPPED), &self.glyphs.alive.chars().take(1).collect::<String>());
+            }
+            _ => write_in(stdout, self.theme.border, &vertical.to_string()),
         }
     }
 
-    fn is_in_bounds(&self, row: isize, col: isize) -> bool {
-        return (row >= 0) &&
-               (row < self.height as isize) &&
-               (col >= 0) &&
-               (col < self.width as isize);
+    /// Draws `count` characters of the line of the screen whose top cells
+    /// are in row `i`, from column `left` on, in the render mode.
+    fn write_screen_row(&self, stdout: &mut dyn Write, i: usize, left: usize, count: usize, show_cursor: bool) {
+        match (self.drawn_as(), self.zoom) {
+            (RenderMode::Cells | RenderMode::Auto, 1) => self.write_row(stdout, i, left, count, show_cursor),
+            (RenderMode::Cells | RenderMode::Auto, _) => self.write_blocks(stdout, i, left, count, show_cursor),
+            (RenderMode::HalfBlock, _) => self.write_half_blocks(stdout, i, left, count, show_cursor),
+            (mode @ (RenderMode::Quadrant | RenderMode::Braille), _) => {
+                self.write_dots(stdout, mode, i, left, count, show_cursor);
+            }
+            // Room for the image `write_graphics` draws over it.
+            (RenderMode::Sixel | RenderMode::Kitty, _) => write!(stdout, "{}", " ".repeat(count)).unwrap(),
+        }
     }
 
-    pub fn get_cell(&self, row: usize, col: usize) -> bool {
-        let ind = self.get_index(row, col);
-        return self.cells[ind];
+    /// Draws the cells of row `i` from column `left` on.
+    fn write_row(&self, stdout: &mut dyn Write, i: usize, left: usize, cols: usize, show_cursor: bool) {
+        for j in left..left + cols {
+            let ind = self.get_index(i, j);
+            let selected = self.overlaps_selection(i..i + 1, j..j + 1);
+            if selected {write_selection_bg(stdout, true);}
+
+            if self.walls[ind] {
+                if ((i, j) == self.selected_cell) && show_cursor {
+                    write_in(stdout, self.theme.cursor, &self.glyphs.selected_alive);
+                } else {write_in(stdout, self.theme.wall, &self.glyphs.wall);}
+            } else if self.cells[ind] {
+                if ((i, j) == self.selected_cell) && show_cursor {
+                    write_in(stdout, self.theme.cursor, &self.glyphs.selected_alive);
+                } else {self.write_cell(stdout, ind);}
+            } else {
+                if ((i, j) == self.selected_cell) && show_cursor {
+                    write_in(stdout, self.theme.cursor, &self.glyphs.selected_dead);
+                } else if self.is_ghost(i, j) {
+                    let (r, g, b) = GHOST;
+                    write!(stdout, "{}{}{}", color::Fg(color::Rgb(r, g, b)), self.glyphs.alive, color::Fg(color::Reset)).unwrap();
+                } else if self.fade_color(ind).is_some() || self.dead_color(ind).is_some() {
+                    self.write_cell(stdout, ind);
+                } else if self.marker == Some((i, j)) {
+                    write!(stdout, "{}", self.mark(MARKER)).unwrap();
+                } else if self.notes.contains_key(&(i, j)) {
+                    write!(stdout, "{}", self.mark(if self.ascii {ASCII_NOTE} else {NOTE})).unwrap();
+                } else if self.reference.as_ref().is_some_and(|r| r[ind]) {
+                    let (r, g, b) = REFERENCE;
+                    write!(stdout, "{}{}{}", color::Fg(color::Rgb(r, g, b)), self.glyphs.alive, color::Fg(color::Reset)).unwrap();
+                } else if self.regions.iter().any(|r| r.on_edge(i, j)) {
+                    write!(stdout, "{}", self.mark(if self.ascii {ASCII_REGION_EDGE} else {REGION_EDGE})).unwrap();
+                } else {write_in(stdout, self.theme.dead, &self.glyphs.dead);}
+            }
+            if selected {write_selection_bg(stdout, false);}
+        }
     }
 
-    pub fn set_cell(&mut self, row: usize, col: usize, val: bool) {
-        let ind = self.get_index(row, col);
-        self.cells[ind] = val;
+    /// Whether the selection takes in any cell of `rows` and `cols`.
+    fn overlaps_selection(&self, rows: Range<usize>, cols: Range<usize>) -> bool {
+        self.selection_bounds().is_some_and(|((top, left), (bottom, right))| {
+            rows.start <= bottom && top < rows.end && cols.start <= right && left < cols.end
+        })
     }
 
-    fn live_neighbour_count(&self, row: usize, col: usize) -> usize {
-        let mut ans: usize = 0;
-        let row = row as isize;
-        let col = col as isize;
+    /// `mark`, two columns wide, cut to the width of a cell.
+    fn mark(&self, mark: &str) -> String {
+        mark.chars().take(self.glyphs.width()).collect()
+    }
 
-        for r in (row - 1)..=(row + 1) {
-            for c in (col - 1)..=(col + 1) {
-                if self.is_in_bounds(r, c) && !((r, c) == (row, col)){
-                    ans += self.get_cell(r as usize, c as usize) as usize;
+    /// Draws `count` characters of a zoomed out row from the block whose
+    /// top left cell is at row `i`, column `left` on. A block shows as
+    /// alive if any of its cells is, and as a wall if any is one.
+    fn write_blocks(&self, stdout: &mut dyn Write, i: usize, left: usize, count: usize, show_cursor: bool) {
+        let rows = i..(i + self.zoom).min(self.height);
+        for j in (left..self.width).step_by(self.zoom).take(count) {
+            let cols = j..(j + self.zoom).min(self.width);
+            let block = || rows.clone().flat_map(|r| cols.clone().map(move |c| self.get_index(r, c)));
+            let alive = block().any(|ind| self.cells[ind]);
+            let (row, col) = self.selected_cell;
+            let selected = self.overlaps_selection(rows.clone(), cols.clone());
+            if selected {write_selection_bg(stdout, true);}
+            if show_cursor && rows.contains(&row) && cols.contains(&col) {
+                let glyph = if alive {&self.glyphs.selected_alive} else {&self.glyphs.selected_dead};
+                write_in(stdout, self.theme.cursor, glyph);
+            } else if alive {
+                write_in(stdout, self.theme.alive, &self.glyphs.alive);
+            } else if self.block_ghost(i, j) {
+                write_in(stdout, Some(GHOST), &self.glyphs.alive);
+            } else if block().any(|ind| self.walls[ind]) {
+                write_in(stdout, self.theme.wall, &self.glyphs.wall);
+            } else {write_in(stdout, self.theme.dead, &self.glyphs.dead);}
+            if selected {write_selection_bg(stdout, false);}
+        }
+    }
+
+    /// Draws `count` quadrant block or Braille characters, as `mode`
+    /// says, from the one whose top left cell is at row `i`, column `left`
+    /// on, a dot for every live cell or, zoomed out, for every block with
+    /// one. The character with the cursor is drawn in reverse.
+    fn write_dots(&self, stdout: &mut dyn Write, mode: RenderMode, i: usize, left: usize, count: usize, show_cursor: bool) {
+        let zoom = self.zoom;
+        let (across, down) = self.cells_per_char();
+        for j in (left..self.width).step_by(across).take(count) {
+            let mut dots = [[false; 2]; 4];
+            // Drawn in the ghost's colour when only the ghost has dots.
+            let (mut alive, mut ghost) = (false, false);
+            for (r, row) in dots.iter_mut().enumerate().take(down / zoom) {
+                for (c, dot) in row.iter_mut().enumerate() {
+                    let (row, col) = (i + r * zoom, j + c * zoom);
+                    *dot = self.block_alive(row, col);
+                    alive |= *dot;
+                    if !*dot && self.block_ghost(row, col) {(*dot, ghost) = (true, true);}
                 }
             }
+            let glyph = if ghost && !alive {
+                let (r, g, b) = GHOST;
+                format!("{}{}{}", color::Fg(color::Rgb(r, g, b)), mode.glyph(dots), color::Fg(color::Reset))
+            } else {mode.glyph(dots).to_string()};
+            let (row, col) = self.selected_cell;
+            let selected = self.overlaps_selection(i..i + down, j..j + across);
+            if selected {write_selection_bg(stdout, true);}
+            if show_cursor && (i..i + down).contains(&row) && (j..j + across).contains(&col) {
+                write!(stdout, "{}{}{}", style::Invert, glyph, style::NoInvert).unwrap();
+            } else {write!(stdout, "{}", glyph).unwrap();}
+            if selected {write_selection_bg(stdout, false);}
         }
+    }
 
-        return ans;
+    /// Draws `count` characters from the one whose upper cell is at row
+    /// `i`, column `left` on, each a block with its upper and lower half
+    /// in the colours of the cells there: live cells in their paint
+    /// colour or the terminal's, the cursor and walls in their own.
+    fn write_half_blocks(&self, stdout: &mut dyn Write, i: usize, left: usize, count: usize, show_cursor: bool) {
+        let zoom = self.zoom;
+        for j in (left..self.width).step_by(zoom).take(count) {
+            let half = |row: usize| {
+                let rgb = |(r, g, b): (u8, u8, u8)| Some(color::Rgb(r, g, b));
+                match self.dot(row, j, show_cursor) {
+                    Dot::Dead => None,
+                    Dot::Marked(color) => Some(rgb(color)),
+                    Dot::Alive(color) => Some(color.and_then(rgb)),
+                    Dot::Cursor => Some(rgb(self.theme.cursor.unwrap_or(CURSOR_COLOR))),
+                    Dot::Wall => Some(rgb(self.theme.wall.unwrap_or(WALL_COLOR))),
+                }
+            };
+            // `None` for an empty half, `Some(None)` for one in the
+            // terminal's colour.
+            let (upper, lower) = (half(i), half(i + zoom));
+            let fg = |c: color::Rgb| color::Fg(c).to_string();
+            let bg = |c: color::Rgb| color::Bg(c).to_string();
+            // The terminal's own colour can only be the foreground.
+            let (colors, glyph) = match (upper, lower) {
+                (None, None) => (String::new(), ' '),
+                (Some(a), None) => (a.map(fg).unwrap_or_default(), '▀'),
+                (None, Some(b)) => (b.map(fg).unwrap_or_default(), '▄'),
+                (Some(a), Some(b)) if a == b => (a.map(fg).unwrap_or_default(), '█'),
+                (Some(None), Some(Some(b))) => (bg(b), '▀'),
+                (Some(Some(a)), Some(None)) => (bg(a), '▄'),
+                (Some(Some(a)), Some(Some(b))) => (fg(a) + &bg(b), '▀'),
+                (Some(None), Some(None)) => unreachable!(),
+            };
+            if colors.is_empty() {
+                write!(stdout, "{}", glyph).unwrap();
+            } else {
+                write!(stdout, "{}{}{}{}", colors, glyph, color::Fg(color::Reset), color::Bg(color::Reset)).unwrap();
+            }
+        }
     }
 
-    pub fn tick(&mut self) {
-        let mut next = vec![false; self.width * self.height];
-        for row in 0..self.height {
-            for col in 0..self.width {
-                let idx = self.get_index(row, col);
-                let cell = self.cells[idx];
-                let live_neighbours = self.live_neighbour_count(row, col);
-                next[idx] = match (cell, live_neighbours) {
-                    (true, x) if x < 2 => false,
-                    (true, 2) | (true, 3) => true,
-                    (true, x) if x > 3 => false,
-                    (false, 3) => true,
-                    (otherwise, _) => otherwise,
+    /// What the cell at `row`, `col`, or the block the zoom makes of it,
+    /// shows: the cursor over a live cell over a wall over a dead cell
+    /// with a colour.
+    fn dot(&self, row: usize, col: usize, show_cursor: bool) -> Dot {
+        let rows = row..(row + self.zoom).min(self.height);
+        let cols = col..(col + self.zoom).min(self.width);
+        let (cursor_row, cursor_col) = self.selected_cell;
+        if show_cursor && rows.contains(&cursor_row) && cols.contains(&cursor_col) {return Dot::Cursor;}
+        let block = || rows.clone().flat_map(|r| cols.clone().map(move |c| self.get_index(r, c)));
+        if let Some(ind) = block().find(|&ind| self.cells[ind]) {return Dot::Alive(self.cell_color(ind));}
+        if block().any(|ind| self.walls[ind]) {return Dot::Wall;}
+        if self.block_ghost(row, col) {return Dot::Marked(GHOST);}
+        block().find_map(|ind| self.dead_color(ind)).map_or(Dot::Dead, Dot::Marked)
+    }
+
+    /// Draws the board as sixel or kitty graphics over the room `render`
+    /// left for it, when that is the render mode. Must come after the lines
+    /// `render` wrote are on screen.
+    pub fn write_graphics(&self, out: &mut dyn Write) {
+        let mode = self.drawn_as();
+        if !mode.is_image() {return;}
+        let panes = match self.other_pane {
+            _ if self.photo_mode => vec![(self.view, true)],
+            Some(other) if self.focus_right => vec![(other.view, false), (self.view, true)],
+            Some(other) => vec![(self.view, true), (other.view, false)],
+            None => vec![(self.view, true)],
+        };
+        // The board starts below the title and the top border, right of
+        // the left border, or in the top left corner in photo mode.
+        let (x, y) = if self.photo_mode {(1, 1)} else {
+            let mut title = Vec::new();
+            write_title(&mut title, self.should_write_help);
+            (2, title.iter().filter(|&&b| b == b'\n').count() + 2)
+        };
+        let pane_width = self.screen_size().1 + 2;
+        for (i, &(view, focused)) in panes.iter().enumerate() {
+            let picture = self.picture(view, self.show_cursor && focused && !self.photo_mode);
+            let image = match mode {
+                RenderMode::Kitty => kitty::encode(&picture, i as u32 + 1),
+                _ => sixel::encode(&picture),
+            };
+            write!(out, "{}{}", termion::cursor::Goto((x + i * pane_width) as u16, y as u16), image).unwrap();
+        }
+    }
+
+    /// The view with its top left cell at `view` as an image, each cell,
+    /// or block of cells when zoomed out, a square of pixels.
+    fn picture(&self, (top, left): (usize, usize), show_cursor: bool) -> Picture {
+        let (rows, cols) = self.visible_size();
+        let (block_rows, block_cols) = (rows.div_ceil(self.zoom), cols.div_ceil(self.zoom));
+        let scale = self.image_scale();
+        let (width, height) = (block_cols * scale, block_rows * scale);
+        let mut pixels = vec![0u8; width * height];
+        let mut palette = vec![self.dead_rgb()];
+        for block_row in 0..block_rows {
+            for block_col in 0..block_cols {
+                let rgb = match self.dot(top + block_row * self.zoom, left + block_col * self.zoom, show_cursor) {
+                    Dot::Dead => self.dead_rgb(),
+                    Dot::Marked(color) => color,
+                    Dot::Alive(color) => color.unwrap_or(FADE_ALIVE),
+                    Dot::Cursor => self.theme.cursor.unwrap_or(CURSOR_COLOR),
+                    Dot::Wall => self.theme.wall.unwrap_or(WALL_COLOR),
                 };
+                let color = match palette.iter().position(|&c| c == rgb) {
+                    Some(color) => color,
+                    None => {
+                        palette.push(rgb);
+                        palette.len() - 1
+                    }
+                };
+                for row in block_row * scale..(block_row + 1) * scale {
+                    pixels[row * width + block_col * scale..row * width + (block_col + 1) * scale].fill(color as u8);
+                }
             }
         }
-        if self.history.len() >= self.history_len {self.history.pop_front();}
-        self.history.push_back(self.cells.clone());
-        self.cells = next;
+        Picture { pixels, width, height, palette }
     }
 
-    pub fn tick_back(&mut self) -> Result<&str, &str> {
-        match self.history.pop_back() {
-            Some(x) => {self.cells = x},
-            None => {return Err("No more moves in history!");},
-        };
-        return Ok("Returned to previous step");
+    /// How many pixels across and down a cell, or the block of cells the
+    /// zoom makes, takes up in an image: as many as let the whole board
+    /// fit the view, up to `IMAGE_SCALE_MAX`, and at least one.
+    fn image_scale(&self) -> usize {
+        let Some((rows, cols)) = self.pane_size() else {return IMAGE_SCALE_MAX};
+        let (char_width, char_height) = self.char_pixels;
+        let (blocks_down, blocks_across) = (self.height.div_ceil(self.zoom), self.width.div_ceil(self.zoom));
+        (rows * char_height / blocks_down).min(cols * char_width / blocks_across).clamp(1, IMAGE_SCALE_MAX)
     }
 
-    pub fn toggle_selected_cell(&mut self) {
-        self.set_cell(self.selected_cell.0,
-                      self.selected_cell.1,
-                      !self.get_cell(self.selected_cell.0,
-                                    self.selected_cell.1,));
+    /// Whether any cell of the block the zoom makes of the cell at `row`,
+    /// `col` and those below and to the right of it is alive.
+    fn block_alive(&self, row: usize, col: usize) -> bool {
+        (row..(row + self.zoom).min(self.height))
+            .any(|r| (col..(col + self.zoom).min(self.width)).any(|c| self.cells[self.get_index(r, c)]))
     }
 
-    pub fn clear(&mut self) {
-        self.cells = vec![false; self.width * self.height];
+    /// Whether the ghost has a cell in the block the zoom makes of the
+    /// cell at `row`, `col` and those below and to the right of it.
+    fn block_ghost(&self, row: usize, col: usize) -> bool {
+        self.ghost.is_some() && (row..(row + self.zoom).min(self.height))
+            .any(|r| (col..(col + self.zoom).min(self.width)).any(|c| self.is_ghost(r, c)))
     }
-}
 
+    /// Whether the ghost has a cell at `row`, `col`.
+    fn is_ghost(&self, row: usize, col: usize) -> bool {
+        let Some(ghost) = &self.ghost else {return false};
+        let (top, left) = self.selected_cell;
+        row >= top && col >= left && ghost.cells.binary_search(&(row - top, col - left)).is_ok()
+    }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+    /// The lines below the board about the marker, notes, rules and so on.
+    fn render_status(&self, stdout: &mut dyn Write) {
+        if let Some((row, col)) = self.marker {
+            let rows = self.selected_cell.0 as isize - row as isize;
+            let cols = self.selected_cell.1 as isize - col as isize;
+            let euclidean = ((rows * rows + cols * cols) as f64).sqrt();
+            write!(stdout, "Marker: {:+} rows, {:+} columns, Chebyshev {}, Euclidean {:.2}\n\r",
+                   rows, cols, rows.abs().max(cols.abs()), euclidean).unwrap();
+        }
+        if let Some(note) = self.notes.get(&self.selected_cell) {
+            write!(stdout, "Note: {}\n\r", note).unwrap();
+        }
+        if !self.regions.is_empty() {
+            let (row, col) = self.selected_cell;
+            write!(stdout, "Rule here: {}\n\r", self.rule_at(row, col)).unwrap();
+        } else if self.rule != Rule::conway() {
+            write!(stdout, "Rule: {}\n\r", self.rule).unwrap();
+        }
+        match self.topology {
+            Topology::Bounded => {}
+            Topology::Torus => write!(stdout, "Edges wrap around (torus)\n\r").unwrap(),
+            Topology::Unbounded => {
+                write!(stdout, "Unbounded plane: board at {},{}, {} live cells beyond the edges\n\r",
+                       self.origin.1, self.origin.0, self.beyond.len()).unwrap();
+            }
+        }
+        if let Some((matching, missing, extra)) = self.reference_counts() {
+            write!(stdout, "Reference: {} cells match, {} missing, {} extra\n\r", matching, missing, extra).unwrap();
+        }
+        if let Some(brush) = self.brush {
+            let (name, (r, g, b)) = PAINT_COLORS[brush as usize];
+            write!(stdout, "Paint: {}{} {}{}\n\r",
+                   color::Fg(color::Rgb(r, g, b)), name, self.glyphs.alive, color::Fg(color::Reset)).unwrap();
+        }
+    }
 
-    let stdin = async_stdin();
-    let mut stdout = stdout().into_raw_mode().unwrap();
-    let mut it = stdin.keys();
+    /// Draws only the cells, without title, borders, cursor or status.
+    fn render_photo(&self, stdout: &mut dyn Write) {
+        let (rows, cols) = self.visible_size();
+        let (top, left) = self.view;
+        if (self.drawn_as(), self.zoom) != (RenderMode::Cells, 1) {
+            let (screen_rows, screen_cols) = self.screen_size();
+            let (_, down) = self.cells_per_char();
+            for row in 0..screen_rows {
+                self.write_screen_row(stdout, top + row * down, left, screen_cols, false);
+                if row + 1 < screen_rows {write!(stdout, "\n\r").unwrap();}
+            }
+            return;
+        }
+        for i in top..top + rows {
+            for j in left..left + cols {
+                let ind = self.get_index(i, j);
+                if self.walls[ind] {
+                    write_in(stdout, self.theme.wall, &self.glyphs.wall);
+                } else {self.write_cell(stdout, ind);}
+            }
+            if i + 1 < top + rows {write!(stdout, "\n\r").unwrap();}
+        }
+    }
 
-    if (args.len() >= 2) && args[1].to_lowercase().contains("help") {
-        write!(stdout, "\r{}\n\r", "Game Of Life");
-        write!(stdout, "\r{}\n\r", "Args: game-of-life <rows> <cols>");
-        exit(0);
+    /// Attaches `text` to the cell, or removes its note when empty.
+    pub fn set_note(&mut self, row: usize, col: usize, text: &str) {
+        if text.is_empty() {
+            self.notes.remove(&(row, col));
+        } else {
+            self.notes.insert((row, col), text.to_string());
+        }
     }
 
-    let mut sz_rows = SIZE_ROWS_DEFAULT;
-    let mut sz_cols = SIZE_COLS_DEFAULT;
-    if args.len() >= 3 {
-        sz_rows = match args[1].parse() {
-            Ok(r) => r,
-            Err(_) => SIZE_ROWS_DEFAULT,
-        };
-        sz_cols = match args[2].parse() {
-            Ok(c) => c,
-            Err(_) => SIZE_ROWS_DEFAULT,
+    pub fn render_notes(&self, stdout: &mut dyn Write) {
+        write!(stdout, "Notes:\n\r").unwrap();
+        if self.notes.is_empty() {
+            write!(stdout, "  none yet, Shift+N adds one at the cursor\n\r").unwrap();
+        }
+        for ((row, col), text) in &self.notes {
+            write!(stdout, "  {},{}: {}\n\r", row, col, text).unwrap();
+        }
+        write!(stdout, "Press any key to return\n\r").unwrap();
+    }
+
+    /// Shows `pattern` centered under the live cells as a target to
+    /// rebuild, or removes the reference with `None`.
+    pub fn set_reference(&mut self, pattern: Option<&Pattern>) {
+        self.reference = pattern.map(|p| {
+            let mut target = Universe::new(self.width, self.height);
+            place_centered(&mut target, p);
+            target.cells
+        });
+    }
+
+    /// Live cells on the reference, reference cells that are dead and live
+    /// cells off the reference.
+    pub fn reference_counts(&self) -> Option<(usize, usize, usize)> {
+        let reference = self.reference.as_ref()?;
+        let mut counts = (0, 0, 0);
+        for (&alive, &target) in self.cells.iter().zip(reference) {
+            match (alive, target) {
+                (true, true) => counts.0 += 1,
+                (false, true) => counts.1 += 1,
+                (true, false) => counts.2 += 1,
+                (false, false) => {}
+            }
+        }
+        Some(counts)
+    }
+
+    pub fn save_view(&mut self, name: &str) {
+        self.saved_views.insert(name.to_string(), SavedView { view: self.view, cursor: self.selected_cell });
+    }
+
+    pub fn restore_view(&mut self, name: &str) -> Result<(), String> {
+        let saved = *self.saved_views.get(name).ok_or(format!("No view named `{}`", name))?;
+        self.view = saved.view;
+        self.selected_cell = saved.cursor;
+        // The terminal may have shrunk since the view was saved.
+        self.scroll_to(saved.cursor.0, saved.cursor.1);
+        Ok(())
+    }
+
+    /// Draws a live or dead cell, in a fading colour if it just changed
+    /// and in the colour the colouring gives it if any.
+    fn write_cell(&self, stdout: &mut dyn Write, ind: usize) {
+        let coloring = || {
+            let color = if self.cells[ind] {self.cell_color(ind)} else {self.dead_color(ind)};
+            color.map(|(r, g, b)| color::Rgb(r, g, b))
         };
+        match self.fade_color(ind).or_else(coloring) {
+            Some(c) => write!(stdout, "{}{}{}", color::Fg(c), self.glyphs.alive, color::Fg(color::Reset)).unwrap(),
+            None if self.cells[ind] => write!(stdout, "{}", self.glyphs.alive).unwrap(),
+            None => write_in(stdout, self.theme.dead, &self.glyphs.dead),
+        }
     }
 
-    let mut game = Universe::new(sz_rows, sz_cols);
-    game.show_cursor = true;
-    game.render(&mut stdout);
+    /// The colour of the live cell `ind` under the colouring, `None` for
+    /// the terminal's own.
+    fn cell_color(&self, ind: usize) -> Option<(u8, u8, u8)> {
+        match self.coloring {
+            Coloring::Paint => self.colors[ind].map(|c| PAINT_COLORS[c as usize].1).or(self.theme.alive),
+            Coloring::Age => {
+                let old = self.ages[ind].min(AGE_OLD_AFTER) as f32 / AGE_OLD_AFTER as f32;
+                let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * old) as u8;
+                Some((mix(AGE_YOUNG.0, AGE_OLD.0), mix(AGE_YOUNG.1, AGE_OLD.1), mix(AGE_YOUNG.2, AGE_OLD.2)))
+            }
+            Coloring::Changes => self.history.previous().is_some_and(|previous| !previous[ind]).then_some(BORN_COLOR),
+        }
+    }
 
-    let mut tick_millis: u64 = 200;
+    /// The colour live cells are shown in when nothing else colours them.
+    fn alive_rgb(&self) -> (u8, u8, u8) {
+        self.theme.alive.unwrap_or(FADE_ALIVE)
+    }
 
-    loop {
-        sleep(Duration::from_millis(1));
-        let b = it.next();
+    /// The colour dead cells are shown in, where they have one.
+    fn dead_rgb(&self) -> (u8, u8, u8) {
+        self.theme.dead.unwrap_or(FADE_DEAD)
+    }
 
-        match b {
-            Some(x) => match x.unwrap() {
-                Key::Up => {
-                    game.move_cursor(-1, 0);
-                    game.render(&mut stdout);
-                    stdout.flush().unwrap();
-                }
-                Key::Down => {
-                    game.move_cursor(1, 0);
-                    game.render(&mut stdout);
-                    stdout.flush().unwrap();
-                }
-                Key::Right => {
-                    game.move_cursor(0, 1);
-                    game.render(&mut stdout);
-                    stdout.flush().unwrap();
-                }
-                Key::Left => {
-                    game.move_cursor(0, -1);
-                    game.render(&mut stdout);
-                    stdout.flush().unwrap();
-                }
-                Key::Char('r') => {
-                    game.is_running = true;
-                }
-                Key::Char('s') => {
-                    game.is_running = false;
-                }
-                Key::Char('n') => {
-                    game.tick();
-                    game.render(&mut stdout);
-                    stdout.flush().unwrap();
-                }
-                Key::Char('p') => {
-                    match game.tick_back() {
-                        Ok(_) => {game.render(&mut stdout);}
-                        Err(msg) => {write!(stdout, "\r{}{}",
-                                                     termion::clear::CurrentLine,
-                                                     msg).unwrap();}
-                    };
-                    stdout.flush().unwrap();
-                }
-                Key::Char('c') => {
-                    game.clear();
-                    game.render(&mut stdout);
-                    stdout.flush().unwrap();
-                }
-                Key::Char('t') => {
-                    game.show_cursor = !game.show_cursor;
-                    game.render(&mut stdout);
-                    stdout.flush().unwrap();
-                }
-                Key::Char(' ') => {
-                    game.toggle_selected_cell();
-                    game.render(&mut stdout);
-                    stdout.flush().unwrap();
-                }
-                Key::Char('-') => {tick_millis += 50;}
-                Key::Char('+') => {tick_millis = max(tick_millis - 50, 50);}
-                Key::Char('q') => break,
-                other => {
-                    write!(stdout, "Unexpected key: {:?}", other).unwrap();
-                    stdout.flush().unwrap();
-                }
+    /// The colour of the dead cell `ind` under the colouring or, if it
+    /// died recently enough, of its trail; `None` when it is drawn as
+    /// usual.
+    fn dead_color(&self, ind: usize) -> Option<(u8, u8, u8)> {
+        let coloring = match self.coloring {
+            Coloring::Changes => self.history.previous().is_some_and(|previous| previous[ind]).then_some(DIED_COLOR),
+            Coloring::Paint | Coloring::Age => None,
+        };
+        coloring.or_else(|| {
+            let dead_for = self.dead_for[ind];
+            if !(1..=self.trail_len).contains(&dead_for) {return None;}
+            // Dimmer the longer ago, but never quite the dead colour.
+            let faded = (dead_for - 1) as f32 / self.trail_len as f32;
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * faded) as u8;
+            let dead = self.dead_rgb();
+            Some((mix(TRAIL_COLOR.0, dead.0), mix(TRAIL_COLOR.1, dead.1), mix(TRAIL_COLOR.2, dead.2)))
+        })
+    }
+
+    /// Draws with the colours of `theme`, and its glyphs if it has any;
+    /// glyphs the theme before brought go back to the default ones.
+    pub fn set_theme(&mut self, theme: Theme) {
+        match &theme.glyphs {
+            Some(glyphs) => self.set_glyphs(glyphs.clone()),
+            None if self.theme.glyphs.as_ref() == Some(&self.glyphs) => self.set_glyphs(Glyphs::default()),
+            None => {}
+        }
+        match theme.borders {
+            Some(borders) => self.set_borders(borders),
+            None if self.theme.borders == Some((self.borders, self.pane_borders)) => {
+                self.set_borders((glyphs::DOUBLE, glyphs::SINGLE));
             }
-            _ => {}
+            None => {}
+        }
+        self.theme = theme;
+    }
+
+    /// Draws cells with `glyphs`, unless only ASCII is drawn and they are
+    /// not all ASCII. Keeps the view on the same cells, which may now be
+    /// another number of columns wide.
+    pub fn set_glyphs(&mut self, glyphs: Glyphs) {
+        if self.ascii && !glyphs.is_ascii() {return;}
+        self.keep_middle(|game| game.glyphs = glyphs);
+    }
+
+    /// Draws the focused pane and the other one with `borders`, unless
+    /// only ASCII is drawn and they are not ASCII.
+    pub fn set_borders(&mut self, (borders, pane_borders): (Borders, Borders)) {
+        if self.ascii && !(borders.is_ascii() && pane_borders.is_ascii()) {return;}
+        (self.borders, self.pane_borders) = (borders, pane_borders);
+    }
+
+    /// Draws only ASCII from now on: plain borders and marks, and cells in
+    /// the ASCII glyph set.
+    pub fn set_ascii(&mut self) {
+        self.ascii = true;
+        self.set_glyphs(Glyphs::parse("ascii").unwrap());
+        self.set_borders((glyphs::ASCII, glyphs::ASCII_DOTTED));
+    }
+
+    /// Turns trails of dying cells on, `TRAIL_LEN_DEFAULT` generations
+    /// long, or off. Returns whether they are on.
+    pub fn toggle_trails(&mut self) -> bool {
+        self.trail_len = if self.trail_len == 0 {TRAIL_LEN_DEFAULT} else {0};
+        self.trail_len > 0
+    }
+
+    /// The colour between dead and alive of a cell that was born or died
+    /// in the last generation, while fading.
+    fn fade_color(&self, ind: usize) -> Option<color::Rgb> {
+        let progress = self.fade?;
+        let previous = self.history.previous()?;
+        let painted = |color: Option<u8>| color.map_or(self.alive_rgb(), |c| PAINT_COLORS[c as usize].1);
+        let (from, to) = match (previous[ind], self.cells[ind]) {
+            (false, true) => (self.dead_rgb(), self.cell_color(ind).unwrap_or(FADE_ALIVE)),
+            (true, false) => (painted(self.history.previous_color(&self.colors, ind)), self.dead_rgb()),
+            _ => return None,
+        };
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * progress) as u8;
+        Some(color::Rgb(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2)))
+    }
+
+    /// Limits drawing to `rows` lines of `cols` terminal columns, e.g. to
+    /// fit the terminal.
+    pub fn set_view_size(&mut self, rows: usize, cols: usize) {
+        self.view_size = Some((rows.max(1), cols.max(1)));
+        // A larger window may now reach past the bottom or right edge.
+        let (rows, cols) = self.visible_size();
+        let (max_top, max_left) = (self.height - rows, self.width - cols);
+        let clamp = |(top, left): (usize, usize)| (top.min(max_top), left.min(max_left));
+        self.view = clamp(self.view);
+        if let Some(other) = &mut self.other_pane {other.view = clamp(other.view);}
+        self.scroll_to(self.selected_cell.0, self.selected_cell.1);
+    }
+
+    /// Sizes the view for a terminal of `cols` by `rows` characters,
+    /// leaving room for the border and the lines around the board.
+    pub fn fit_view(&mut self, cols: u16, rows: u16) {
+        self.char_pixels = terminal::char_pixels().unwrap_or(CHAR_PIXELS_DEFAULT);
+        self.set_view_size((rows as usize).saturating_sub(VIEW_MARGIN_ROWS + FIT_STATUS_ROWS),
+                           (cols as usize).saturating_sub(2));
+    }
+
+    /// How many rows and columns of cells are drawn.
+    fn visible_size(&self) -> (usize, usize) {
+        match self.window(self.drawn_as()) {
+            Some((rows, cols)) => (rows.min(self.height), cols.clamp(1, self.width)),
+            None => (self.height, self.width),
+        }
+    }
+
+    /// How many lines and terminal columns a pane takes up inside its
+    /// borders, `None` when the view is not limited.
+    fn pane_size(&self) -> Option<(usize, usize)> {
+        let (rows, cols) = self.view_size?;
+        // Split panes share the width, less a border.
+        Some((rows, if self.other_pane.is_some() {cols.saturating_sub(2) / 2} else {cols}))
+    }
+
+    /// How many rows and columns of cells the view has room for in `mode`
+    /// at the zoom, `None` when it is not limited.
+    fn window(&self, mode: RenderMode) -> Option<(usize, usize)> {
+        let (rows, cols) = self.pane_size()?;
+        Some(self.cells_in(mode, rows, cols / self.char_width(mode)))
+    }
+
+    /// How many terminal columns one character takes up in `mode`: as many
+    /// as a glyph when drawing cells.
+    fn char_width(&self, mode: RenderMode) -> usize {
+        match mode {
+            RenderMode::Cells | RenderMode::Auto => self.glyphs.width(),
+            _ => mode.char_width(),
+        }
+    }
+
+    /// How many rows and columns of cells `rows` lines of `cols`
+    /// characters stand for in `mode` at the zoom.
+    fn cells_in(&self, mode: RenderMode, rows: usize, cols: usize) -> (usize, usize) {
+        if mode.is_image() {
+            let (char_width, char_height) = self.char_pixels;
+            let scale = self.image_scale();
+            return (rows * char_height / scale * self.zoom, cols * char_width / scale * self.zoom);
+        }
+        let (across, down) = mode.cells_per_char();
+        (rows * down * self.zoom, cols * across * self.zoom)
+    }
+
+    /// The render mode the board is drawn in, with `RenderMode::Auto`
+    /// resolved to the one the whole board fits the view in.
+    pub fn drawn_as(&self) -> RenderMode {
+        self.render.resolve(|mode| {
+            self.window(mode).is_none_or(|(rows, cols)| rows >= self.height && cols >= self.width)
+        })
+    }
+
+    /// How many cells across and down one character stands for in the
+    /// render mode at the zoom, at least one even for large image pixels.
+    fn cells_per_char(&self) -> (usize, usize) {
+        let (down, across) = self.cells_in(self.drawn_as(), 1, 1);
+        (across.max(1), down.max(1))
+    }
+
+    /// How many rows and columns of characters the drawn cells take up.
+    fn screen_size(&self) -> (usize, usize) {
+        let (rows, cols) = self.visible_size();
+        if self.drawn_as().is_image() {
+            let (char_width, char_height) = self.char_pixels;
+            let scale = self.image_scale();
+            return ((rows.div_ceil(self.zoom) * scale).div_ceil(char_height),
+                    (cols.div_ceil(self.zoom) * scale).div_ceil(char_width));
         }
+        let (across, down) = self.cells_per_char();
+        (rows.div_ceil(down), cols.div_ceil(across))
+    }
+
+    /// Zooms out so that each character stands for twice as many cells
+    /// across and down, or back in, keeping the middle of the view in the
+    /// middle. Returns `false` when already at the furthest or closest.
+    pub fn zoom_by(&mut self, zoom_out: bool) -> bool {
+        let zoom = if zoom_out {self.zoom * 2} else {self.zoom / 2};
+        if !(1..=ZOOM_MAX).contains(&zoom) {return false;}
+        self.keep_middle(|game| game.zoom = zoom);
+        true
+    }
+
+    /// Draws the board in another mode, keeping the middle of the view in
+    /// the middle.
+    pub fn set_render(&mut self, render: RenderMode) {
+        self.keep_middle(|game| game.render = render);
+    }
+
+    /// Makes a change to how much of the board is drawn and moves the view
+    /// so that the cell in its middle stays there.
+    fn keep_middle(&mut self, change: impl FnOnce(&mut Universe)) {
+        let (rows, cols) = self.visible_size();
+        let middle = (self.view.0 + rows / 2, self.view.1 + cols / 2);
+        change(self);
+        self.center_view(middle.0, middle.1);
+    }
+
+    /// Moves the view `rows` characters down and `cols` right, as far as
+    /// the edges of the board allow, leaving the cursor where it is.
+    pub fn pan(&mut self, rows: isize, cols: isize) {
+        let (visible_rows, visible_cols) = self.visible_size();
+        let (down, across) = self.cells_in(self.drawn_as(), rows.unsigned_abs(), cols.unsigned_abs());
+        let pan = |start: usize, by: isize, len: usize, visible: usize| {
+            start.saturating_add_signed(by).min(len - visible)
+        };
+        let (rows, cols) = (rows.signum() * down.max(1) as isize, cols.signum() * across.max(1) as isize);
+        self.view = (pan(self.view.0, rows, self.height, visible_rows), pan(self.view.1, cols, self.width, visible_cols));
+    }
+
+    /// Which pane, `true` for the right one, and cell are drawn at a
+    /// terminal position, counting from 1 like mouse events do.
+    pub fn cell_at(&self, x: u16, y: u16) -> Option<(bool, (usize, usize))> {
+        if self.photo_mode {return None;}
+        let mut title = Vec::new();
+        write_title(&mut title, self.should_write_help);
+        // The board starts below the title and the top border.
+        let top = title.iter().filter(|&&b| b == b'\n').count() + 2;
+        let (rows, cols) = self.screen_size();
+        let row = (y as usize).checked_sub(top).filter(|&r| r < rows)?;
+        // Every pane is a border, the characters and another border.
+        let width = self.char_width(self.drawn_as());
+        let pane_width = width * cols + 2;
+        let x = (x as usize).checked_sub(1)?;
+        let right = match x / pane_width {
+            0 => false,
+            1 if self.other_pane.is_some() => true,
+            _ => return None,
+        };
+        let col = (x % pane_width).checked_sub(1).filter(|&c| c < width * cols)? / width;
+        let (top, left) = if right == self.focus_right {self.view} else {self.other_pane?.view};
+        let (rows, cols) = self.cells_in(self.drawn_as(), row, col);
+        let cell = ((top + rows).min(self.height - 1), (left + cols).min(self.width - 1));
+        Some((right, cell))
+    }
+
+    /// Splits the screen into two panes onto the board, both starting at
+    /// the current view, or joins them again keeping the focused one.
+    pub fn toggle_split(&mut self) {
+        self.other_pane = match self.other_pane {
+            Some(_) => None,
+            None => Some(SavedView { view: self.view, cursor: self.selected_cell }),
+        };
+        self.focus_right = false;
+        self.scroll_to(self.selected_cell.0, self.selected_cell.1);
+    }
+
+    /// Moves the focus, with its own view and cursor, to the other pane.
+    pub fn switch_pane(&mut self) -> bool {
+        let Some(other) = self.other_pane.as_mut() else {return false};
+        let current = SavedView { view: self.view, cursor: self.selected_cell };
+        (self.view, self.selected_cell) = (other.view, other.cursor);
+        *other = current;
+        self.focus_right = !self.focus_right;
+        true
+    }
+
+    /// Moves the view as little as possible to bring the cell into it.
+    pub fn scroll_to(&mut self, row: usize, col: usize) {
+        let (rows, cols) = self.visible_size();
+        let scroll = |start: usize, pos: usize, len: usize| {
+            if pos < start {pos} else if pos >= start + len {pos + 1 - len} else {start}
+        };
+        self.view = (scroll(self.view.0, row, rows), scroll(self.view.1, col, cols));
+    }
+
+    /// Moves the view so the cell is in its middle, as far as the edges
+    /// of the board allow.
+    pub fn center_view(&mut self, row: usize, col: usize) {
+        let (rows, cols) = self.visible_size();
+        self.view = (row.saturating_sub(rows / 2).min(self.height - rows),
+                     col.saturating_sub(cols / 2).min(self.width - cols));
+    }
 
-        if game.is_running {
-            game.tick();
-            game.render(&mut stdout);
-            // write!(stdout, "{}", "game was updated by regular tick").unwrap();
-            stdout.flush().unwrap();
-            sleep(Duration::from_millis(tick_millis));
+    /// The average position of the live cells.
+    pub fn centroid(&self) -> Option<(usize, usize)> {
+        let cells = self.live_cells();
+        if cells.is_empty() {return None;}
+        let (rows, cols) = cells.iter().fold((0, 0), |(r, c), &(row, col)| (r + row, c + col));
+        Some((rows / cells.len(), cols / cells.len()))
+    }
+
+    /// Moves the cursor, wrapping around at the edges; on an unbounded
+    /// plane moving off the board moves the board instead.
+    pub fn move_cursor(&mut self, r: isize, c: isize) {
+        if self.topology == Topology::Unbounded {
+            let (row, col) = (self.selected_cell.0 as isize + r, self.selected_cell.1 as isize + c);
+            let past = |pos: isize, len: usize| if pos < 0 {pos} else {(pos - (len as isize - 1)).max(0)};
+            let (by_rows, by_cols) = (past(row, self.height), past(col, self.width));
+            if (by_rows, by_cols) != (0, 0) {self.move_board(by_rows, by_cols);}
+            self.selected_cell = ((row - by_rows) as usize, (col - by_cols) as usize);
+            self.scroll_to(self.selected_cell.0, self.selected_cell.1);
+            return;
         }
+        // Moving off an edge comes back on the other, however far.
+        self.selected_cell.0 = (self.selected_cell.0 as isize + r).rem_euclid(self.height as isize) as usize;
+        self.selected_cell.1 = (self.selected_cell.1 as isize + c).rem_euclid(self.width as isize) as usize;
+        self.scroll_to(self.selected_cell.0, self.selected_cell.1);
     }
 
-    write!(stdout, "{}", termion::cursor::Show).unwrap();
+    fn is_in_bounds(&self, row: isize, col: isize) -> bool {
+        (row >= 0) &&
+        (row < self.height as isize) &&
+        (col >= 0) &&
+        (col < self.width as isize)
+    }
+
+    /// The cell at `row`, `col`, which may lie one step off the board:
+    /// wrapped around on a torus, `None` otherwise; cells beyond an
+    /// unbounded board are only looked at by `next_unbounded`.
+    fn neighbour_at(&self, row: isize, col: isize) -> Option<(usize, usize)> {
+        match self.topology {
+            Topology::Torus => Some((row.rem_euclid(self.height as isize) as usize,
+                                     col.rem_euclid(self.width as isize) as usize)),
+            _ if self.is_in_bounds(row, col) => Some((row as usize, col as usize)),
+            Topology::Bounded | Topology::Unbounded => None,
+        }
+    }
+
+    /// Switches to the topology `by` places along bounded, torus and
+    /// unbounded. The cells beyond an unbounded board are dropped when
+    /// leaving it.
+    pub fn cycle_topology(&mut self, by: isize) {
+        let topologies = [Topology::Bounded, Topology::Torus, Topology::Unbounded];
+        let at = topologies.iter().position(|&t| t == self.topology).unwrap() as isize;
+        self.set_topology(topologies[(at + by).rem_euclid(topologies.len() as isize) as usize]);
+    }
+
+    /// Switches to `topology`, dropping the cells beyond an unbounded
+    /// board when leaving it.
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+        if self.topology != Topology::Unbounded {
+            self.beyond.clear();
+        }
+    }
+
+    pub fn live_cells(&self) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for row in 0..self.height {
+            for col in 0..self.width {
+                if self.get_cell(row, col) {cells.push((row, col));}
+            }
+        }
+        cells
+    }
+
+    pub fn get_cell(&self, row: usize, col: usize) -> bool {
+        let ind = self.get_index(row, col);
+        self.cells[ind]
+    }
+
+    /// Sets the cell, painting it with the brush colour when made alive.
+    pub fn set_cell(&mut self, row: usize, col: usize, val: bool) {
+        let ind = self.get_index(row, col);
+        if self.cells[ind] != val {
+            self.population = if val {self.population + 1} else {self.population - 1};
+            self.ages[ind] = 0;
+            // Cells killed by hand leave no trail.
+            self.dead_for[ind] = if val {0} else {u32::MAX};
+        }
+        self.cells[ind] = val;
+        self.colors[ind] = if val {self.brush} else {None};
+    }
+
+    /// Switches the paint colour to the next one, then back to none.
+    pub fn next_brush(&mut self) {
+        self.brush = match self.brush {
+            None => Some(0),
+            Some(c) if (c as usize) + 1 < PAINT_COLORS.len() => Some(c + 1),
+            Some(_) => None,
+        };
+    }
+
+    /// Paints the live cell under the cursor with the brush colour.
+    pub fn paint_selected_cell(&mut self) -> Result<(), &'static str> {
+        let ind = self.get_index(self.selected_cell.0, self.selected_cell.1);
+        if !self.cells[ind] {return Err("Only live cells can be painted");}
+        self.colors[ind] = self.brush;
+        Ok(())
+    }
+
+    /// The most common colour among the live neighbours that give birth to
+    /// a cell. Ties go to painted colours, in palette order.
+    fn inherited_color(&self, row: usize, col: usize) -> Option<u8> {
+        let mut counts = [0; PAINT_COLORS.len() + 1];
+        let (row, col) = (row as isize, col as isize);
+        for r in (row - 1)..=(row + 1) {
+            for c in (col - 1)..=(col + 1) {
+                if (r, c) == (row, col) {continue;}
+                let Some((r, c)) = self.neighbour_at(r, c) else {continue};
+                let ind = self.get_index(r, c);
+                if self.cells[ind] {
+                    counts[self.colors[ind].map_or(PAINT_COLORS.len(), |c| c as usize)] += 1;
+                }
+            }
+        }
+        let best = (0..counts.len()).rev().max_by_key(|&i| counts[i])?;
+        if best < PAINT_COLORS.len() && counts[best] > 0 {Some(best as u8)} else {None}
+    }
+
+    fn live_neighbour_count(&self, row: usize, col: usize) -> usize {
+        let mut ans: usize = 0;
+        let row = row as isize;
+        let col = col as isize;
+
+        for r in (row - 1)..=(row + 1) {
+            for c in (col - 1)..=(col + 1) {
+                if (r, c) == (row, col) {continue;}
+                if let Some((r, c)) = self.neighbour_at(r, c) {
+                    let wall = self.walls_alive && self.is_wall(r, c);
+                    ans += (self.get_cell(r, c) || wall) as usize;
+                }
+            }
+        }
+
+        ans
+    }
+
+    /// Advances one generation. Survivors keep their paint colour and
+    /// newborn cells inherit the majority colour of their parents.
+    pub fn tick(&mut self) {
+        let simple = self.regions.is_empty() && !self.walls.contains(&true);
+        let mut beyond = None;
+        let next = match self.engine {
+            _ if self.topology == Topology::Unbounded => {
+                let (next, next_beyond) = self.next_unbounded();
+                beyond = Some(next_beyond);
+                next
+            }
+            Engine::Lookup if simple => {
+                if self.lookup.as_ref().is_none_or(|l| l.rule != self.rule) {
+                    self.lookup = Some(Arc::new(LookupTable::new(self.rule)));
+                }
+                self.lookup.as_ref().unwrap().step(&self.cells, self.width, self.height, self.topology == Topology::Torus)
+            }
+            Engine::Packed if simple => {
+                let wrap = self.topology == Topology::Torus;
+                Bitboard::from_cells(&self.cells, self.width, self.height).step(self.rule, wrap).to_cells()
+            }
+            Engine::HashLife if self.hashlife_problem().is_none() => {
+                let life = self.hashlife();
+                let next = life.lock().unwrap().advance(&self.cells, self.width, self.height, 1);
+                next
+            }
+            _ => self.next_naive(),
+        };
+
+        let colors = if self.colors.iter().any(Option::is_some) {
+            (0..next.len()).map(|idx| {
+                if !next[idx] {None}
+                else if self.cells[idx] {self.colors[idx]}
+                else {self.inherited_color(idx / self.width, idx % self.width)}
+            }).collect()
+        } else {vec![None; next.len()]};
+        self.advance(next, colors);
+        if let Some(beyond) = beyond {self.beyond = beyond;}
+    }
+
+    /// Why HashLife cannot run this board, if it cannot.
+    fn hashlife_problem(&self) -> Option<&'static str> {
+        if !self.regions.is_empty() || self.walls.contains(&true) {
+            Some("HashLife cannot run boards with walls or regions")
+        } else if self.topology == Topology::Torus {
+            Some("HashLife cannot run a torus")
+        } else if self.rule.next(false, 0) {
+            Some("HashLife cannot run rules where cells are born with no neighbours (B0)")
+        } else {None}
+    }
+
+    /// The HashLife cache for the rule, started over when the rule changed.
+    fn hashlife(&mut self) -> Arc<Mutex<HashLife>> {
+        let stale = self.hashlife.as_ref().is_none_or(|life| life.lock().unwrap().rule != self.rule);
+        if stale {self.hashlife = Some(Arc::new(Mutex::new(HashLife::new(self.rule))));}
+        self.hashlife.clone().unwrap()
+    }
+
+    /// Runs `generations` generations at once with HashLife, keeping the
+    /// board before the jump in the history. Only unbounded boards jump:
+    /// HashLife runs the whole plane, which an edge or a torus would stop
+    /// being after a step.
+    pub fn jump(&mut self, generations: u64) -> Result<(), String> {
+        if self.engine != Engine::HashLife {
+            return Err("Jumping ahead needs the HashLife engine (--engine hashlife)".to_string());
+        }
+        if self.topology != Topology::Unbounded {
+            return Err("Jumping ahead needs an unbounded board (--topology unbounded)".to_string());
+        }
+        if let Some(problem) = self.hashlife_problem() {return Err(problem.to_string());}
+        let mut live: Vec<sparse::Cell> = self.beyond.iter().copied().collect();
+        live.extend((0..self.cells.len()).filter(|&i| self.cells[i]).map(|i| ((i / self.width) as isize, (i % self.width) as isize)));
+        let life = self.hashlife();
+        let plane = life.lock().unwrap().advance_plane(&live, generations);
+        let mut next = vec![false; self.width * self.height];
+        let mut beyond = HashSet::new();
+        for (row, col) in plane {
+            if self.is_in_bounds(row, col) {
+                next[self.get_index(row as usize, col as usize)] = true;
+            } else {
+                beyond.insert((row, col));
+            }
+        }
+        // Paint does not survive a jump.
+        self.advance(next, vec![None; self.width * self.height]);
+        self.beyond = beyond;
+        self.generation += generations - 1;
+        Ok(())
+    }
+
+    /// The next generation of the board and of the live cells beyond it.
+    fn next_unbounded(&self) -> (Vec<bool>, HashSet<sparse::Cell>) {
+        let on_board = |(row, col): sparse::Cell| self.is_in_bounds(row, col).then(|| self.get_index(row as usize, col as usize));
+        let mut live = self.beyond.clone();
+        live.extend((0..self.cells.len()).filter(|&i| self.cells[i]).map(|i| ((i / self.width) as isize, (i % self.width) as isize)));
+        let walls = (0..self.walls.len())
+            .filter(|&i| self.walls_alive && self.walls[i])
+            .map(|i| ((i / self.width) as isize, (i % self.width) as isize));
+        let next = sparse::step(&live, walls, |cell, alive, neighbours| match on_board(cell) {
+            Some(idx) if self.walls[idx] => false,
+            Some(idx) => self.rule_at(idx / self.width, idx % self.width).next(alive, neighbours),
+            None => self.rule.next(alive, neighbours),
+        });
+        let mut cells = vec![false; self.width * self.height];
+        let mut beyond = HashSet::new();
+        for cell in next {
+            match on_board(cell) {
+                Some(idx) => cells[idx] = true,
+                None => {beyond.insert(cell);}
+            }
+        }
+        (cells, beyond)
+    }
+
+    /// Moves the board `rows` down and `cols` right over the unbounded
+    /// plane, the live cells staying where they are on the plane. Walls,
+    /// paint, regions and notes belong to the board and move with it. The
+    /// history is dropped.
+    pub fn move_board(&mut self, rows: isize, cols: isize) {
+        let live = self.plane_cells().into_iter().map(|(row, col)| (row - rows, col - cols)).collect();
+        self.place_on_plane(live);
+        self.origin = (self.origin.0 + rows, self.origin.1 + cols);
+        self.history.clear();
+    }
+
+    /// Moves every live cell, with its paint and age, `rows` down and
+    /// `cols` right. Cells pushed over an edge wrap around on a torus, go
+    /// on beyond it on an unbounded plane and are lost otherwise. Walls,
+    /// regions and notes stay, and cells landing on walls die.
+    pub fn shift(&mut self, rows: isize, cols: isize) {
+        let (height, width) = (self.height as isize, self.width as isize);
+        let mut live: Vec<(sparse::Cell, Option<u8>, u32)> = mem::take(&mut self.beyond).into_iter()
+            .map(|cell| (cell, None, 0))
+            .collect();
+        live.extend((0..self.cells.len()).filter(|&i| self.cells[i])
+            .map(|i| (((i / self.width) as isize, (i % self.width) as isize), self.colors[i], self.ages[i])));
+        self.cells = vec![false; self.width * self.height];
+        self.colors = vec![None; self.width * self.height];
+        self.ages = vec![0; self.width * self.height];
+        self.dead_for = vec![u32::MAX; self.width * self.height];
+        for ((row, col), color, age) in live {
+            let (row, col) = match self.topology {
+                Topology::Torus => ((row + rows).rem_euclid(height), (col + cols).rem_euclid(width)),
+                _ => (row + rows, col + cols),
+            };
+            if self.is_in_bounds(row, col) {
+                let idx = self.get_index(row as usize, col as usize);
+                if self.walls[idx] {continue;}
+                (self.cells[idx], self.colors[idx], self.ages[idx]) = (true, color, age);
+            } else if self.topology == Topology::Unbounded {
+                self.beyond.insert((row, col));
+            }
+        }
+        self.count_population();
+    }
+
+    /// The live cells on the board and beyond it.
+    fn plane_cells(&mut self) -> HashSet<sparse::Cell> {
+        let mut live = mem::take(&mut self.beyond);
+        live.extend(self.live_cells().into_iter().map(|(row, col)| (row as isize, col as isize)));
+        live
+    }
+
+    /// Makes `live` the live cells of the plane, those on walls excepted.
+    fn place_on_plane(&mut self, live: HashSet<sparse::Cell>) {
+        self.cells = vec![false; self.width * self.height];
+        self.beyond.clear();
+        for (row, col) in live {
+            if self.is_in_bounds(row, col) {
+                let idx = self.get_index(row as usize, col as usize);
+                self.cells[idx] = !self.walls[idx];
+            } else {
+                self.beyond.insert((row, col));
+            }
+        }
+        for idx in 0..self.cells.len() {
+            if !self.cells[idx] {
+                self.colors[idx] = None;
+                self.ages[idx] = 0;
+                self.dead_for[idx] = u32::MAX;
+            }
+        }
+        self.count_population();
+    }
+
+    fn next_naive(&self) -> Vec<bool> {
+        let mut next = vec![false; self.width * self.height];
+        parallel::for_row_runs(&mut next, self.width, |first_row, run| {
+            for (i, next) in run.iter_mut().enumerate() {
+                let (row, col) = (first_row + i / self.width, i % self.width);
+                let idx = self.get_index(row, col);
+                if self.walls[idx] {continue;}
+                let cell = self.cells[idx];
+                let live_neighbours = self.live_neighbour_count(row, col);
+                *next = self.rule_at(row, col).next(cell, live_neighbours);
+            }
+        });
+        next
+    }
+
+    /// Replaces the cells with the next generation, keeping the current
+    /// one in the history.
+    fn advance(&mut self, next: Vec<bool>, colors: Vec<Option<u8>>) {
+        self.population = next.iter().filter(|&&alive| alive).count();
+        self.generation += 1;
+        let previous = mem::replace(&mut self.cells, next);
+        self.age(&previous);
+        let previous_colors = mem::replace(&mut self.colors, colors);
+        self.history.push(previous, &self.cells, &previous_colors, &self.colors, self.beyond.clone());
+    }
+
+    /// Counts how long the cells have been alive or dead for, a generation
+    /// on from `previous`.
+    fn age(&mut self, previous: &[bool]) {
+        for ((age, &alive), &was_alive) in self.ages.iter_mut().zip(&self.cells).zip(previous) {
+            *age = if alive && was_alive {age.saturating_add(1)} else {0};
+        }
+        for ((dead_for, &alive), &was_alive) in self.dead_for.iter_mut().zip(&self.cells).zip(previous) {
+            *dead_for = if alive {0} else if was_alive {1} else {dead_for.saturating_add(1)};
+        }
+    }
+
+    /// How many generations back the board can be stepped.
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// How many generations the board was stepped back from and can be
+    /// stepped forward to again.
+    pub fn later_len(&self) -> usize {
+        self.history.later_len()
+    }
+
+    /// Forgets the generations before this one and those stepped back
+    /// from, for a board that did not come from them.
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Forgets the generations stepped back from, for the board to carry
+    /// on differently.
+    pub fn forget_later(&mut self) {
+        self.history.clear_later();
+    }
+
+    pub fn tick_back(&mut self) -> Result<&str, &str> {
+        let later = self.cells.clone();
+        let mut beyond = self.beyond.clone();
+        if !self.history.pop(&mut self.cells, &mut self.colors, &mut beyond) {
+            return Err("No more moves in history!");
+        }
+        // Cells alive a generation later were one younger; how old the
+        // others were is not kept. Likewise for how long dead cells had
+        // been dead.
+        for (age, &alive) in self.ages.iter_mut().zip(&self.cells) {
+            *age = if alive {age.saturating_sub(1)} else {0};
+        }
+        for ((dead_for, &alive), &was_alive) in self.dead_for.iter_mut().zip(&self.cells).zip(&later) {
+            *dead_for = match *dead_for {
+                _ if alive => 0,
+                _ if was_alive => u32::MAX,
+                u32::MAX => u32::MAX,
+                n => n.saturating_sub(1).max(1),
+            };
+        }
+        // Off an unbounded plane since, the cells beyond are gone.
+        self.beyond = if self.topology == Topology::Unbounded {beyond} else {HashSet::new()};
+        self.count_population();
+        self.generation = self.generation.saturating_sub(1);
+        Ok("Returned to previous step")
+    }
+
+    /// Steps forward again to the generation last stepped back from.
+    pub fn tick_forward(&mut self) -> Result<(), &str> {
+        let earlier = self.cells.clone();
+        if !self.history.forward(&mut self.cells, &mut self.colors, &mut self.beyond) {
+            return Err("No later generations kept");
+        }
+        self.age(&earlier);
+        self.count_population();
+        self.generation += 1;
+        Ok(())
+    }
+
+    /// The rule of the most recently added region containing the cell,
+    /// or the universe's rule outside of all regions.
+    pub fn rule_at(&self, row: usize, col: usize) -> Rule {
+        match self.regions.iter().rev().find(|r| r.contains(row, col)) {
+            Some(region) => region.rule,
+            None => self.rule,
+        }
+    }
+
+    /// Adds a region, replacing any regions it overlaps.
+    pub fn add_region(&mut self, region: Region) {
+        self.remove_regions(&region);
+        self.regions.push(region);
+    }
+
+    pub fn remove_regions(&mut self, area: &Region) {
+        self.regions.retain(|r| !r.overlaps(area));
+    }
+
+    /// Cells born and cells that died in the most recent tick.
+    pub fn last_changes(&self) -> (usize, usize) {
+        let previous = match self.history.previous() {
+            Some(p) => p,
+            None => return (0, 0),
+        };
+        let mut births = 0;
+        let mut deaths = 0;
+        for (&before, &now) in previous.iter().zip(&self.cells) {
+            births += (!before && now) as usize;
+            deaths += (before && !now) as usize;
+        }
+        (births, deaths)
+    }
+
+    /// The cell closest to the cursor that changed in the last generation,
+    /// or the closest live cell when nothing changed.
+    pub fn nearest_activity(&self) -> Option<(usize, usize)> {
+        let changed: Vec<usize> = match self.history.previous() {
+            Some(previous) => (0..self.cells.len()).filter(|&i| previous[i] != self.cells[i]).collect(),
+            None => Vec::new(),
+        };
+        let candidates = if changed.is_empty() {
+            (0..self.cells.len()).filter(|&i| self.cells[i]).collect()
+        } else {changed};
+
+        let (row, col) = self.selected_cell;
+        candidates.into_iter()
+            .map(|i| (i / self.width, i % self.width))
+            .min_by_key(|&(r, c)| r.abs_diff(row).pow(2) + c.abs_diff(col).pow(2))
+    }
+
+    pub fn is_wall(&self, row: usize, col: usize) -> bool {
+        self.walls[self.get_index(row, col)]
+    }
+
+    /// Walls never change, block births and count as alive or dead
+    /// neighbours depending on `walls_alive`.
+    pub fn set_wall(&mut self, row: usize, col: usize, val: bool) {
+        let ind = self.get_index(row, col);
+        if self.cells[ind] {self.population -= 1;}
+        self.walls[ind] = val;
+        self.cells[ind] = false;
+        self.ages[ind] = 0;
+        self.dead_for[ind] = u32::MAX;
+    }
+
+    pub fn toggle_selected_wall(&mut self) {
+        let (row, col) = self.selected_cell;
+        self.set_wall(row, col, !self.is_wall(row, col));
+    }
+
+    /// Toggles the cell under the cursor, and sets the cells mirroring it
+    /// the same way.
+    pub fn toggle_selected_cell(&mut self) {
+        let (row, col) = self.selected_cell;
+        if self.is_wall(row, col) {
+            self.set_wall(row, col, false);
+            return;
+        }
+        let alive = !self.get_cell(row, col);
+        for (row, col) in self.symmetry.images(row, col, self.width, self.height) {
+            if !self.is_wall(row, col) {self.set_cell(row, col, alive);}
+        }
+    }
+
+    /// Sets the live cells of `pattern` with its top-left corner at
+    /// (row, col). Cells falling outside the universe are clipped.
+    pub fn stamp(&mut self, pattern: &Pattern, row: usize, col: usize) {
+        for (r, c) in &pattern.cells {
+            let (r, c) = (row + r, col + c);
+            if r < self.height && c < self.width && !self.is_wall(r, c) {
+                self.set_cell(r, c, true);
+            }
+        }
+    }
+
+    /// The top left and bottom right cells of the selection, which runs
+    /// from `selection` to the cursor.
+    pub fn selection_bounds(&self) -> Option<((usize, usize), (usize, usize))> {
+        let ((r1, c1), (r2, c2)) = (self.selection?, self.selected_cell);
+        Some(((r1.min(r2), c1.min(c2)), (r1.max(r2), c1.max(c2))))
+    }
+
+    /// The live cells of the selection, as a pattern its size.
+    pub fn selected_pattern(&self) -> Option<Pattern> {
+        let ((top, left), (bottom, right)) = self.selection_bounds()?;
+        let cells = (top..=bottom)
+            .flat_map(|row| (left..=right).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.get_cell(row, col))
+            .map(|(row, col)| (row - top, col - left))
+            .collect();
+        Some(Pattern { width: right - left + 1, height: bottom - top + 1, cells, rule: None })
+    }
+
+    /// Puts the live cells of the selection on the clipboard. Returns
+    /// them, or `None` when nothing is selected.
+    pub fn copy_selection(&mut self) -> Option<&Pattern> {
+        self.clipboard = Some(self.selected_pattern()?);
+        self.clipboard.as_ref()
+    }
+
+    /// Kills the cells of the selection. Returns them, or `None` when
+    /// nothing is selected.
+    pub fn clear_selection(&mut self) -> Option<Pattern> {
+        let pattern = self.selected_pattern()?;
+        let ((top, left), (bottom, right)) = self.selection_bounds()?;
+        for row in top..=bottom {
+            for col in left..=right {self.set_cell(row, col, false);}
+        }
+        Some(pattern)
+    }
+
+    /// Copies the selection, then kills its cells.
+    pub fn cut_selection(&mut self) -> Option<&Pattern> {
+        self.clipboard = Some(self.clear_selection()?);
+        self.clipboard.as_ref()
+    }
+
+    /// Turns or mirrors the live cells of the selection in place, about
+    /// its top left corner, the selection following them.
+    pub fn transform_selection(&mut self, transform: Transform) -> Result<(), &'static str> {
+        let ((top, left), _) = self.selection_bounds().ok_or("Nothing selected")?;
+        let pattern = self.selected_pattern().unwrap().transformed(transform);
+        if top + pattern.height > self.height || left + pattern.width > self.width {
+            return Err("The turned selection would not fit on the board");
+        }
+        self.clear_selection();
+        self.place(&pattern, top, left);
+        self.select(&pattern, top, left);
+        Ok(())
+    }
+
+    /// Makes every cell of the selection that is not a wall alive with
+    /// probability `density`, leaving the rest of the board be.
+    pub fn randomize_selection(&mut self, density: f64, rng: &mut Rng) -> Result<(), &'static str> {
+        let ((top, left), (bottom, right)) = self.selection_bounds().ok_or("Nothing selected")?;
+        for row in top..=bottom {
+            for col in left..=right {
+                let wall = self.walls[self.get_index(row, col)];
+                self.set_cell(row, col, !wall && rng.next_f64() < density);
+            }
+        }
+        Ok(())
+    }
+
+    /// Brings the dead cells of `region`, top left and bottom right
+    /// corners, or of the whole board without one, to life and kills the
+    /// live ones. Walls stay.
+    pub fn invert(&mut self, region: Option<((usize, usize), (usize, usize))>) {
+        let ((top, left), (bottom, right)) = region.unwrap_or(((0, 0), (self.height - 1, self.width - 1)));
+        for row in top..=bottom {
+            for col in left..=right {
+                let ind = self.get_index(row, col);
+                if !self.walls[ind] {self.set_cell(row, col, !self.cells[ind]);}
+            }
+        }
+    }
+
+    /// Brings the cells of `shape` between `from` and `to` to life, those
+    /// off the board and on walls excepted.
+    pub fn draw(&mut self, shape: Shape, from: (usize, usize), to: (usize, usize)) {
+        for (row, col) in shape.cells((from.0 as isize, from.1 as isize), (to.0 as isize, to.1 as isize)) {
+            if self.is_in_bounds(row, col) && !self.walls[self.get_index(row as usize, col as usize)] {
+                self.set_cell(row as usize, col as usize, true);
+            }
+        }
+    }
+
+    /// Shows `pattern` where it would be stamped at the cursor, or stops
+    /// showing it with `None`.
+    pub fn set_ghost(&mut self, pattern: Option<Pattern>) {
+        self.ghost = pattern.map(|mut p| {
+            p.cells.sort_unstable();
+            p
+        });
+    }
+
+    /// Selects the rectangle `pattern` takes up with its top left corner at
+    /// (top, left), the cursor at the bottom right.
+    pub fn select(&mut self, pattern: &Pattern, top: usize, left: usize) {
+        self.selection = Some((top, left));
+        self.selected_cell = ((top + pattern.height).min(self.height) - 1, (left + pattern.width).min(self.width) - 1);
+    }
+
+    /// Puts `pattern` on the board with its top left corner at (top,
+    /// left), in place of the cells under it. What falls off the board is
+    /// cut off and walls stay.
+    pub fn place(&mut self, pattern: &Pattern, top: usize, left: usize) {
+        for row in top..(top + pattern.height).min(self.height) {
+            for col in left..(left + pattern.width).min(self.width) {self.set_cell(row, col, false);}
+        }
+        self.stamp(pattern, top, left);
+    }
+
+    /// Puts the clipboard on the board with its top left corner at the
+    /// cursor, in place of the cells under it.
+    pub fn paste(&mut self) -> Result<(), &'static str> {
+        let pattern = self.clipboard.take().ok_or("Nothing to paste, select cells with Ctrl+V and copy them first")?;
+        let (top, left) = self.selected_cell;
+        self.place(&pattern, top, left);
+        self.clipboard = Some(pattern);
+        Ok(())
+    }
+
+    /// Changes the board size, keeping the cells and walls that still fit.
+    /// History is dropped since it no longer matches the board.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let plane = (self.topology == Topology::Unbounded).then(|| self.plane_cells());
+        let mut resized = Universe::new(width, height);
+        for row in 0..self.height.min(height) {
+            for col in 0..self.width.min(width) {
+                let (from, to) = (self.get_index(row, col), resized.get_index(row, col));
+                resized.cells[to] = self.cells[from];
+                resized.colors[to] = self.colors[from];
+                resized.ages[to] = self.ages[from];
+                resized.dead_for[to] = self.dead_for[from];
+                resized.walls[to] = self.walls[from];
+            }
+        }
+        self.width = width;
+        self.height = height;
+        self.cells = resized.cells;
+        self.colors = resized.colors;
+        self.ages = resized.ages;
+        self.dead_for = resized.dead_for;
+        self.walls = resized.walls;
+        self.count_population();
+        self.history.clear();
+        // On an unbounded plane the cells cut off carry on beyond the edges.
+        if let Some(live) = plane {self.place_on_plane(live);}
+        self.reference = None;
+        self.other_pane = self.other_pane.map(|_| SavedView { view: (0, 0), cursor: (0, 0) });
+        self.notes.retain(|&(row, col), _| row < height && col < width);
+        self.marker = self.marker.filter(|&(row, col)| row < height && col < width);
+        self.selection = None;
+        self.selected_cell = (self.selected_cell.0.min(height - 1), self.selected_cell.1.min(width - 1));
+        self.view = (0, 0);
+        self.scroll_to(self.selected_cell.0, self.selected_cell.1);
+    }
+
+    /// The cells, paint and walls, for undoing an edit.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.clone(),
+            colors: self.colors.clone(),
+            walls: self.walls.clone(),
+        }
+    }
+
+    /// Puts back the cells, paint and walls of `snapshot`, at its size.
+    /// The cells start their lives and trails afresh.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        if (snapshot.width, snapshot.height) != (self.width, self.height) {
+            self.resize(snapshot.width, snapshot.height);
+        }
+        self.cells = snapshot.cells;
+        self.colors = snapshot.colors;
+        self.walls = snapshot.walls;
+        self.ages = vec![0; self.width * self.height];
+        self.dead_for = vec![u32::MAX; self.width * self.height];
+        self.count_population();
+    }
+
+    /// Makes every cell that is not a wall alive with probability `density`.
+    pub fn randomize(&mut self, density: f64, rng: &mut Rng) {
+        for (cell, &wall) in self.cells.iter_mut().zip(&self.walls) {
+            *cell = !wall && rng.next_f64() < density;
+        }
+        self.colors = vec![None; self.width * self.height];
+        self.ages = vec![0; self.width * self.height];
+        self.dead_for = vec![u32::MAX; self.width * self.height];
+        self.count_population();
+    }
+
+    pub fn clear(&mut self) {
+        self.cells = vec![false; self.width * self.height];
+        self.colors = vec![None; self.width * self.height];
+        self.ages = vec![0; self.width * self.height];
+        self.dead_for = vec![u32::MAX; self.width * self.height];
+        self.population = 0;
+        self.beyond.clear();
+    }
+
+    pub fn clear_walls(&mut self) {
+        self.walls = vec![false; self.width * self.height];
+    }
+}
+
+
+/// The largest board, as width and height, that fits the terminal with
+/// its border, `cell_width` columns per cell and the title and status
+/// lines.
+fn terminal_board_size(cell_width: usize) -> Option<(usize, usize)> {
+    let (cols, rows) = termion::terminal_size().ok()?;
+    let width = (cols as usize).saturating_sub(2) / cell_width;
+    let height = (rows as usize).saturating_sub(VIEW_MARGIN_ROWS + FIT_STATUS_ROWS);
+    if width == 0 || height == 0 {return None;}
+    Some((width, height))
+}
+
+
+/// Removes `flag` from `args`, returning whether it was present.
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    match args.iter().position(|a| a == flag) {
+        Some(i) => {args.remove(i); true}
+        None => false,
+    }
+}
+
+
+/// Removes `option` and the value following it from `args`.
+fn take_option(args: &mut Vec<String>, option: &str) -> Option<String> {
+    let i = args.iter().position(|a| a == option)?;
+    args.remove(i);
+    if i < args.len() {Some(args.remove(i))} else {None}
+}
+
+
+#[derive(Clone, Copy)]
+struct ImageOptions {
+    threshold: u8,
+    invert: bool,
+}
+
+
+/// Reads an image scaled to `width` by `height` or, for any other file, a
+/// `.cells` pattern or ASCII art. Also returns warnings about parts of the
+/// file that were skipped.
+fn read_pattern(path: &str, width: usize, height: usize, options: &ImageOptions)
+    -> Result<(Pattern, Vec<String>), String> {
+    let extension = Path::new(path).extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+        let image = Image::load(path)?;
+        return Ok((image.to_pattern(width, height, options.threshold, options.invert), Vec::new()));
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    if extension == "rle" {
+        let (pattern, diagnostics) = Pattern::parse_rle(&text).map_err(|d| d.in_file(path))?;
+        return Ok((pattern, diagnostics.iter().map(|d| d.in_file(path)).collect()));
+    }
+    if extension == "lif" {
+        let (pattern, diagnostics) = Pattern::parse_life106(&text).map_err(|d| d.in_file(path))?;
+        return Ok((pattern, diagnostics.iter().map(|d| d.in_file(path)).collect()));
+    }
+    if extension == "cells" {
+        let (pattern, diagnostics) = Pattern::parse_plaintext(&text);
+        Ok((pattern, diagnostics.iter().map(|d| d.in_file(path)).collect()))
+    } else {
+        Ok((Pattern::from_ascii(&text), Vec::new()))
+    }
+}
+
+
+/// Where a pattern loaded at startup goes on the board.
+#[derive(Clone, Copy, PartialEq)]
+enum Placement {
+    Center,
+    TopLeft,
+    /// The pattern's top left corner at (row, col).
+    At(usize, usize),
+}
+
+
+impl Placement {
+    /// Parses `center`, `topleft` or a position given as `x,y`.
+    fn parse(text: &str) -> Result<Placement, String> {
+        match text {
+            "center" => Ok(Placement::Center),
+            "topleft" => Ok(Placement::TopLeft),
+            _ => parse_position(text).map(|(row, col)| Placement::At(row, col))
+                .map_err(|_| format!("invalid placement `{}`: expected center, topleft or x,y", text)),
+        }
+    }
+}
+
+
+/// Parses `x,y` as (row, col), i.e. x counts columns and y rows.
+fn parse_position(text: &str) -> Result<(usize, usize), String> {
+    let err = || format!("invalid position `{}`: expected x,y", text);
+    let (x, y) = text.split_once(',').ok_or_else(err)?;
+    Ok((y.trim().parse().map_err(|_| err())?, x.trim().parse().map_err(|_| err())?))
+}
+
+
+/// Clears the board and stamps the pattern in its middle. A pattern larger
+/// than the board is cropped around its center. The board takes the
+/// pattern's rule if it has one.
+fn place_centered(game: &mut Universe, pattern: &Pattern) {
+    if let Some(rule) = pattern.rule {game.rule = rule;}
+    let cropped = pattern.crop(pattern.height.saturating_sub(game.height) / 2,
+                               pattern.width.saturating_sub(game.width) / 2,
+                               game.height,
+                               game.width);
+    game.clear();
+    game.stamp(&cropped,
+               game.height.saturating_sub(cropped.height) / 2,
+               game.width.saturating_sub(cropped.width) / 2);
+}
+
+
+/// Replaces the board's cells with the file's contents, see `read_pattern`
+/// and `import_pattern`. Returns warnings, including one when the pattern
+/// had to be cropped.
+fn import_file(game: &mut Universe, path: &str, options: &ImageOptions, placement: Placement)
+    -> Result<Vec<String>, String> {
+    let (pattern, mut warnings) = read_pattern(path, game.width, game.height, options)?;
+    warnings.extend(import_pattern(game, &pattern, path, placement));
+    Ok(warnings)
+}
+
+
+/// Reads a pattern piped into standard input, see `Pattern::parse`.
+fn read_stdin() -> Result<Pattern, String> {
+    if unsafe {libc::isatty(libc::STDIN_FILENO)} == 1 {
+        return Err("--stdin expects a pattern piped in, e.g. cat glider.rle | game-of-life --stdin".to_string());
+    }
+    let mut text = String::new();
+    io::stdin().read_to_string(&mut text).map_err(|e| format!("cannot read stdin: {}", e))?;
+    Pattern::parse(&text).map_err(|d| d.in_file("stdin"))
+}
+
+
+/// Replaces the board's cells with `pattern`, read from `source`, see
+/// `place_centered`. Returns a warning when the pattern had to be cropped.
+fn import_pattern(game: &mut Universe, pattern: &Pattern, source: &str, placement: Placement) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let (top, left) = match placement {
+        Placement::Center => {
+            if pattern.width > game.width || pattern.height > game.height {
+                warnings.push(format!("{} is {}x{}, larger than the {}x{} board; cropped around its center",
+                                      source, pattern.width, pattern.height, game.width, game.height));
+            }
+            place_centered(game, pattern);
+            return warnings;
+        }
+        Placement::TopLeft => (0, 0),
+        Placement::At(row, col) => (row, col),
+    };
+    if top + pattern.height > game.height || left + pattern.width > game.width {
+        warnings.push(format!("{} is {}x{} and does not fit on the {}x{} board at column {}, row {}; clipped",
+                              source, pattern.width, pattern.height, game.width, game.height, left, top));
+    }
+    if let Some(rule) = pattern.rule {game.rule = rule;}
+    game.clear();
+    game.stamp(pattern, top, left);
+    warnings
+}
+
+
+fn main() {
+    let mut args: Vec<String> = env::args().collect();
+    if args.get(1).is_some_and(|a| a == "convert") {
+        args.remove(1);
+        if let Err(e) = convert::run(&mut args) {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        return;
+    }
+    let daemon_socket = if args.get(1).is_some_and(|a| a == "daemon") {
+        args.remove(1);
+        Some(take_option(&mut args, "--socket").unwrap_or(DAEMON_SOCKET_DEFAULT.to_string()))
+    } else {None};
+    let resume_path = take_option(&mut args, "--resume");
+    let checkpoint_path = take_option(&mut args, "--checkpoint").or(resume_path.clone());
+    let follow_path = take_option(&mut args, "--follow");
+    let fade = take_flag(&mut args, "--fade");
+    let edge_preview = take_flag(&mut args, "--edge-preview");
+    let checkpoint_every = match take_option(&mut args, "--checkpoint-every").map(|n| n.parse()) {
+        Some(Ok(n)) => n,
+        Some(Err(_)) => {
+            eprintln!("--checkpoint-every expects a number of generations");
+            exit(1);
+        }
+        None => CHECKPOINT_EVERY_DEFAULT,
+    };
+    let server_address = if args.get(1).is_some_and(|a| a == "serve") {
+        args.remove(1);
+        let port = take_option(&mut args, "--port").unwrap_or(SERVER_PORT_DEFAULT.to_string());
+        let bind = take_option(&mut args, "--bind").unwrap_or(SERVER_BIND_DEFAULT.to_string());
+        Some(format!("{}:{}", bind, port))
+    } else {None};
+    let sweep = if args.get(1).is_some_and(|a| a == "experiment") {
+        args.remove(1);
+        match experiment::Sweep::from_args(&mut args) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        }
+    } else {None};
+    let headless = if args.get(1).is_some_and(|a| a == "run") {
+        args.remove(1);
+        match headless::Run::from_args(&mut args) {
+            Ok(r) => Some(r),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        }
+    } else {None};
+    let config_path = config::path(take_option(&mut args, "--config").as_deref());
+    let config_overridden = config::overridden(&args);
+    if let Err(e) = config_path.as_deref().map_or(Ok(()), |path| config::add_defaults(&mut args, path)) {
+        eprintln!("{}", e);
+        exit(1);
+    }
+    let lexicon_path = take_option(&mut args, "--lexicon");
+    let with_challenges = take_flag(&mut args, "--challenges");
+    let puzzle_dir = take_option(&mut args, "--puzzles");
+    let board_code = take_option(&mut args, "--board");
+    let mut load_path = take_option(&mut args, "--load");
+    let density = match take_option(&mut args, "--random").map(|d| d.parse::<f64>()) {
+        Some(Ok(d)) if (0.0..=1.0).contains(&d) => Some(d),
+        Some(_) => {
+            eprintln!("--random expects a density from 0 to 1, e.g. 0.35");
+            exit(1);
+        }
+        None => None,
+    };
+    let seed = match take_option(&mut args, "--seed").map(|s| s.parse::<u64>()) {
+        Some(Ok(seed)) => Some(seed),
+        Some(Err(_)) => {
+            eprintln!("--seed expects a whole number, e.g. 42");
+            exit(1);
+        }
+        None => None,
+    };
+    let image_path = take_option(&mut args, "--image");
+    let text_path = take_option(&mut args, "--text");
+    let mut pattern_path = take_option(&mut args, "--pattern");
+    // A run loads patterns as well as saved states.
+    if headless.is_some() && load_path.as_deref().is_some_and(|path| !path.to_lowercase().ends_with(".json")) {
+        if pattern_path.is_some() {
+            eprintln!("--load and --pattern both give the board to run, use one of them");
+            exit(1);
+        }
+        pattern_path = load_path.take();
+    }
+    let from_stdin = take_flag(&mut args, "--stdin");
+    let mut number_option = |option: &str| match take_option(&mut args, option).map(|n| n.parse::<usize>()) {
+        Some(Ok(n)) if n > 0 => Some(n),
+        Some(_) => {
+            eprintln!("{} expects a positive number", option);
+            exit(1);
+        }
+        None => None,
+    };
+    let width = number_option("--width");
+    let height = number_option("--height");
+    let tick_millis = number_option("--tick-ms");
+    let history_len = number_option("--history");
+    let history_mb = number_option("--history-mb");
+    let trail_len = number_option("--trails").map(|n| u32::try_from(n).unwrap_or(u32::MAX));
+    let with_audio = take_flag(&mut args, "--audio");
+    let audio_command = take_option(&mut args, "--audio-cmd");
+    let midi_path = take_option(&mut args, "--midi");
+    let screensaver_after = match take_option(&mut args, "--screensaver").map(|s| s.parse()) {
+        Some(Ok(secs)) => Some(Duration::from_secs(secs)),
+        Some(Err(_)) => {
+            eprintln!("--screensaver expects a number of seconds");
+            exit(1);
+        }
+        None => None,
+    };
+    let image_options = ImageOptions {
+        threshold: match take_option(&mut args, "--threshold").map(|t| t.parse()) {
+            Some(Ok(t)) => t,
+            Some(Err(_)) => {
+                eprintln!("--threshold must be a number from 0 to 255");
+                exit(1);
+            }
+            None => IMAGE_THRESHOLD_DEFAULT,
+        },
+        invert: take_flag(&mut args, "--invert-image"),
+    };
+    let max_run = match take_option(&mut args, "--max-seconds").map(|s| s.parse()) {
+        Some(Ok(secs)) => Some(Duration::from_secs(secs)),
+        Some(Err(_)) => {
+            eprintln!("--max-seconds expects a number of seconds");
+            exit(1);
+        }
+        None => None,
+    };
+    let quicksave_path = take_option(&mut args, "--quicksave")
+        .unwrap_or(session::QUICKSAVE_PATH_DEFAULT.to_string());
+    let quicksave = match QuickSave::new(&quicksave_path, take_option(&mut args, "--quicksave-keys").as_deref()) {
+        Ok(q) => q,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    let autostart = take_flag(&mut args, "--autostart");
+    if take_flag(&mut args, "--paused") && autostart {
+        eprintln!("--autostart and --paused cannot be combined");
+        exit(1);
+    }
+    let placement = match take_option(&mut args, "--place").map(|p| Placement::parse(&p)) {
+        Some(Ok(p)) => p,
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        None => Placement::Center,
+    };
+    let cursor = match take_option(&mut args, "--cursor").map(|p| parse_position(&p)) {
+        Some(Ok(p)) => Some(p),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        None => None,
+    };
+    let engine = match take_option(&mut args, "--engine").map(|e| Engine::parse(&e)) {
+        Some(Ok(e)) => e,
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        None => Engine::Naive,
+    };
+    let topology = match take_option(&mut args, "--topology").map(|t| Topology::parse(&t)) {
+        Some(Ok(t)) => t,
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        None if take_flag(&mut args, "--torus") => Topology::Torus,
+        None => Topology::Bounded,
+    };
+    let render = match take_option(&mut args, "--render").map(|r| RenderMode::parse(&r)) {
+        Some(Ok(r)) => r,
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        None => RenderMode::Cells,
+    };
+    let themes_path = theme::path(take_option(&mut args, "--themes").as_deref());
+    let themes = match theme::load(themes_path.as_deref()) {
+        Ok(themes) => themes,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    let theme_name = take_option(&mut args, "--theme");
+    let ascii = take_flag(&mut args, "--ascii");
+    let glyphs = match take_option(&mut args, "--glyphs").map(|g| Glyphs::parse(&g)) {
+        Some(Ok(g)) => Some(g),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        None => None,
+    };
+    let borders = match take_option(&mut args, "--borders").map(|b| Borders::parse(&b)) {
+        Some(Ok(b)) => Some(b),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        None => None,
+    };
+    let coloring = match take_option(&mut args, "--colors").map(|c| Coloring::parse(&c)) {
+        Some(Ok(c)) => c,
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        None => Coloring::Paint,
+    };
+    let reference_path = take_option(&mut args, "--reference");
+    let layers = match take_option(&mut args, "--layers").map(|n| n.parse::<usize>()) {
+        Some(Ok(n)) if n >= 2 => Some(n),
+        Some(_) => {
+            eprintln!("--layers expects a number of layers, at least 2");
+            exit(1);
+        }
+        None => None,
+    };
+    let rule = match take_option(&mut args, "--rule").map(|r| Rule::parse(&r)) {
+        Some(Ok(rule)) => Some(rule),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        None => None,
+    };
+    let rule3d = match take_option(&mut args, "--rule3d").map(|r| Rule3d::parse(&r)) {
+        Some(Ok(rule)) => rule,
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        None => Rule3d::bays(),
+    };
+    let mut regions = Vec::new();
+    while let Some(region) = take_option(&mut args, "--region") {
+        regions.push(region);
+    }
+
+    if (args.len() >= 2) && args[1].to_lowercase().contains("help") {
+        println!("Game Of Life");
+        println!("Args: game-of-life [options] [<rows> <cols>]");
+        println!("                    without a size the board fills the terminal");
+        println!("      game-of-life daemon [--socket <path>] [options] <rows> <cols>");
+        println!("                    run headless, controlled over a Unix socket");
+        println!("      game-of-life run [--load <file>] [--generations <n>] --out <file|->");
+        println!("                    [--format rle|cells|json] <rows> <cols>");
+        println!("                    run without the terminal and write the board, - to stdout");
+        println!("      game-of-life serve [--port <n>] [--bind <address>] [options] <rows> <cols>");
+        println!("                    give every telnet client its own game (default port 2323,");
+        println!("                    this machine only without --bind)");
+        println!("      game-of-life experiment [--densities <d,..>] [--rules <rule,..>]");
+        println!("                    [--seeds <a-b,..>] [--generations <n>] [--out <csv>] <rows> <cols>");
+        println!("                    run every combination headlessly and write metrics as CSV");
+        println!("      game-of-life convert <in> <out>");
+        println!("      game-of-life convert --recursive <dir> --to <format> --out-dir <dir>");
+        println!("                    convert pattern files (cells, txt, rle, lif), one or a whole tree");
+        println!("  --width <n>       board width in cells (instead of <cols>)");
+        println!("  --height <n>      board height in cells (instead of <rows>)");
+        println!("  --tick-ms <n>     milliseconds per generation when running (default: 200)");
+        println!("  --history <n>     at most n generations kept to step back through (default:");
+        println!("                    as many as fit the history's memory)");
+        println!("  --history-mb <n>  memory for stepping back, in MiB (default: {})", history::BUDGET_DEFAULT >> 20);
+        println!("  --config <file>   defaults for width, height, tick_ms, rule, topology, theme,");
+        println!("                    glyphs, history and history_mb as TOML, overridden by the");
+        println!("                    options");
+        println!("                    (default: ~/.config/game-of-life-cli/config.toml)");
+        println!("  --pattern <file>  start with a pattern file or image in the middle");
+        println!("  --stdin           start with an RLE, Life 1.06 or plaintext pattern piped in");
+        println!("  --checkpoint <file>");
+        println!("                    save the board every 1000 generations (and after edits");
+        println!("                    in the interactive game)");
+        println!("  --checkpoint-every <n>");
+        println!("                    checkpoint every n generations instead");
+        println!("  --resume <file>   continue a checkpointed daemon or headless run where it");
+        println!("                    left off");
+        println!("  --follow <file>   watch another instance's checkpoint file, read-only");
+        println!("  --lexicon <file>  search a full copy of the Life Lexicon");
+        println!("  --challenges      track challenges ([A] shows progress)");
+        println!("  --puzzles <dir>   load puzzle levels (*.puzzle) from a directory");
+        println!("  --board <code>    start from a shared board code (life:...)");
+        println!("  --random <d>      start from a random soup with density d (0 to 1)");
+        println!("  --seed <n>        seed random soups, the same seed, density and size give the same soup");
+        println!("  --load <file>     continue a game saved with Shift+S, with its history and settings");
+        println!("  --image <file>    seed the board from a PNG or PBM/PGM/PPM image");
+        println!("  --text <file>     seed the board from ASCII art (not space or '.' is alive)");
+        println!("  --threshold <n>   pixels darker than n (0-255) become alive");
+        println!("  --invert-image    make light pixels alive instead");
+        println!("  --audio           play the simulation as sound (needs the audio feature)");
+        println!("  --audio-cmd <cmd> raw 16 bit 44.1kHz mono player (default: aplay)");
+        println!("  --midi <path>     send a note for every birth to a MIDI device or FIFO,");
+        println!("                    or record them when the path ends in .mid");
+        println!("  --fade            fade births in and deaths out (needs a truecolor terminal)");
+        println!("  --screensaver <s> run a random soup after s seconds without input");
+        println!("  --quicksave <file>");
+        println!("                    file for quicksaves (default: game-of-life.quicksave)");
+        println!("  --quicksave-keys <save>,<load>");
+        println!("                    quicksave and quickload keys (default: F5,F9)");
+        println!("  --max-seconds <s> pause after running for s seconds (also in the daemon)");
+        println!("  --reference <file>");
+        println!("                    show a pattern dimmed under the board as a target");
+        println!("  --autostart       start running right away (--paused, the default, does not)");
+        println!("  --place <center|topleft|x,y>");
+        println!("                    where --image or --text puts the pattern (default: center)");
+        println!("  --cursor <x,y>    start with the cursor at column x, row y");
+        println!("  --rule <rule>     B/S rulestring, e.g. B36/S23 (HighLife) or B3678/S34678");
+        println!("                    (Day & Night); default B3/S23");
+        println!("  --region <top,left,bottom,right:rule>");
+        println!("                    use another rule (e.g. B36/S23) in a region");
+        println!("  --engine <name>   naive (default); lookup, which steps 2x2 blocks through a");
+        println!("                    precomputed table; packed, which steps 64 cells at a time;");
+        println!("                    or hashlife, which can jump far ahead with Shift+J");
+        println!("  --topology <bounded|torus|unbounded>");
+        println!("                    whether the edges wrap around (--torus for short) or the board");
+        println!("                    is a window onto an unbounded plane");
+        println!("  --edge-preview    on a torus, show the live cells across the opposite edges");
+        println!("                    dimmed in the border (also in the settings, E)");
+        println!("  --render <cells|half-block|quadrant|braille|sixel|kitty|auto>");
+        println!("                    draw two columns per cell (default), two cells per");
+        println!("                    character as half blocks, 2x2 as quadrants, 2x4 as");
+        println!("                    Braille dots or as an image on terminals that show sixel");
+        println!("                    or kitty graphics; auto picks the first of cells, quadrants and");
+        println!("                    Braille that fits the whole board. Shift+G switches in game");
+        println!("  --colors <paint|age|changes>");
+        println!("                    colour live cells by their paint (default), by how many");
+        println!("                    generations they have been alive, bright when newborn and");
+        println!("                    blue when old, or show births in green and deaths in red.");
+        println!("                    Shift+A switches in game");
+        println!("  --trails <n>      cells that died in the last n generations leave a fading");
+        println!("                    trail, to follow the paths of spaceships. Shift+H toggles");
+        println!("  --theme <name>    colours to draw with: dark (the terminal's own, default),");
+        println!("                    light, solarized, matrix or one from the theme file.");
+        println!("                    Shift+P switches in game");
+        println!("  --themes <file>   TOML file with more themes (default:");
+        println!("                    ~/.config/game-of-life-cli/themes.toml)");
+        println!("  --glyphs <set>    cell glyphs: blocks (default), dots, ascii, or five glyphs");
+        println!("                    separated by commas for live, dead, selected live and");
+        println!("                    selected dead cells and walls, all one or all two columns");
+        println!("                    wide, e.g. \"#,.,@,_,X\" for one column per cell");
+        println!("  --borders <chars> border corners clockwise from the top left, then the");
+        println!("                    horizontal and vertical lines, e.g. \"++++-|\"; six more");
+        println!("                    for the other pane of a split screen");
+        println!("  --ascii           draw only ASCII, +-| borders and #/. cells, for terminals");
+        println!("                    without Unicode fonts");
+        println!("  --layers <n>      experimental 3D Life with n stacked layers");
+        println!("  --rule3d <rule>   rule for the 3D mode (default: B5/S4-5)");
+        exit(0);
+    }
+
+    let lexicon = match lexicon_path {
+        Some(path) => match Lexicon::from_file(&path) {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("Cannot read lexicon {}: {}", path, e);
+                exit(1);
+            }
+        },
+        None => Lexicon::bundled(),
+    };
+
+    let (levels, level_errors) = match puzzle_dir {
+        Some(dir) => match Level::load_dir(Path::new(&dir)) {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("Cannot read puzzles from {}: {}", dir, e);
+                exit(1);
+            }
+        },
+        None => (Level::bundled(), Vec::new()),
+    };
+
+    let mut sz_rows = SIZE_ROWS_DEFAULT;
+    let mut sz_cols = SIZE_COLS_DEFAULT;
+    if args.len() >= 3 {
+        sz_rows = match args[1].parse() {
+            Ok(r) => r,
+            Err(_) => SIZE_ROWS_DEFAULT,
+        };
+        sz_cols = match args[2].parse() {
+            Ok(c) => c,
+            Err(_) => SIZE_ROWS_DEFAULT,
+        };
+    }
+    // Without a size the interactive game fills the terminal, less the
+    // full help, which then starts hidden.
+    let fit_terminal = args.len() < 3 && daemon_socket.is_none() && headless.is_none() && board_code.is_none()
+        && load_path.is_none() && follow_path.is_none();
+    if fit_terminal {
+        let cell_width = glyphs.as_ref().map_or(Glyphs::default().width(), Glyphs::width);
+        if let Some((cols, rows)) = terminal_board_size(cell_width) {
+            sz_rows = cols;
+            sz_cols = rows;
+        }
+    }
+    // The positional size is read the other way round by Universe::new.
+    if let Some(width) = width {sz_rows = width;}
+    if let Some(height) = height {sz_cols = height;}
+    // Read before the board is made, so a headless run without a size
+    // gets a board the size of the pattern, as Universe::from_rle does.
+    let stdin_pattern = from_stdin.then(|| read_stdin().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        exit(1);
+    }));
+    if headless.is_some() && args.len() < 3 && width.is_none() && height.is_none() {
+        let is_image = |path: &&String| Path::new(path).extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+        let from_file = match pattern_path.iter().find(|path| !is_image(path)) {
+            Some(path) => match read_pattern(path, sz_rows, sz_cols, &image_options) {
+                Ok((pattern, _)) => Some(pattern),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    exit(1);
+                }
+            },
+            None => None,
+        };
+        if let Some(pattern) = from_file.as_ref().or(stdin_pattern.as_ref()) {
+            sz_rows = pattern.width.max(1);
+            sz_cols = pattern.height.max(1);
+        }
+    }
+
+    let resumed = match resume_path {
+        Some(_) if daemon_socket.is_none() && headless.is_none() => {
+            eprintln!("--resume continues a daemon or headless run, use it with game-of-life daemon or run");
+            exit(1);
+        }
+        Some(_) if board_code.is_some() || image_path.is_some() || text_path.is_some() || pattern_path.is_some()
+            || from_stdin => {
+            eprintln!("--resume cannot be combined with --board, --image, --text, --pattern or --stdin");
+            exit(1);
+        }
+        Some(path) => match checkpoint::load(&path) {
+            Ok(c) => Some(c),
+            Err(e) => {
+                eprintln!("Cannot resume from {}: {}", path, e);
+                exit(1);
+            }
+        },
+        None => None,
+    };
+    if let Some(c) = &resumed {
+        if (args.len() >= 3 || width.is_some() || height.is_some()) && (c.game.width, c.game.height) != (sz_rows, sz_cols) {
+            eprintln!("The checkpoint's board is {}x{}, not {}x{}", c.game.width, c.game.height, sz_rows, sz_cols);
+            exit(1);
+        }
+    }
+
+    let loaded = match load_path {
+        Some(_) if resumed.is_some() || board_code.is_some() => {
+            eprintln!("--load cannot be combined with --resume or --board");
+            exit(1);
+        }
+        Some(path) => match state::load(&path) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        },
+        None => None,
+    };
+    let saved_tick_millis = loaded.as_ref().and_then(|s| s.tick_millis);
+    let saved_theme = loaded.as_ref().and_then(|s| s.theme.clone());
+
+    let (mut game, mut rng) = match (resumed, board_code, loaded) {
+        (Some(c), _, _) => (c.game, c.rng),
+        (None, None, Some(s)) => (s.game, Rng::from_time()),
+        (None, Some(code), _) => match share::decode(&code) {
+            Ok(universe) => (universe, Rng::from_time()),
+            Err(e) => {
+                eprintln!("Invalid board code: {}", e);
+                exit(1);
+            }
+        },
+        (None, None, None) => (Universe::new(sz_rows, sz_cols), Rng::from_time()),
+    };
+    game.show_cursor = true;
+    // The soup is made from the seed itself, so passing the seed shown in
+    // the status bar to --seed makes the same soup again.
+    let soup_seed = density.map(|density| {
+        let seed = seed.unwrap_or_else(|| rng.next_u64());
+        game.randomize(density, &mut Rng::new(seed));
+        seed
+    });
+    game.should_write_help = !fit_terminal || height.is_some();
+    for path in image_path.iter().chain(text_path.iter()).chain(pattern_path.iter()) {
+        match import_file(&mut game, path, &image_options, placement) {
+            Ok(warnings) => warnings.iter().for_each(|w| eprintln!("warning: {}", w)),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        }
+    }
+    if let Some(pattern) = stdin_pattern {
+        let warnings = import_pattern(&mut game, &pattern, "stdin", placement);
+        warnings.iter().for_each(|w| eprintln!("warning: {}", w));
+    }
+    // Overrides the rule of a loaded pattern or board code.
+    if let Some(rule) = rule {game.rule = rule;}
+    if let Some(path) = reference_path {
+        match read_pattern(&path, game.width, game.height, &image_options) {
+            Ok((pattern, warnings)) => {
+                warnings.iter().for_each(|w| eprintln!("warning: {}", w));
+                game.set_reference(Some(&pattern));
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        }
+    }
+    if let Some((row, col)) = cursor {
+        if row >= game.height || col >= game.width {
+            eprintln!("--cursor {},{} is outside the {}x{} board", col, row, game.width, game.height);
+            exit(1);
+        }
+        game.selected_cell = (row, col);
+    }
+    game.is_running = autostart;
+    game.engine = engine;
+    game.topology = topology;
+    game.edge_preview = edge_preview;
+    game.render = render;
+    game.coloring = coloring;
+    if ascii {game.set_ascii();}
+    match theme_name.map(|name| Theme::find(&themes, &name)) {
+        Some(Ok(theme)) => game.set_theme(theme),
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        // A theme saved with the state may since have left the theme file.
+        None => if let Some(Ok(theme)) = saved_theme.map(|name| Theme::find(&themes, &name)) {game.set_theme(theme);}
+    }
+    if let Some(glyphs) = glyphs {game.set_glyphs(glyphs);}
+    if let Some(borders) = borders {game.set_borders(borders);}
+    if let Some(len) = trail_len {game.trail_len = len;}
+    if let Some(len) = history_len {game.history.len_max = Some(len);}
+    if let Some(mb) = history_mb {game.history.budget = mb << 20;}
+    for text in regions {
+        match Region::parse(&text) {
+            Ok(region) => game.add_region(region),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        }
+    }
+
+    if let Some(socket) = daemon_socket {
+        let checkpointer = checkpoint_path.map(|path| Checkpointer::new(&path, checkpoint_every));
+        let start = Checkpoint { game, rng };
+        let tick_millis = tick_millis.map(|m| m as u64).or(saved_tick_millis).unwrap_or(session::TICK_MILLIS_DEFAULT);
+        if let Err(e) = daemon::run(&socket, start, image_options, checkpointer, tick_millis, max_run) {
+            eprintln!("Daemon failed: {}", e);
+            exit(1);
+        }
+        return;
+    }
+
+    if let Some(headless) = headless {
+        let tick_millis = tick_millis.map(|m| m as u64).or(saved_tick_millis).unwrap_or(session::TICK_MILLIS_DEFAULT);
+        let checkpointer = checkpoint_path.map(|path| Checkpointer::new(&path, checkpoint_every));
+        if let Err(e) = headless.run(Checkpoint { game, rng }, checkpointer, tick_millis, max_run) {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        return;
+    }
+
+    if let Some(sweep) = sweep {
+        let result = match &sweep.out {
+            Some(path) => fs::File::create(path).and_then(|mut f| experiment::run(&sweep, &game, &mut f)),
+            None => experiment::run(&sweep, &game, &mut stdout()),
+        };
+        if let Err(e) = result {
+            eprintln!("Experiment failed: {}", e);
+            exit(1);
+        }
+        return;
+    }
+
+    if let Some(address) = server_address {
+        // Sessions run on the server, so audio, MIDI and the local
+        // challenge progress are left out, and clients get no files.
+        let new_session = || {
+            let panels = Panels {
+                lexicon: lexicon.clone(),
+                challenges: None,
+                levels: levels.clone(),
+                level_errors: level_errors.clone(),
+                puzzle: None,
+                recording: None,
+                #[cfg(feature = "audio")]
+                sonifier: None,
+                midi: None,
+            };
+            let mut session = Session::new(game.clone(), panels, image_options, screensaver_after);
+            // Remote terminals are not asked which graphics they show.
+            session.set_graphics(Graphics { ascii, ..Graphics::default() });
+            session.set_themes(themes.clone());
+            if let Some(millis) = tick_millis {session.set_tick_millis(millis as u64);}
+            session.sandbox();
+            session
+        };
+        if let Err(e) = server::run(&address, new_session) {
+            eprintln!("Server failed: {}", e);
+            exit(1);
+        }
+        return;
+    }
+
+    #[cfg(feature = "audio")]
+    let sonifier = if with_audio {
+        let command = audio_command.as_deref().unwrap_or(audio::PLAYER_DEFAULT);
+        match audio::Sonifier::start(command) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                eprintln!("Cannot start audio player `{}`: {}", command, e);
+                exit(1);
+            }
+        }
+    } else {None};
+    #[cfg(not(feature = "audio"))]
+    if with_audio || audio_command.is_some() {
+        eprintln!("This build has no audio support, rebuild with --features audio");
+        exit(1);
+    }
+
+    let midi = match midi_path.map(|path| (MidiOut::open(&path), path)) {
+        Some((Ok(m), _)) => Some(m),
+        Some((Err(e), path)) => {
+            eprintln!("Cannot open MIDI output {}: {}", path, e);
+            exit(1);
+        }
+        None => None,
+    };
+
+    let panels = Panels {
+        lexicon,
+        challenges: if with_challenges {Some(Challenges::load())} else {None},
+        levels,
+        level_errors,
+        puzzle: None,
+        recording: None,
+        #[cfg(feature = "audio")]
+        sonifier,
+        midi,
+    };
+    // Keys come from the terminal once the pattern has been piped in.
+    if from_stdin {
+        if let Err(e) = terminal::reopen_stdin() {
+            eprintln!("Cannot read keys from the terminal: {}", e);
+            exit(1);
+        }
+    }
+    terminal::restore_on_panic();
+    let mut stdout = AlternateScreen::from(MouseTerminal::from(stdout().into_raw_mode().unwrap()));
+    // Asked before anything else reads the terminal's answer.
+    let graphics = Graphics { ascii, ..Graphics::detect(&mut stdout) };
+    let stdin = async_stdin();
+    // Boards larger than the terminal are drawn through a window onto them.
+    if let Ok((cols @ 1.., rows @ 1..)) = termion::terminal_size() {
+        game.fit_view(cols, rows);
+    }
+    let mut session = Session::new(game, panels, image_options, screensaver_after);
+    session.set_graphics(graphics);
+    session.set_themes(themes);
+    session.follow_terminal_size();
+    if let Some(path) = checkpoint_path {
+        session.autosave(Checkpointer::new(&path, checkpoint_every));
+    }
+    if let Some(path) = follow_path {
+        session.watch(&path);
+    }
+    if let Some(path) = config_path {
+        session.watch_config(&path.display().to_string(), config_overridden);
+    }
+    if let Some(path) = themes_path {
+        session.watch_themes(&path.display().to_string());
+    }
+    session.set_fade(fade);
+    if let Some(density) = density {session.set_density(density);}
+    match (soup_seed, seed) {
+        (Some(soup_seed), _) => session.set_soup_seed(soup_seed),
+        (None, Some(seed)) => session.set_seed(seed),
+        (None, None) => {}
+    }
+    if let Some(millis) = tick_millis.map(|m| m as u64).or(saved_tick_millis) {session.set_tick_millis(millis);}
+    session.stop_after(max_run);
+    session.set_quicksave(quicksave);
+    if let Some(depth) = layers {session.set_layers(depth, rule3d);}
+    session.run(&mut stdin.events(), &mut stdout);
 }
@@ -0,0 +1,131 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::time::Instant;
+
+use crate::Universe;
+
+
+const NOTE_ON: u8 = 0x90;
+const NOTE_OFF: u8 = 0x80;
+const LOWEST_NOTE: usize = 36;
+const NOTE_RANGE: usize = 60;
+const TICKS_PER_QUARTER: u16 = 480;
+// The default tempo of 120 bpm makes a quarter note 500ms long.
+const MILLIS_PER_QUARTER: u128 = 500;
+
+
+enum Sink {
+    /// Events are written as they happen, e.g. to a MIDI device or a FIFO.
+    Stream(File),
+    /// Events are collected and saved as a Standard MIDI File on exit.
+    SmfFile {
+        path: String,
+        track: Vec<u8>,
+        last: Instant,
+    },
+}
+
+
+/// Emits a note for every birth: the pitch follows the column of the cell
+/// and the velocity the number of neighbours it was born with.
+pub struct MidiOut {
+    sink: Sink,
+    playing: Vec<u8>,
+}
+
+
+impl MidiOut {
+    /// Opens `path` for live output, or records into a `.mid` file when the
+    /// path has that extension.
+    pub fn open(path: &str) -> io::Result<MidiOut> {
+        let sink = if path.ends_with(".mid") {
+            Sink::SmfFile {
+                path: path.to_string(),
+                track: Vec::new(),
+                last: Instant::now(),
+            }
+        } else {
+            Sink::Stream(OpenOptions::new().write(true).open(path)?)
+        };
+        Ok(MidiOut { sink, playing: Vec::new() })
+    }
+
+    /// Ends the notes of the previous generation and starts one note per
+    /// column with births in the latest generation.
+    pub fn play(&mut self, universe: &Universe) {
+        let mut velocities = [0u8; 128];
+        if let Some(previous) = universe.history.previous() {
+            for row in 0..universe.height {
+                for col in 0..universe.width {
+                    let idx = universe.get_index(row, col);
+                    if previous[idx] || !universe.cells[idx] {continue;}
+                    let note = LOWEST_NOTE + col * NOTE_RANGE / universe.width.max(1);
+                    let velocity = 40 + 10 * universe.live_neighbour_count(row, col) as u8;
+                    velocities[note] = velocities[note].max(velocity.min(127));
+                }
+            }
+        }
+
+        let mut events = Vec::new();
+        for note in self.playing.drain(..) {
+            events.extend([NOTE_OFF, note, 0]);
+        }
+        for (note, &velocity) in velocities.iter().enumerate().filter(|(_, v)| **v > 0) {
+            events.extend([NOTE_ON, note as u8, velocity]);
+            self.playing.push(note as u8);
+        }
+        self.send(&events);
+    }
+
+    fn send(&mut self, events: &[u8]) {
+        match &mut self.sink {
+            Sink::Stream(file) => {
+                // A disconnected device must not stop the simulation.
+                let _ = file.write_all(events).and_then(|_| file.flush());
+            }
+            Sink::SmfFile { track, last, .. } => {
+                let ticks = last.elapsed().as_millis() * TICKS_PER_QUARTER as u128 / MILLIS_PER_QUARTER;
+                *last = Instant::now();
+                for (i, event) in events.chunks(3).enumerate() {
+                    let delta = if i == 0 {ticks as u32} else {0};
+                    push_varlen(track, delta);
+                    track.extend_from_slice(event);
+                }
+            }
+        }
+    }
+}
+
+
+impl Drop for MidiOut {
+    fn drop(&mut self) {
+        let notes: Vec<u8> = self.playing.drain(..).flat_map(|n| [NOTE_OFF, n, 0]).collect();
+        self.send(&notes);
+
+        if let Sink::SmfFile { path, track, .. } = &mut self.sink {
+            track.extend([0x00, 0xff, 0x2f, 0x00]);
+            let mut smf = Vec::new();
+            smf.extend(b"MThd");
+            smf.extend(6u32.to_be_bytes());
+            smf.extend(0u16.to_be_bytes());
+            smf.extend(1u16.to_be_bytes());
+            smf.extend(TICKS_PER_QUARTER.to_be_bytes());
+            smf.extend(b"MTrk");
+            smf.extend((track.len() as u32).to_be_bytes());
+            smf.extend(track.iter());
+            let _ = fs::write(path, smf);
+        }
+    }
+}
+
+
+/// Writes a MIDI variable length quantity.
+fn push_varlen(bytes: &mut Vec<u8>, value: u32) {
+    let mut groups = vec![(value & 0x7f) as u8];
+    let mut rest = value >> 7;
+    while rest > 0 {
+        groups.push((rest & 0x7f) as u8 | 0x80);
+        rest >>= 7;
+    }
+    bytes.extend(groups.iter().rev());
+}
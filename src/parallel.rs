@@ -0,0 +1,31 @@
+#[cfg(feature = "parallel")]
+use std::thread;
+
+
+/// Boards of fewer items, cells or packed words, are stepped on one
+/// thread; starting threads would take longer than the step.
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_ITEMS: usize = 40_000;
+
+
+/// Calls `step` on runs of whole rows of `out`, `width` items to a row,
+/// with the index of the first row of each run. With the `parallel`
+/// feature large boards are split into one run per thread the machine can
+/// run at once; otherwise `step` gets all of `out`.
+pub fn for_row_runs<T: Send>(out: &mut [T], width: usize, step: impl Fn(usize, &mut [T]) + Sync) {
+    #[cfg(feature = "parallel")]
+    if out.len() >= PARALLEL_MIN_ITEMS {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let rows = (out.len() / width).div_ceil(threads).max(1);
+        let step = &step;
+        thread::scope(|scope| {
+            for (i, run) in out.chunks_mut(rows * width).enumerate() {
+                scope.spawn(move || step(i * rows, run));
+            }
+        });
+        return;
+    }
+    #[cfg(not(feature = "parallel"))]
+    let _ = width;
+    step(0, out);
+}
@@ -0,0 +1,383 @@
+use std::io::Write;
+
+use crate::diagnostic::Diagnostic;
+use crate::rule::Rule;
+
+
+/// Golly and the LifeWiki keep RLE lines at most this long.
+const RLE_LINE_MAX: usize = 70;
+
+
+/// A rectangular pattern of live cells that can be stamped onto a `Universe`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    /// Live cells as (row, col) offsets from the top-left corner.
+    pub cells: Vec<(usize, usize)>,
+    /// The rule the pattern was made for, if the file says.
+    pub rule: Option<Rule>,
+}
+
+
+/// A quarter turn or a mirror image, to aim a pattern before placing it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transform {
+    RotateRight,
+    RotateLeft,
+    /// Left and right swapped.
+    FlipHorizontal,
+    /// Upside down.
+    FlipVertical,
+}
+
+
+impl Transform {
+    pub fn name(self) -> &'static str {
+        match self {
+            Transform::RotateRight => "rotated clockwise",
+            Transform::RotateLeft => "rotated anticlockwise",
+            Transform::FlipHorizontal => "flipped left to right",
+            Transform::FlipVertical => "flipped upside down",
+        }
+    }
+}
+
+
+impl Pattern {
+    /// Parses a plaintext drawing where `O` (or `*`) is alive and anything
+    /// else is dead. Lines starting with `!` are comments.
+    pub fn from_plaintext(text: &str) -> Pattern {
+        Pattern::parse_plaintext(text).0
+    }
+
+    /// Like `from_plaintext`, but also returns a warning for every
+    /// character that is neither a cell nor blank. Those are read as dead.
+    pub fn parse_plaintext(text: &str) -> (Pattern, Vec<Diagnostic>) {
+        let mut cells = Vec::new();
+        let mut warnings = Vec::new();
+        let mut width = 0;
+        let mut height = 0;
+
+        for (n, line) in text.lines().enumerate() {
+            if line.starts_with('!') {continue;}
+            let line = line.trim_end();
+            for (col, ch) in line.chars().enumerate() {
+                match ch {
+                    'O' | '*' => cells.push((height, col)),
+                    '.' => {}
+                    c if c.is_whitespace() => {}
+                    c => warnings.push(Diagnostic::new(
+                        n + 1, col + 1,
+                        format!("unexpected `{}`, expected `O` or `.` (read as dead)", c))),
+                }
+            }
+            width = width.max(line.chars().count());
+            height += 1;
+        }
+
+        (Pattern { width, height, cells, rule: None }, warnings)
+    }
+
+    /// Parses free-form ASCII art: every character other than whitespace
+    /// and `.` is a live cell.
+    pub fn from_ascii(text: &str) -> Pattern {
+        let mut cells = Vec::new();
+        let mut width = 0;
+        let mut height = 0;
+
+        for line in text.lines() {
+            for (col, ch) in line.chars().enumerate() {
+                if !ch.is_whitespace() && ch != '.' {
+                    cells.push((height, col));
+                }
+            }
+            width = width.max(line.trim_end().chars().count());
+            height += 1;
+        }
+
+        Pattern { width, height, cells, rule: None }
+    }
+
+    /// Parses a run length encoded pattern as used by Golly and the
+    /// LifeWiki: `#` comment lines, a header like `x = 3, y = 3, rule = B3/S23`
+    /// and runs of `b` (dead) and `o` (alive) cells with `$` ending a row
+    /// and `!` ending the pattern. Also returns a warning for every row
+    /// wider than `x`, cut off at the edge, for rows below `y`, dropped,
+    /// and for characters that are not tags, read as dead. Only a missing
+    /// or broken header or a run count too large to read is an error.
+    pub fn parse_rle(text: &str) -> Result<(Pattern, Vec<Diagnostic>), Diagnostic> {
+        let mut lines = text.lines().enumerate()
+            .filter(|(_, line)| !line.trim_start().starts_with('#') && !line.trim().is_empty());
+        let (n, header) = lines.next()
+            .ok_or_else(|| Diagnostic::new(1, 1, "expected a header like `x = 3, y = 3`"))?;
+        let (width, height, rule) = parse_rle_header(n + 1, header)?;
+
+        let mut cells = Vec::new();
+        let mut warnings = Vec::new();
+        let (mut row, mut col): (usize, usize) = (0, 0);
+        let mut count: Option<usize> = None;
+        // Each too wide row, and the rows below `y`, are warned about once.
+        let (mut wide_row, mut too_tall) = (None, false);
+        'body: for (n, line) in lines {
+            for (i, c) in line.chars().enumerate() {
+                let diagnostic = |message: String| Diagnostic::new(n + 1, i + 1, message);
+                let run = count.unwrap_or(1);
+                match c {
+                    '0'..='9' => {
+                        let digit = c.to_digit(10).unwrap() as usize;
+                        count = Some(count.unwrap_or(0).checked_mul(10).and_then(|n| n.checked_add(digit))
+                            .ok_or_else(|| diagnostic("run count is too large".to_string()))?);
+                        continue;
+                    }
+                    'b' | 'o' => {
+                        let end = col.saturating_add(run);
+                        if end > width && wide_row != Some(row) {
+                            warnings.push(diagnostic(format!("row {} is wider than x = {} (cut off)", row + 1, width)));
+                            wide_row = Some(row);
+                        }
+                        if c == 'o' && row >= height && !too_tall {
+                            warnings.push(diagnostic(format!("the pattern is taller than y = {} (rows below dropped)", height)));
+                            too_tall = true;
+                        }
+                        if c == 'o' && row < height {cells.extend((col..end.min(width)).map(|c| (row, c)));}
+                        col = end;
+                    }
+                    '$' => {
+                        row = row.saturating_add(run);
+                        col = 0;
+                    }
+                    '!' => break 'body,
+                    c if c.is_whitespace() => {
+                        if count.is_some() {
+                            warnings.push(diagnostic("expected `b`, `o` or `$` after a run count (ignored)".to_string()));
+                        }
+                    }
+                    c => {
+                        warnings.push(diagnostic(format!(
+                            "unexpected `{}`, expected `b`, `o`, `$`, `!` or a run count (read as dead)", c)));
+                        col = col.saturating_add(run);
+                    }
+                }
+                count = None;
+            }
+        }
+
+        Ok((Pattern { width, height, cells, rule }, warnings))
+    }
+
+    /// Parses a Life 1.06 pattern: a `#Life 1.06` header, more `#` lines,
+    /// and one live cell per line as its column and row, which may be
+    /// negative. The pattern starts at the top left live cell. Also returns
+    /// a warning for every line that is not a cell, which is skipped; only
+    /// a missing header is an error.
+    pub fn parse_life106(text: &str) -> Result<(Pattern, Vec<Diagnostic>), Diagnostic> {
+        let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        match lines.next() {
+            Some((_, header)) if header.trim() == "#Life 1.06" => {}
+            Some((n, header)) if header.trim() == "#Life 1.05" => {
+                return Err(Diagnostic::new(n + 1, 1, "Life 1.05 files are not supported, only Life 1.06"));
+            }
+            Some((n, _)) => return Err(Diagnostic::new(n + 1, 1, "expected a `#Life 1.06` header")),
+            None => return Err(Diagnostic::new(1, 1, "expected a `#Life 1.06` header")),
+        }
+
+        let mut live = Vec::new();
+        let mut warnings = Vec::new();
+        for (n, line) in lines {
+            if line.trim_start().starts_with('#') {continue;}
+            // Each word with the column it starts at.
+            let words: Vec<(usize, &str)> = line.split_whitespace()
+                .map(|word| (line[..word.as_ptr() as usize - line.as_ptr() as usize].chars().count() + 1, word))
+                .collect();
+            let number = |&(column, word): &(usize, &str)| word.parse::<i32>().map_err(|_| {
+                Diagnostic::new(n + 1, column, format!("`{}` is not a whole number (line skipped)", word))
+            });
+            match words.as_slice() {
+                [x, y] => match (number(x), number(y)) {
+                    (Ok(x), Ok(y)) => live.push((y as i64, x as i64)),
+                    (Err(warning), _) | (_, Err(warning)) => warnings.push(warning),
+                },
+                [.., (column, _)] => warnings.push(Diagnostic::new(
+                    n + 1, *column, format!("expected a column and a row, found `{}` (line skipped)", line.trim()))),
+                [] => unreachable!(),
+            }
+        }
+
+        let top = live.iter().map(|&(y, _)| y).min().unwrap_or(0);
+        let left = live.iter().map(|&(_, x)| x).min().unwrap_or(0);
+        let mut cells: Vec<(usize, usize)> = live.iter().map(|&(y, x)| ((y - top) as usize, (x - left) as usize)).collect();
+        cells.sort_unstable();
+        cells.dedup();
+        let height = cells.iter().map(|&(r, _)| r + 1).max().unwrap_or(0);
+        let width = cells.iter().map(|&(_, c)| c + 1).max().unwrap_or(0);
+        Ok((Pattern { width, height, cells, rule: None }, warnings))
+    }
+
+    /// Parses pattern text of any kind, as pasted or piped in: Life 1.06
+    /// after a `#Life` header, RLE when it starts with an `x = ..` header,
+    /// plaintext otherwise. Unlike in files, anything the parsers would
+    /// warn about is an error.
+    pub fn parse(text: &str) -> Result<Pattern, Diagnostic> {
+        let first = text.lines().map(str::trim).find(|line| !line.is_empty() && !line.starts_with('#'));
+        let (pattern, warnings) = if text.trim_start().starts_with("#Life") {
+            Pattern::parse_life106(text)?
+        } else if first.is_some_and(|line| line.starts_with('x')) {
+            Pattern::parse_rle(text)?
+        } else {
+            Pattern::parse_plaintext(text)
+        };
+        match warnings.into_iter().next() {
+            Some(warning) => Err(warning),
+            None => Ok(pattern),
+        }
+    }
+
+    /// Writes the pattern as RLE with an `x = .., y = .., rule = ..` header,
+    /// see `parse_rle`. Lines are wrapped at 70 characters.
+    pub fn to_rle(&self, rule: Rule) -> String {
+        let mut rows = vec![Vec::new(); self.height];
+        for &(row, col) in &self.cells {rows[row].push(col);}
+
+        // Runs of (count, tag); dead cells at the end of rows are left out
+        // and empty rows merge into the `$` before them.
+        let mut runs: Vec<(usize, char)> = Vec::new();
+        let push = |runs: &mut Vec<(usize, char)>, count: usize, tag: char| {
+            if count == 0 {return;}
+            match runs.last_mut() {
+                Some((n, t)) if *t == tag => *n += count,
+                _ => runs.push((count, tag)),
+            }
+        };
+        for (row, cols) in rows.iter_mut().enumerate() {
+            cols.sort_unstable();
+            if row > 0 {push(&mut runs, 1, '$');}
+            let mut col = 0;
+            for &c in cols.iter() {
+                push(&mut runs, c - col, 'b');
+                push(&mut runs, 1, 'o');
+                col = c + 1;
+            }
+        }
+        while runs.last().is_some_and(|&(_, tag)| tag == '$') {runs.pop();}
+
+        let mut text = format!("x = {}, y = {}, rule = {}\n", self.width, self.height, rule);
+        let mut line = String::new();
+        let items = runs.iter().map(|&(n, tag)| if n == 1 {tag.to_string()} else {format!("{}{}", n, tag)});
+        for item in items.chain(["!".to_string()]) {
+            if line.len() + item.len() > RLE_LINE_MAX {
+                text += &line;
+                text.push('\n');
+                line.clear();
+            }
+            line += &item;
+        }
+        text += &line;
+        text.push('\n');
+        text
+    }
+
+    /// Writes the pattern as plaintext with `O` for live and `.` for dead
+    /// cells, preceded by a `!Name:` comment when given a name.
+    pub fn to_plaintext(&self, name: Option<&str>) -> String {
+        let mut grid = vec![vec!['.'; self.width]; self.height];
+        for &(row, col) in &self.cells {grid[row][col] = 'O';}
+
+        let mut text = String::new();
+        if let Some(name) = name {text += &format!("!Name: {}\n", name);}
+        for row in grid {
+            text.extend(row);
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Writes the pattern as Life 1.06, see `parse_life106`, with the top
+    /// left corner at 0 0.
+    pub fn to_life106(&self) -> String {
+        let mut cells = self.cells.clone();
+        cells.sort_unstable();
+        let mut text = "#Life 1.06\n".to_string();
+        for (row, col) in cells {text += &format!("{} {}\n", col, row);}
+        text
+    }
+
+    /// The part of the pattern inside the given rectangle.
+    pub fn crop(&self, top: usize, left: usize, height: usize, width: usize) -> Pattern {
+        let cells = self.cells.iter()
+            .filter(|&&(r, c)| r >= top && r < top + height && c >= left && c < left + width)
+            .map(|&(r, c)| (r - top, c - left))
+            .collect();
+        Pattern {
+            width: width.min(self.width.saturating_sub(left)),
+            height: height.min(self.height.saturating_sub(top)),
+            cells,
+            rule: self.rule,
+        }
+    }
+
+    /// The pattern turned or mirrored, a turn swapping its width and
+    /// height.
+    pub fn transformed(&self, transform: Transform) -> Pattern {
+        let (width, height) = (self.width, self.height);
+        let mut cells: Vec<(usize, usize)> = self.cells.iter().map(|&(r, c)| match transform {
+            Transform::RotateRight => (c, height - 1 - r),
+            Transform::RotateLeft => (width - 1 - c, r),
+            Transform::FlipHorizontal => (r, width - 1 - c),
+            Transform::FlipVertical => (height - 1 - r, c),
+        }).collect();
+        cells.sort_unstable();
+        let turned = matches!(transform, Transform::RotateRight | Transform::RotateLeft);
+        let (width, height) = if turned {(height, width)} else {(width, height)};
+        Pattern { width, height, cells, rule: self.rule }
+    }
+
+    /// Draws the pattern shrunk to fit `rows` by `cols` characters: each
+    /// character stands for a block of cells and is filled if any of them
+    /// is alive. With `ascii` only ASCII characters are drawn.
+    pub fn render_preview(&self, stdout: &mut dyn Write, rows: usize, cols: usize, ascii: bool) {
+        let block_rows = self.height.div_ceil(rows.max(1)).max(1);
+        let block_cols = self.width.div_ceil(cols.max(1)).max(1);
+        let (rows, cols) = (self.height.div_ceil(block_rows), self.width.div_ceil(block_cols));
+        let mut grid = vec![vec![false; cols]; rows];
+        for &(r, c) in &self.cells {grid[r / block_rows][c / block_cols] = true;}
+
+        let (alive, dead) = if ascii {('#', '.')} else {('█', '·')};
+        for row in grid {
+            let line: String = row.iter().map(|&a| if a {alive} else {dead}).collect();
+            write!(stdout, "{}\n\r", line).unwrap();
+        }
+    }
+}
+
+
+/// Reads `x = <width>, y = <height>` and an optional `rule = <rule>`, the
+/// rule in B/S or S/B (`23/3`) notation.
+fn parse_rle_header(line_number: usize, line: &str) -> Result<(usize, usize, Option<Rule>), Diagnostic> {
+    let (mut width, mut height, mut rule) = (None, None, None);
+    let mut column = 1;
+    for part in line.split(',') {
+        let error = |message: String| Diagnostic::new(line_number, column, message);
+        let (key, value) = part.split_once('=')
+            .ok_or_else(|| error(format!("expected `key = value` in the header, found `{}`", part.trim())))?;
+        let value = value.trim();
+        let number = || value.parse::<usize>().map_err(|_| error(format!("expected a number, found `{}`", value)));
+        match key.trim() {
+            "x" => width = Some(number()?),
+            "y" => height = Some(number()?),
+            "rule" => {
+                let rule_text = match value.split_once('/') {
+                    Some((s, b)) if s.chars().chain(b.chars()).all(|c| c.is_ascii_digit()) => format!("B{}/S{}", b, s),
+                    _ => value.to_string(),
+                };
+                rule = Some(Rule::parse(&rule_text).map_err(error)?);
+            }
+            key => return Err(error(format!("unknown header key `{}`, expected x, y or rule", key))),
+        }
+        column += part.chars().count() + 1;
+    }
+    match (width, height) {
+        (Some(width), Some(height)) => Ok((width, height, rule)),
+        _ => Err(Diagnostic::new(line_number, 1, "the header needs both `x = ...` and `y = ...`")),
+    }
+}
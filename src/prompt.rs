@@ -0,0 +1,43 @@
+use std::io::Write;
+
+use termion::event::Key;
+
+
+pub enum PromptAction {
+    None,
+    Cancel,
+    Submit(String),
+}
+
+
+/// A single line text input shown below the board.
+pub struct Prompt {
+    label: String,
+    text: String,
+}
+
+
+impl Prompt {
+    pub fn new(label: &str) -> Prompt {
+        Prompt {
+            label: label.to_string(),
+            text: String::new(),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: Key) -> PromptAction {
+        match key {
+            Key::Esc => return PromptAction::Cancel,
+            Key::Char('\n') => return PromptAction::Submit(self.text.trim().to_string()),
+            Key::Backspace => {self.text.pop();}
+            Key::Char(c) if !c.is_control() => {self.text.push(c);}
+            _ => {}
+        }
+        PromptAction::None
+    }
+
+    pub fn render(&self, stdout: &mut dyn Write) {
+        write!(stdout, "{} {}_\n\r", self.label, self.text).unwrap();
+        write!(stdout, "[Enter] confirm  [Esc] cancel\n\r").unwrap();
+    }
+}
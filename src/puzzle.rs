@@ -0,0 +1,326 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use termion::event::Key;
+
+use crate::diagnostic::Diagnostic;
+use crate::pattern::Pattern;
+use crate::Universe;
+
+
+const BUNDLED: [&str; 3] = [
+    include_str!("../puzzles/01-stay-alive.puzzle"),
+    include_str!("../puzzles/02-hive-builder.puzzle"),
+    include_str!("../puzzles/03-glider-catcher.puzzle"),
+];
+const EXTENSION: &str = "puzzle";
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Goal {
+    /// The board has to match the target within the generation limit.
+    Reach,
+    /// The board must not match the target before the generation limit.
+    Avoid,
+}
+
+
+#[derive(Clone)]
+pub struct Level {
+    pub name: String,
+    pub description: String,
+    pub goal: Goal,
+    pub max_edits: usize,
+    pub max_generations: usize,
+    pub width: usize,
+    pub height: usize,
+    pub start: Pattern,
+    pub target: Pattern,
+}
+
+
+impl Level {
+    /// Parses a level file made of `key: value` lines followed by the
+    /// `start:` and `target:` boards.
+    pub fn parse(text: &str) -> Result<Level, String> {
+        Level::parse_with_warnings(text).map(|(level, _)| level)
+    }
+
+    /// Like `parse`, but unknown keys and stray characters in the boards
+    /// are skipped with a warning instead of failing the whole level.
+    pub fn parse_with_warnings(text: &str) -> Result<(Level, Vec<Diagnostic>), String> {
+        let mut name = None;
+        let mut description = String::new();
+        let mut goal = None;
+        let mut max_edits = None;
+        let mut max_generations = None;
+        let mut start = String::new();
+        let mut target = String::new();
+        let mut board: Option<&mut String> = None;
+        let mut warnings = Vec::new();
+
+        for (n, raw) in text.lines().enumerate() {
+            let n = n + 1;
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {continue;}
+            let indent = raw.len() - raw.trim_start().len();
+
+            if let Some(b) = board.as_mut() {
+                if !line.contains(':') {
+                    for (col, c) in line.chars().enumerate() {
+                        if c == '.' || c == 'O' {
+                            b.push(c);
+                        } else {
+                            let message = format!("unexpected `{}` in board, expected `O` or `.` (read as dead)", c);
+                            warnings.push(Diagnostic::new(n, indent + col + 1, message));
+                            b.push('.');
+                        }
+                    }
+                    b.push('\n');
+                    continue;
+                }
+            }
+
+            let (key, value) = line.split_once(':')
+                .ok_or(Diagnostic::new(n, indent + 1, "expected `key: value`").to_string())?;
+            let column = indent + key.len() + 2 + (value.len() - value.trim_start().len());
+            let value = value.trim();
+            let error = |message: &str| Diagnostic::new(n, column, message).to_string();
+            board = None;
+            match key.trim() {
+                "name" => {name = Some(value.to_string());}
+                "description" => {description = value.to_string();}
+                "goal" => {
+                    goal = Some(match value {
+                        "reach" => Goal::Reach,
+                        "avoid" => Goal::Avoid,
+                        _ => return Err(error(&format!("expected `reach` or `avoid` as goal, found `{}`", value))),
+                    });
+                }
+                "edits" => {
+                    max_edits = Some(value.parse()
+                        .map_err(|_| error(&format!("expected a number of edits, found `{}`", value)))?);
+                }
+                "generations" => {
+                    max_generations = Some(value.parse()
+                        .map_err(|_| error(&format!("expected a number of generations, found `{}`", value)))?);
+                }
+                "start" => {board = Some(&mut start);}
+                "target" => {board = Some(&mut target);}
+                other => {
+                    let message = format!("unknown key `{}`, expected name, description, goal, \
+                                           edits, generations, start or target (skipped)", other);
+                    warnings.push(Diagnostic::new(n, indent + 1, message));
+                }
+            }
+        }
+
+        let start = Pattern::from_plaintext(&start);
+        let target = Pattern::from_plaintext(&target);
+        if start.height == 0 {return Err("missing start board".to_string());}
+        if (start.width, start.height) != (target.width, target.height) {
+            return Err(format!("start board is {}x{} but target board is {}x{}",
+                               start.width, start.height, target.width, target.height));
+        }
+
+        let level = Level {
+            name: name.ok_or("missing name")?,
+            description,
+            goal: goal.ok_or("missing goal")?,
+            max_edits: max_edits.ok_or("missing edits")?,
+            max_generations: max_generations.ok_or("missing generations")?,
+            width: start.width,
+            height: start.height,
+            start,
+            target,
+        };
+        Ok((level, warnings))
+    }
+
+    pub fn bundled() -> Vec<Level> {
+        BUNDLED.iter().map(|text| Level::parse(text).unwrap()).collect()
+    }
+
+    /// Loads every `.puzzle` file in `dir` in file name order. Files that
+    /// fail to parse, and warnings for those that did, are reported in the
+    /// second list.
+    pub fn load_dir(dir: &Path) -> io::Result<(Vec<Level>, Vec<String>)> {
+        let mut paths: Vec<_> = fs::read_dir(dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == EXTENSION))
+            .collect();
+        paths.sort();
+
+        let mut levels = Vec::new();
+        let mut errors = Vec::new();
+        for path in paths {
+            let parsed = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| Level::parse_with_warnings(&text));
+            match parsed {
+                Ok((level, warnings)) => {
+                    errors.extend(warnings.iter().map(|w| format!("{}: warning: {}", path.display(), w)));
+                    levels.push(level);
+                }
+                Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+            }
+        }
+        Ok((levels, errors))
+    }
+
+    /// Creates a universe set up with the level's start board.
+    pub fn universe(&self) -> Universe {
+        let mut universe = Universe::new(self.width, self.height);
+        universe.stamp(&self.start, 0, 0);
+        universe
+    }
+}
+
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Editing,
+    Running,
+    Won,
+    Lost,
+}
+
+
+/// A level being played: counts edits and generations and decides the
+/// outcome. Editing is only possible before the simulation is started.
+pub struct PuzzleSession {
+    pub level: Level,
+    edits: usize,
+    generation: usize,
+    state: State,
+}
+
+
+impl PuzzleSession {
+    pub fn new(level: Level) -> PuzzleSession {
+        PuzzleSession {
+            level,
+            edits: 0,
+            generation: 0,
+            state: State::Editing,
+        }
+    }
+
+    /// Uses up one edit, or explains why the board can't be changed.
+    pub fn try_edit(&mut self) -> Result<(), &'static str> {
+        if self.state != State::Editing {
+            return Err("The simulation has started, press C to restart the level");
+        }
+        if self.edits >= self.level.max_edits {
+            return Err("No edits left, press C to restart the level");
+        }
+        self.edits += 1;
+        Ok(())
+    }
+
+    pub fn after_tick(&mut self, universe: &Universe) {
+        if self.state == State::Won || self.state == State::Lost {return;}
+        self.state = State::Running;
+        self.generation += 1;
+
+        let matches = universe.live_cells() == self.level.target.cells;
+        let out_of_time = self.generation >= self.level.max_generations;
+        self.state = match (self.level.goal, matches, out_of_time) {
+            (Goal::Reach, true, _) => State::Won,
+            (Goal::Reach, false, true) => State::Lost,
+            (Goal::Avoid, true, _) => State::Lost,
+            (Goal::Avoid, false, true) => State::Won,
+            _ => State::Running,
+        };
+    }
+
+    /// `solved` or `failed` once the level is decided.
+    pub fn outcome(&self) -> Option<&'static str> {
+        match self.state {
+            State::Won => Some("solved"),
+            State::Lost => Some("failed"),
+            State::Editing | State::Running => None,
+        }
+    }
+
+    pub fn render(&self, stdout: &mut dyn Write) {
+        let level = &self.level;
+        write!(stdout, "Puzzle: {} - {}\n\r", level.name, level.description).unwrap();
+        write!(stdout, "Edits: {}/{}  Generation: {}/{}  ",
+               self.edits, level.max_edits, self.generation, level.max_generations).unwrap();
+        match self.state {
+            State::Editing | State::Running => {}
+            State::Won => write!(stdout, "SOLVED! ").unwrap(),
+            State::Lost => write!(stdout, "Failed, press C to retry ").unwrap(),
+        }
+        write!(stdout, "[Esc] leave puzzle\n\r").unwrap();
+
+        let goal = match level.goal {
+            Goal::Reach => "Reach this board:",
+            Goal::Avoid => "Never reach this board:",
+        };
+        write!(stdout, "{}\n\r", goal).unwrap();
+        for row in 0..level.height {
+            for col in 0..level.width {
+                let alive = level.target.cells.contains(&(row, col));
+                write!(stdout, "{}", if alive {'O'} else {'.'}).unwrap();
+            }
+            write!(stdout, "\n\r").unwrap();
+        }
+    }
+}
+
+
+pub enum PickerAction {
+    None,
+    Close,
+    Start(Box<Level>),
+}
+
+
+/// Lists the available levels so one can be started.
+pub struct PuzzlePicker {
+    selected: usize,
+}
+
+
+impl PuzzlePicker {
+    pub fn new() -> PuzzlePicker {
+        PuzzlePicker { selected: 0 }
+    }
+
+    pub fn handle_key(&mut self, key: Key, levels: &[Level]) -> PickerAction {
+        match key {
+            Key::Esc => PickerAction::Close,
+            Key::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                PickerAction::None
+            }
+            Key::Down if self.selected + 1 < levels.len() => {
+                self.selected += 1;
+                PickerAction::None
+            }
+            Key::Char('\n') => match levels.get(self.selected) {
+                Some(level) => PickerAction::Start(Box::new(level.clone())),
+                None => PickerAction::None,
+            },
+            _ => PickerAction::None,
+        }
+    }
+
+    pub fn render(&self, stdout: &mut dyn Write, levels: &[Level], errors: &[String]) {
+        write!(stdout, "Puzzles:\n\r").unwrap();
+        for (i, level) in levels.iter().enumerate() {
+            let marker = if i == self.selected {'>'} else {' '};
+            write!(stdout, "{} {} ({}x{})\n\r", marker, level.name, level.height, level.width).unwrap();
+        }
+        if levels.is_empty() {
+            write!(stdout, "  (no levels found)\n\r").unwrap();
+        }
+        for error in errors {
+            write!(stdout, "! {}\n\r", error).unwrap();
+        }
+        write!(stdout, "[Up/Down] select  [Enter] play  [Esc] close\n\r").unwrap();
+    }
+}
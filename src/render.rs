@@ -0,0 +1,196 @@
+use std::io::Write;
+
+use crate::kitty;
+use crate::terminal;
+
+
+/// The device attribute of terminals that show sixel graphics.
+const SIXEL_ATTRIBUTE: u32 = 4;
+
+
+/// How the board is drawn on the terminal: a glyph per cell, several
+/// cells packed into one character to see more of a large board, or an
+/// image on terminals with graphics.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderMode {
+    /// Two columns per cell, drawn with the glyph set.
+    Cells,
+    /// Two cells one above the other per character, as the upper and
+    /// lower half of a block in their own colours.
+    HalfBlock,
+    /// 2 by 2 cells per character, as the quarters of a block.
+    Quadrant,
+    /// 2 by 4 cells per character, as the dots of a Braille pattern.
+    Braille,
+    /// An image with a square of pixels per cell, sent as sixel graphics.
+    /// How many cells a character covers depends on the size of its
+    /// pixels, which the universe works out.
+    Sixel,
+    /// The same image sent with the kitty graphics protocol.
+    Kitty,
+    /// The first of `AUTO_MODES` the whole board fits the terminal in,
+    /// see `resolve`; measured like `Cells` until resolved.
+    Auto,
+}
+
+
+pub const RENDER_MODES: [RenderMode; 7] = [
+    RenderMode::Cells,
+    RenderMode::HalfBlock,
+    RenderMode::Quadrant,
+    RenderMode::Braille,
+    RenderMode::Sixel,
+    RenderMode::Kitty,
+    RenderMode::Auto,
+];
+
+
+/// The modes `Auto` chooses from, from the largest cells to the smallest.
+const AUTO_MODES: [RenderMode; 3] = [RenderMode::Cells, RenderMode::Quadrant, RenderMode::Braille];
+
+
+impl RenderMode {
+    pub fn parse(name: &str) -> Result<RenderMode, String> {
+        RENDER_MODES.iter().copied().find(|mode| mode.name() == name).ok_or_else(|| {
+            let names: Vec<&str> = RENDER_MODES.iter().map(|mode| mode.name()).collect();
+            format!("unknown render mode `{}`, expected {}", name, names.join(", "))
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderMode::Cells => "cells",
+            RenderMode::HalfBlock => "half-block",
+            RenderMode::Quadrant => "quadrant",
+            RenderMode::Braille => "braille",
+            RenderMode::Sixel => "sixel",
+            RenderMode::Kitty => "kitty",
+            RenderMode::Auto => "auto",
+        }
+    }
+
+    /// The mode `by` places further along `RENDER_MODES`, wrapping around,
+    /// skipping those `graphics` cannot show.
+    pub fn cycle(self, by: isize, graphics: Graphics) -> RenderMode {
+        let modes: Vec<RenderMode> = RENDER_MODES.into_iter().filter(|&mode| graphics.supports(mode)).collect();
+        let at = modes.iter().position(|&mode| mode == self).unwrap_or(0) as isize;
+        modes[(at + by).rem_euclid(modes.len() as isize) as usize]
+    }
+
+    /// How many cells across and down one character stands for.
+    pub fn cells_per_char(self) -> (usize, usize) {
+        match self {
+            RenderMode::Cells | RenderMode::Sixel | RenderMode::Kitty | RenderMode::Auto => (1, 1),
+            RenderMode::HalfBlock => (1, 2),
+            RenderMode::Quadrant => (2, 2),
+            RenderMode::Braille => (2, 4),
+        }
+    }
+
+    /// How many terminal columns one character takes up, with glyphs two
+    /// columns wide for `Cells`.
+    pub fn char_width(self) -> usize {
+        match self {
+            RenderMode::Cells | RenderMode::Auto => 2,
+            RenderMode::HalfBlock | RenderMode::Quadrant | RenderMode::Braille => 1,
+            RenderMode::Sixel | RenderMode::Kitty => 1,
+        }
+    }
+
+    /// Whether the board is drawn as an image rather than text.
+    pub fn is_image(self) -> bool {
+        matches!(self, RenderMode::Sixel | RenderMode::Kitty)
+    }
+
+    /// The mode to draw in: for `Auto` the first of `AUTO_MODES` that
+    /// `fits`, Braille when none does, otherwise the mode itself.
+    pub fn resolve(self, fits: impl Fn(RenderMode) -> bool) -> RenderMode {
+        if self != RenderMode::Auto {return self;}
+        AUTO_MODES.into_iter().find(|&mode| fits(mode)).unwrap_or(RenderMode::Braille)
+    }
+
+    /// The character for `dots`, by row and column, in the modes that pack
+    /// several cells into one; rows below those of the mode are ignored.
+    pub fn glyph(self, dots: [[bool; 2]; 4]) -> char {
+        match self {
+            RenderMode::Quadrant => quadrant([dots[0], dots[1]]),
+            _ => braille(dots),
+        }
+    }
+}
+
+
+/// An image for the modes that draw one, with few enough colours to be
+/// given as indices into a palette.
+pub struct Picture {
+    /// Indices into `palette`, row by row.
+    pub pixels: Vec<u8>,
+    pub width: usize,
+    pub height: usize,
+    pub palette: Vec<(u8, u8, u8)>,
+}
+
+
+/// Which graphics the terminal can show besides text.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Graphics {
+    pub sixel: bool,
+    pub kitty: bool,
+    /// Only ASCII can be shown, so none of the block or Braille characters
+    /// the modes that pack cells draw with.
+    pub ascii: bool,
+}
+
+
+impl Graphics {
+    /// Asks the terminal what it can show, see `terminal::ask`.
+    pub fn detect(out: &mut dyn Write) -> Graphics {
+        let answer = terminal::ask(out, kitty::QUERY);
+        Graphics {
+            sixel: terminal::device_attributes(&answer).contains(&SIXEL_ATTRIBUTE),
+            kitty: answer.contains("\x1b_Gi=31;OK"),
+            ascii: false,
+        }
+    }
+
+    pub fn supports(self, mode: RenderMode) -> bool {
+        match mode {
+            RenderMode::Sixel => self.sixel,
+            RenderMode::Kitty => self.kitty,
+            RenderMode::HalfBlock | RenderMode::Quadrant | RenderMode::Braille | RenderMode::Auto => !self.ascii,
+            RenderMode::Cells => true,
+        }
+    }
+
+    /// Takes the images drawn before off the screen, as kitty graphics
+    /// stay on it until removed.
+    pub fn clear(self, out: &mut dyn Write) {
+        if self.kitty {write!(out, "{}", kitty::DELETE_ALL).unwrap();}
+    }
+}
+
+
+/// The quadrant block with the quarters of `quarters`, by row and column,
+/// filled.
+pub fn quadrant(quarters: [[bool; 2]; 2]) -> char {
+    // Indexed by upper left, upper right, lower left and lower right as
+    // bits from the lowest.
+    const BLOCKS: [char; 16] = [' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█'];
+    let [[ul, ur], [ll, lr]] = quarters;
+    BLOCKS[ul as usize | (ur as usize) << 1 | (ll as usize) << 2 | (lr as usize) << 3]
+}
+
+
+/// The Braille pattern with the dots of `dots`, given by row and column,
+/// raised.
+pub fn braille(dots: [[bool; 2]; 4]) -> char {
+    // Dots 1-3 and 4-6 run down the columns, 7 and 8 are the bottom row.
+    const BITS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+    let mut code = 0x2800;
+    for (row, bits) in dots.iter().zip(BITS) {
+        for (&raised, bit) in row.iter().zip(bits) {
+            if raised {code |= bit;}
+        }
+    }
+    char::from_u32(code).unwrap()
+}
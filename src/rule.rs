@@ -0,0 +1,115 @@
+use std::fmt;
+
+
+/// A Life-like rule in B/S notation, e.g. `B3/S23` for Conway's Life.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rule {
+    birth: [bool; 9],
+    survival: [bool; 9],
+}
+
+
+impl Rule {
+    pub fn conway() -> Rule {
+        Rule::parse("B3/S23").unwrap()
+    }
+
+    /// Parses `B<digits>/S<digits>`. The parts may come in either order
+    /// and are case insensitive.
+    pub fn parse(text: &str) -> Result<Rule, String> {
+        let mut birth = None;
+        let mut survival = None;
+
+        for part in text.trim().split('/') {
+            let mut chars = part.chars();
+            let target = match chars.next().map(|c| c.to_ascii_uppercase()) {
+                Some('B') => &mut birth,
+                Some('S') => &mut survival,
+                _ => return Err(format!("invalid rule `{}`: expected B.../S...", text)),
+            };
+            let mut counts = [false; 9];
+            for c in chars {
+                match c.to_digit(10) {
+                    Some(d) if d <= 8 => {counts[d as usize] = true;}
+                    _ => return Err(format!("invalid rule `{}`: `{}` is not a neighbour count", text, c)),
+                }
+            }
+            *target = Some(counts);
+        }
+
+        match (birth, survival) {
+            (Some(birth), Some(survival)) => Ok(Rule { birth, survival }),
+            _ => Err(format!("invalid rule `{}`: expected B.../S...", text)),
+        }
+    }
+
+    /// Whether a cell is alive in the next generation.
+    pub fn next(&self, alive: bool, live_neighbours: usize) -> bool {
+        if alive {self.survival[live_neighbours]} else {self.birth[live_neighbours]}
+    }
+}
+
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B")?;
+        for (n, _) in self.birth.iter().enumerate().filter(|(_, b)| **b) {
+            write!(f, "{}", n)?;
+        }
+        write!(f, "/S")?;
+        for (n, _) in self.survival.iter().enumerate().filter(|(_, s)| **s) {
+            write!(f, "{}", n)?;
+        }
+        Ok(())
+    }
+}
+
+
+/// A rectangle of the board (inclusive bounds) governed by its own rule.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub top: usize,
+    pub left: usize,
+    pub bottom: usize,
+    pub right: usize,
+    pub rule: Rule,
+}
+
+
+impl Region {
+    /// Creates the region spanned by two opposite corners.
+    pub fn new(a: (usize, usize), b: (usize, usize), rule: Rule) -> Region {
+        Region {
+            top: a.0.min(b.0),
+            left: a.1.min(b.1),
+            bottom: a.0.max(b.0),
+            right: a.1.max(b.1),
+            rule,
+        }
+    }
+
+    /// Parses `top,left,bottom,right:rule`, e.g. `0,0,9,19:B36/S23`.
+    pub fn parse(text: &str) -> Result<Region, String> {
+        let err = || format!("invalid region `{}`: expected top,left,bottom,right:rule", text);
+        let (bounds, rule) = text.split_once(':').ok_or_else(err)?;
+        let bounds: Vec<usize> = bounds.split(',')
+            .map(|n| n.trim().parse().map_err(|_| err()))
+            .collect::<Result<_, _>>()?;
+        if bounds.len() != 4 {return Err(err());}
+        Ok(Region::new((bounds[0], bounds[1]), (bounds[2], bounds[3]), Rule::parse(rule)?))
+    }
+
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        (self.top..=self.bottom).contains(&row) && (self.left..=self.right).contains(&col)
+    }
+
+    pub fn overlaps(&self, other: &Region) -> bool {
+        self.top <= other.bottom && other.top <= self.bottom &&
+        self.left <= other.right && other.left <= self.right
+    }
+
+    pub fn on_edge(&self, row: usize, col: usize) -> bool {
+        self.contains(row, col) &&
+        (row == self.top || row == self.bottom || col == self.left || col == self.right)
+    }
+}
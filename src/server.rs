@@ -0,0 +1,148 @@
+use std::io::{self, BufWriter, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+use termion::event::{self, Event, Key};
+
+use crate::session::Session;
+
+
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+const LINEMODE: u8 = 34;
+const ESC: u8 = 0x1b;
+
+
+/// Where the telnet input filter is within the byte stream.
+enum Telnet {
+    Data,
+    /// Right after a carriage return, which telnet follows with NUL or LF.
+    Return,
+    Command,
+    /// The option byte of WILL, WONT, DO or DONT.
+    Option,
+    Subnegotiation,
+    SubnegotiationCommand,
+}
+
+
+/// Serves every telnet client its own interactive session, BBS style.
+/// `new_session` is called once per connection.
+pub fn run(address: &str, new_session: impl Fn() -> Session) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    for stream in listener.incoming().flatten() {
+        let session = new_session();
+        thread::spawn(move || serve(stream, session));
+    }
+    Ok(())
+}
+
+
+fn serve(stream: TcpStream, mut session: Session) {
+    let (reader, writer) = match (stream.try_clone(), stream.try_clone()) {
+        (Ok(r), Ok(w)) => (r, w),
+        _ => return,
+    };
+    let _ = stream.set_nodelay(true);
+    // Ask the client for character at a time input without local echo.
+    let negotiation = [IAC, WILL, ECHO, IAC, WILL, SUPPRESS_GO_AHEAD, IAC, DONT, LINEMODE];
+    let mut out = Output(BufWriter::new(writer));
+    if out.0.write_all(&negotiation).is_err() {return;}
+
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || read_keys(reader, sender));
+    session.run(&mut Events(receiver), &mut out);
+    let _ = out.0.flush();
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+
+fn read_keys(mut stream: TcpStream, sender: Sender<Event>) {
+    let mut state = Telnet::Data;
+    let mut buffer = [0u8; 256];
+    loop {
+        let n = match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => return,
+            Ok(n) => n,
+        };
+        let mut bytes = filter(&mut state, &buffer[..n]).into_iter().map(Ok);
+        while let Some(Ok(byte)) = bytes.next() {
+            // A lone escape is the Esc key rather than the start of a sequence.
+            let parsed = if byte == ESC && bytes.len() == 0 {
+                Ok(Event::Key(Key::Esc))
+            } else {
+                event::parse_event(byte, &mut bytes)
+            };
+            if let Ok(key @ Event::Key(_)) = parsed {
+                if sender.send(key).is_err() {return;}
+            }
+        }
+    }
+}
+
+
+/// Strips telnet commands from `input`, keeping the user's key presses.
+fn filter(state: &mut Telnet, input: &[u8]) -> Vec<u8> {
+    let mut keys = Vec::new();
+    for &byte in input {
+        *state = match (&*state, byte) {
+            (Telnet::Data, IAC) | (Telnet::Return, IAC) => Telnet::Command,
+            (Telnet::Data, b'\r') => {keys.push(byte); Telnet::Return}
+            (Telnet::Data, _) => {keys.push(byte); Telnet::Data}
+            (Telnet::Return, 0) | (Telnet::Return, b'\n') => Telnet::Data,
+            (Telnet::Return, _) => {keys.push(byte); Telnet::Data}
+            (Telnet::Command, IAC) => {keys.push(byte); Telnet::Data}
+            (Telnet::Command, SB) => Telnet::Subnegotiation,
+            (Telnet::Command, 251..=254) => Telnet::Option,
+            (Telnet::Command, _) | (Telnet::Option, _) => Telnet::Data,
+            (Telnet::Subnegotiation, IAC) => Telnet::SubnegotiationCommand,
+            (Telnet::Subnegotiation, _) => Telnet::Subnegotiation,
+            (Telnet::SubnegotiationCommand, SE) => Telnet::Data,
+            (Telnet::SubnegotiationCommand, _) => Telnet::Subnegotiation,
+        };
+    }
+    keys
+}
+
+
+/// Key presses from the connection; fails once the client is gone.
+struct Events(Receiver<Event>);
+
+
+impl Iterator for Events {
+    type Item = io::Result<Event>;
+
+    fn next(&mut self) -> Option<io::Result<Event>> {
+        match self.0.try_recv() {
+            Ok(key) => Some(Ok(key)),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                Some(Err(io::Error::new(io::ErrorKind::UnexpectedEof, "client disconnected")))
+            }
+        }
+    }
+}
+
+
+/// The renderer unwraps its writes, so errors are dropped here; a closed
+/// connection ends the session through `Events` instead.
+struct Output(BufWriter<TcpStream>);
+
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let _ = self.0.write_all(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let _ = self.0.flush();
+        Ok(())
+    }
+}
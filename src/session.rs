@@ -0,0 +1,2332 @@
+use std::cmp::max;
+use std::fs;
+use std::io::{self, Write};
+use std::mem;
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, Instant, SystemTime};
+
+use termion::event::{Event, Key, MouseButton, MouseEvent};
+
+use crate::background::Job;
+use crate::bookmarks::{Bookmark, BookmarksAction, BookmarksPanel};
+use crate::challenges::Challenges;
+use crate::checkpoint::{self, Checkpointer};
+use crate::clipboard;
+use crate::config;
+use crate::events::EventLog;
+use crate::export::Recording;
+use crate::frame::Frame;
+use crate::glyphs::Glyphs;
+use crate::layers::{Layers, Rule3d};
+use crate::lexicon::{BrowserAction, Lexicon, LexiconBrowser};
+use crate::midi::MidiOut;
+use crate::pattern::{Pattern, Transform};
+use crate::prompt::{Prompt, PromptAction};
+use crate::puzzle::{Level, PickerAction, PuzzlePicker, PuzzleSession};
+use crate::random::Rng;
+use crate::render::{Graphics, RenderMode};
+use crate::rule::{Region, Rule};
+use crate::settings::{Setting, SettingsAction, SettingsPanel};
+use crate::shapes::Shape;
+use crate::symmetry::Symmetry;
+use crate::state;
+use crate::theme::{self, Theme};
+use crate::timer::{format_duration, RunTimer};
+use crate::undo::{Edits, Snapshot};
+use crate::{place_centered, read_pattern, share, write_in, ImageOptions, Topology, Universe};
+
+
+pub const TICK_MILLIS_DEFAULT: u64 = 200;
+const SCREENSAVER_DENSITY: f64 = 0.35;
+pub const DENSITY_DEFAULT: f64 = 0.35;
+const WATCH_INTERVAL: Duration = Duration::from_millis(100);
+const PREVIEW_ROWS: usize = 12;
+const PREVIEW_COLS: usize = 40;
+const TIMELINE_COLS: usize = 40;
+// Counts typed before a key go up to this.
+const COUNT_MAX: u64 = 1_000_000;
+const FRAME: Duration = Duration::from_millis(33);
+const FADE_MILLIS_MAX: u64 = 400;
+const PROGRESS_REDRAW: Duration = Duration::from_millis(200);
+const RESIZE_INTERVAL: Duration = Duration::from_millis(100);
+// How long the terminal has to answer for the system clipboard before the
+// pattern is asked for instead.
+const CLIPBOARD_WAIT: Duration = Duration::from_millis(1000);
+const STATE_PATH_DEFAULT: &str = "game-of-life.json";
+// Longer jumps would take the plane past the coordinates HashLife can hold.
+pub const JUMP_MAX: u64 = 1 << 48;
+pub const QUICKSAVE_PATH_DEFAULT: &str = "game-of-life.quicksave";
+/// What sessions served over the network are told when they ask for a file.
+const NO_FILES: &str = "Files are not available over the network";
+
+
+/// What is shown below the board and receives key presses.
+enum Screen {
+    Board,
+    Lexicon(LexiconBrowser),
+    Challenges,
+    Notes,
+    /// A file's pattern that does not fit on the board, waiting for the
+    /// user to choose what to do with it.
+    Oversized(Pattern),
+    Puzzles(PuzzlePicker),
+    Settings(SettingsPanel),
+    Prompt(Prompt, PromptKind),
+    /// A yes/no question before an action that cannot be taken back.
+    Confirm(String, Confirmed),
+    /// Stepping back and forth through the generations in the history.
+    Timeline,
+    Bookmarks(BookmarksPanel),
+    /// Extending the selection from the cursor, to copy or cut it.
+    Select,
+    /// Moving the selected cells around before dropping them.
+    Move(Grab),
+    /// Drawing a shape from an anchor to the cursor.
+    Draw(Drawing),
+    /// Moving the ghost of a pattern from the lexicon or the clipboard
+    /// around before stamping it.
+    Stamp,
+    /// Waiting for the terminal to answer with the system clipboard.
+    Clipboard(ClipboardAnswer),
+}
+
+
+/// The terminal's answer with the system clipboard, as it comes in as
+/// keys.
+struct ClipboardAnswer {
+    asked: Instant,
+    /// What came after `ESC ]` so far, once it did.
+    text: Option<String>,
+}
+
+
+/// Selected cells lifted off the board to move them.
+struct Grab {
+    /// The board before they were lifted, to put them back.
+    original: Snapshot,
+    /// The board without them, to drop them onto.
+    base: Snapshot,
+    cells: Pattern,
+    /// Where their top left corner is.
+    at: (usize, usize),
+}
+
+
+/// A shape being drawn, shown on the board as it would be drawn.
+struct Drawing {
+    /// The board without the shape.
+    base: Snapshot,
+    anchor: (usize, usize),
+    shape: Shape,
+}
+
+
+impl Drawing {
+    /// Draws the shape afresh up to the cursor.
+    fn redraw(&self, game: &mut Universe) {
+        let to = game.selected_cell;
+        game.restore(self.base.clone());
+        game.draw(self.shape, self.anchor, to);
+    }
+}
+
+
+/// An action waiting for the user to confirm it.
+enum Confirmed {
+    Clear,
+    Randomize,
+    ClearWalls,
+    ExportHtml(String),
+    ExportEvents(String),
+    ExportRle(String),
+    SaveState(String),
+}
+
+
+/// What the text typed into a `Screen::Prompt` is used for.
+enum PromptKind {
+    RegionRule((usize, usize), (usize, usize)),
+    ExportHtml,
+    LoadCode,
+    ImportImage,
+    Note((usize, usize)),
+    ExportEvents,
+    ExportRle,
+    SaveState,
+    LoadState,
+    Rule,
+    Timer,
+    Jump,
+    Command,
+    Bookmark,
+    Reference,
+    SaveView,
+    RestoreView,
+    /// The density of a random soup in the selection.
+    Soup,
+    /// A pattern to paste, line by line, with the lines so far.
+    Pattern(Vec<String>),
+}
+
+
+/// A generation ahead being run to in batches, between frames.
+struct Goto {
+    from: u64,
+    target: u64,
+}
+
+
+/// A file looked at for changes: a checkpoint written by another
+/// instance, shown read-only, or the configuration or themes.
+struct Watch {
+    path: String,
+    modified: Option<SystemTime>,
+    checked: Instant,
+}
+
+
+impl Watch {
+    /// Looks for changes to `path` made from now on.
+    fn from_now(path: &str) -> Watch {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        Watch { path: path.to_string(), modified, checked: Instant::now() }
+    }
+
+    /// Whether the file changed since the last look, looking at most every
+    /// `WATCH_INTERVAL` once it has been seen.
+    fn changed(&mut self) -> bool {
+        if self.modified.is_some() && self.checked.elapsed() < WATCH_INTERVAL {return false;}
+        self.checked = Instant::now();
+        let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if modified.is_none() || modified == self.modified {return false;}
+        self.modified = modified;
+        true
+    }
+}
+
+
+/// A setting the configuration file changed, read before any is applied.
+enum Configured {
+    TickMillis(u64),
+    Rule(Rule),
+    Topology(Topology),
+    Theme(Theme),
+    Glyphs(Glyphs),
+    HistoryLen(usize),
+    HistoryMb(usize),
+}
+
+
+/// Where and with which keys the board is quicksaved and quickloaded.
+pub struct QuickSave {
+    path: String,
+    save_key: Key,
+    load_key: Key,
+}
+
+
+impl QuickSave {
+    /// `keys` names the save and load keys as `save,load`, each either
+    /// `F1` to `F12` or a single character. Defaults to F5 and F9.
+    pub fn new(path: &str, keys: Option<&str>) -> Result<QuickSave, String> {
+        let (save_key, load_key) = match keys {
+            Some(keys) => {
+                let (save, load) = keys.split_once(',')
+                    .ok_or(format!("invalid keys `{}`: expected <save>,<load>, e.g. F5,F9", keys))?;
+                (parse_key(save)?, parse_key(load)?)
+            }
+            None => (Key::F(5), Key::F(9)),
+        };
+        Ok(QuickSave { path: path.to_string(), save_key, load_key })
+    }
+}
+
+
+fn parse_key(name: &str) -> Result<Key, String> {
+    let name = name.trim();
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {return Ok(Key::Char(c));}
+    match name.strip_prefix(['F', 'f']).map(|n| n.parse()) {
+        Some(Ok(n @ 1..=12)) => Ok(Key::F(n)),
+        _ => Err(format!("invalid key `{}`: expected F1 to F12 or a single character", name)),
+    }
+}
+
+
+/// Whether `lines` make a whole RLE pattern: a header, then a line that
+/// ends it with `!`.
+fn rle_ended(lines: &[String]) -> bool {
+    lines.iter().any(|line| line.starts_with('x'))
+        && lines.last().is_some_and(|line| !line.starts_with('#') && line.ends_with('!'))
+}
+
+
+/// Shift+arrows, which termion does not know, as the Alt+W/A/S/D keys
+/// that pan the view like them.
+fn shift_arrow(bytes: &[u8]) -> Option<Key> {
+    match bytes {
+        b"\x1b[1;2A" => Some(Key::Alt('w')),
+        b"\x1b[1;2B" => Some(Key::Alt('s')),
+        b"\x1b[1;2C" => Some(Key::Alt('d')),
+        b"\x1b[1;2D" => Some(Key::Alt('a')),
+        _ => None,
+    }
+}
+
+
+/// Everything the screens below the board need to draw themselves.
+pub struct Panels {
+    pub lexicon: Lexicon,
+    pub challenges: Option<Challenges>,
+    pub levels: Vec<Level>,
+    pub level_errors: Vec<String>,
+    pub puzzle: Option<PuzzleSession>,
+    pub recording: Option<Recording>,
+    #[cfg(feature = "audio")]
+    pub sonifier: Option<crate::audio::Sonifier>,
+    pub midi: Option<MidiOut>,
+}
+
+
+/// One interactive game: the board, the screens around it and the key
+/// handling, drawn to any terminal-like writer.
+pub struct Session {
+    game: Universe,
+    screen: Screen,
+    panels: Panels,
+    message: Option<String>,
+    region_corner: Option<(usize, usize)>,
+    /// Whether the view follows the live cells as they move.
+    following: bool,
+    tick_millis: u64,
+    image_options: ImageOptions,
+    screensaver_after: Option<Duration>,
+    last_input: Instant,
+    /// What cells dragged over with the mouse are set to, while the left
+    /// button is held.
+    drawing: Option<bool>,
+    rng: Rng,
+    /// The seed the next random soup is made from, if it was chosen with
+    /// --seed rather than drawn from `rng`.
+    next_seed: Option<u64>,
+    /// The seed of the last random soup, shown so it can be made again.
+    soup_seed: Option<u64>,
+    // The user's board while the screensaver runs in its place.
+    saved_game: Option<Universe>,
+    checkpointer: Option<Checkpointer>,
+    watch: Option<Watch>,
+    /// The configuration file, applied again when it changes, and the keys
+    /// the command line set instead.
+    config_watch: Option<(Watch, Vec<&'static str>)>,
+    themes_watch: Option<Watch>,
+    log: EventLog,
+    /// The boards before the edits made, to undo them.
+    edits: Edits,
+    bookmarks: Vec<Bookmark>,
+    timer: RunTimer,
+    quicksave: QuickSave,
+    /// Files being written in the background.
+    jobs: Vec<Job>,
+    /// The other layers in 3D mode.
+    layers: Option<Layers>,
+    /// The generation `:goto` is running to.
+    goto: Option<Goto>,
+    /// A count typed before a key, to do what it does that many times.
+    count: Option<u64>,
+    /// Whether births and deaths fade in and out between generations.
+    fade: bool,
+    /// The share of cells a random soup makes alive.
+    density: f64,
+    last_tick: Instant,
+    last_frame: Instant,
+    /// The terminal size last fitted to, when the view follows it, and
+    /// when it was last looked up.
+    terminal_size: Option<(u16, u16)>,
+    resize_checked: Instant,
+    /// What is on screen, to only redraw the lines that changed.
+    frame: Frame,
+    /// The terminal window title last set, to only send changes.
+    title: String,
+    /// An escape sequence for the system clipboard, sent with the next
+    /// frame.
+    to_terminal: Option<String>,
+    /// What the terminal can show besides text.
+    graphics: Graphics,
+    /// The themes to switch between.
+    themes: Vec<Theme>,
+    /// Whether the session is someone else's, over the network, and so
+    /// gets no files on this machine.
+    sandboxed: bool,
+}
+
+
+impl Session {
+    pub fn new(game: Universe,
+               panels: Panels,
+               image_options: ImageOptions,
+               screensaver_after: Option<Duration>) -> Session {
+        Session {
+            game,
+            screen: Screen::Board,
+            panels,
+            message: None,
+            region_corner: None,
+            following: false,
+            tick_millis: TICK_MILLIS_DEFAULT,
+            image_options,
+            screensaver_after,
+            last_input: Instant::now(),
+            drawing: None,
+            rng: Rng::from_time(),
+            next_seed: None,
+            soup_seed: None,
+            saved_game: None,
+            checkpointer: None,
+            watch: None,
+            config_watch: None,
+            themes_watch: None,
+            log: EventLog::new(),
+            edits: Edits::new(),
+            bookmarks: Vec::new(),
+            timer: RunTimer::new(None),
+            quicksave: QuickSave::new(QUICKSAVE_PATH_DEFAULT, None).unwrap(),
+            jobs: Vec::new(),
+            layers: None,
+            goto: None,
+            count: None,
+            fade: false,
+            density: DENSITY_DEFAULT,
+            last_tick: Instant::now(),
+            last_frame: Instant::now(),
+            terminal_size: None,
+            resize_checked: Instant::now(),
+            frame: Frame::new(),
+            title: String::new(),
+            to_terminal: None,
+            graphics: Graphics::default(),
+            themes: theme::builtin(),
+            sandboxed: false,
+        }
+    }
+
+    /// Lets the board be drawn with the graphics the terminal shows, and
+    /// falls back to text if it was meant to be drawn with others. Must be
+    /// called before the session runs, with no graphics for a terminal
+    /// that was not asked.
+    pub fn set_graphics(&mut self, graphics: Graphics) {
+        self.graphics = graphics;
+        if !graphics.supports(self.game.render) {
+            self.message = Some(format!("This terminal cannot show {} graphics, drawing text instead", self.game.render.name()));
+            self.game.set_render(if graphics.supports(RenderMode::Auto) {RenderMode::Auto} else {RenderMode::Cells});
+        }
+    }
+
+    /// Lets the themes be switched between, built-in ones included.
+    pub fn set_themes(&mut self, themes: Vec<Theme>) {
+        self.themes = themes;
+    }
+
+    pub fn set_density(&mut self, density: f64) {
+        self.density = density;
+    }
+
+    /// Makes the random soups repeat from run to run; the first one is
+    /// made from `seed` itself.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+        self.next_seed = Some(seed);
+    }
+
+    /// Shows the seed of the soup the board started as, the soups made
+    /// after it following from that seed.
+    pub fn set_soup_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+        self.soup_seed = Some(seed);
+    }
+
+    /// Fades newborn cells in and dying cells out over the generation.
+    pub fn set_fade(&mut self, fade: bool) {
+        self.fade = fade;
+    }
+
+    /// Turns on the experimental 3D mode with `depth` layers, the board
+    /// being the bottom one.
+    pub fn set_layers(&mut self, depth: usize, rule: Rule3d) {
+        self.layers = Some(Layers::new(&self.game, depth, rule));
+    }
+
+    pub fn set_quicksave(&mut self, quicksave: QuickSave) {
+        self.quicksave = quicksave;
+    }
+
+    pub fn set_tick_millis(&mut self, millis: u64) {
+        self.tick_millis = millis;
+    }
+
+    /// Pauses the game after it ran for `duration`.
+    pub fn stop_after(&mut self, duration: Option<Duration>) {
+        self.timer.stop_after(duration);
+    }
+
+    /// Refuses everything that reads or writes files, for sessions served
+    /// over the network.
+    pub fn sandbox(&mut self) {
+        self.sandboxed = true;
+    }
+
+    /// Saves the board to a checkpoint file after edits and as it runs.
+    pub fn autosave(&mut self, checkpointer: Checkpointer) {
+        self.checkpointer = Some(checkpointer);
+    }
+
+    /// Refits the view whenever the local terminal changes size. Not for
+    /// telnet sessions, whose terminal is on the other end.
+    pub fn follow_terminal_size(&mut self) {
+        self.terminal_size = termion::terminal_size().ok();
+    }
+
+    /// Fits the view to the terminal again if it was resized since the
+    /// last look. Returns whether it was.
+    fn check_resize(&mut self) -> bool {
+        if self.terminal_size.is_none() || self.resize_checked.elapsed() < RESIZE_INTERVAL {return false;}
+        self.resize_checked = Instant::now();
+        let size = match termion::terminal_size() {
+            Ok(size @ (1.., 1..)) => size,
+            _ => return false,
+        };
+        if self.terminal_size == Some(size) {return false;}
+        self.terminal_size = Some(size);
+        let (cols, rows) = size;
+        self.game.fit_view(cols, rows);
+        if let Some(saved) = &mut self.saved_game {saved.fit_view(cols, rows);}
+        true
+    }
+
+    /// Shows the checkpoint file at `path` read-only, reloading it
+    /// whenever it changes.
+    pub fn watch(&mut self, path: &str) {
+        self.watch = Some(Watch { path: path.to_string(), modified: None, checked: Instant::now() });
+        self.reload_watched();
+    }
+
+    /// Applies the configuration file at `path` again whenever it changes,
+    /// all but the `overridden` keys.
+    pub fn watch_config(&mut self, path: &str, overridden: Vec<&'static str>) {
+        self.config_watch = Some((Watch::from_now(path), overridden));
+    }
+
+    /// Loads the themes file at `path` again whenever it changes.
+    pub fn watch_themes(&mut self, path: &str) {
+        self.themes_watch = Some(Watch::from_now(path));
+    }
+
+    /// Runs until `q` is pressed or `events` fails. `events` must not
+    /// block: `None` means no key press or mouse event is waiting.
+    pub fn run(&mut self, events: &mut dyn Iterator<Item = io::Result<Event>>, out: &mut dyn Write) {
+        // Keeps the terminal's own title on its title stack for the end.
+        write!(out, "\x1b[22;0t").unwrap();
+        self.redraw(out);
+
+        loop {
+            sleep(Duration::from_millis(1));
+            if self.reload_watched() {self.redraw(out);}
+            if self.reload_settings() {self.redraw(out);}
+            if self.check_resize() {self.redraw(out);}
+            let event = match events.next() {
+                Some(Ok(event)) => Some(event),
+                Some(Err(_)) => break,
+                None => None,
+            };
+
+            if event.is_some() {
+                self.last_input = Instant::now();
+                if let Some(saved) = self.saved_game.take() {
+                    self.game = saved;
+                    self.redraw(out);
+                    continue;
+                }
+            }
+
+            if let Some(after) = self.screensaver_after {
+                if self.saved_game.is_none() && self.last_input.elapsed() >= after {
+                    let mut soup = Universe::new(self.game.width, self.game.height);
+                    soup.randomize(SCREENSAVER_DENSITY, &mut self.rng);
+                    soup.photo_mode = true;
+                    self.saved_game = Some(mem::replace(&mut self.game, soup));
+                }
+            }
+
+            if self.saved_game.is_some() {
+                self.game.tick();
+                if self.game.last_changes() == (0, 0) {
+                    self.game.randomize(SCREENSAVER_DENSITY, &mut self.rng);
+                }
+                self.redraw(out);
+                sleep(Duration::from_millis(self.tick_millis));
+                continue;
+            }
+
+            match event {
+                Some(Event::Key(key)) => {
+                    if !self.handle_key(key) {break;}
+                    self.redraw(out);
+                }
+                Some(Event::Mouse(mouse)) if self.handle_mouse(mouse) => self.redraw(out),
+                Some(Event::Unsupported(bytes)) => {
+                    if let Some(key) = shift_arrow(&bytes) {
+                        if !self.handle_key(key) {break;}
+                        self.redraw(out);
+                    }
+                }
+                _ => {}
+            }
+
+            if self.timer.update(self.game.is_running) {
+                self.game.is_running = false;
+                let elapsed = format_duration(self.timer.elapsed());
+                self.log.record(self.game.generation, "trigger", format!("timer stopped the run after {}", elapsed));
+                self.message = Some(format!("Timer: paused after {} of running", elapsed));
+                self.redraw(out);
+            }
+
+            if self.clipboard_unanswered() {self.redraw(out);}
+
+            if self.goto.is_some() {
+                self.run_goto();
+                self.redraw(out);
+            } else if self.game.is_running && self.last_tick.elapsed() >= Duration::from_millis(self.tick_millis) {
+                self.step();
+                self.redraw(out);
+            } else if self.game.fade.is_some() && self.last_frame.elapsed() >= FRAME {
+                // Frames between generations only animate the fade.
+                self.redraw(out);
+            } else if !self.jobs.is_empty() && self.last_frame.elapsed() >= PROGRESS_REDRAW {
+                self.redraw(out);
+            }
+
+            if self.jobs.iter().any(Job::is_finished) {
+                let (finished, running) = mem::take(&mut self.jobs).into_iter().partition(Job::is_finished);
+                self.jobs = running;
+                for job in finished {self.message = Some(job.finish());}
+                self.redraw(out);
+            }
+        }
+
+        // Files still being written would be cut short on exit.
+        for job in mem::take(&mut self.jobs) {job.finish();}
+        self.graphics.clear(out);
+        write!(out, "{}\x1b[23;0t", termion::cursor::Show).unwrap();
+        out.flush().unwrap();
+    }
+
+    /// A left click toggles the cell under the pointer and dragging sets
+    /// the cells passed over to the state the first one got, going through
+    /// the Space key so puzzles and read-only boards have their say.
+    /// Returns whether the screen needs a redraw.
+    fn handle_mouse(&mut self, event: MouseEvent) -> bool {
+        let (x, y) = match event {
+            MouseEvent::Press(MouseButton::Left, x, y) => {
+                self.drawing = None;
+                (x, y)
+            }
+            MouseEvent::Hold(x, y) if self.drawing.is_some() => (x, y),
+            MouseEvent::Release(..) => {
+                self.drawing = None;
+                return false;
+            }
+            _ => return false,
+        };
+        if !matches!(self.screen, Screen::Board) {return false;}
+        let Some((right, (row, col))) = self.game.cell_at(x, y) else {return false};
+        if right != self.game.focus_right {self.game.switch_pane();}
+        self.game.selected_cell = (row, col);
+        let alive = *self.drawing.get_or_insert(!self.game.get_cell(row, col));
+        let edits = self.edits.count();
+        if self.game.get_cell(row, col) != alive {self.handle_key(Key::Char(' '));}
+        // A drag is undone in one go, with the press that started it.
+        if matches!(event, MouseEvent::Hold(..)) {self.edits.truncate(edits);}
+        true
+    }
+
+    /// Returns `false` when the session should end.
+    fn handle_key(&mut self, key: Key) -> bool {
+        let mut edited = false;
+        // The board to go back to if the key edits it; moving around is
+        // left out as it never does.
+        let moving = matches!(key, Key::Up | Key::Down | Key::Left | Key::Right | Key::Alt(_)
+                                   | Key::Char('h' | 'j' | 'k' | 'l' | '0'..='9'));
+        let mut before = (!moving).then(|| self.game.snapshot());
+        self.message = None;
+        self.log.record(self.game.generation, "key", format!("{:?}", key));
+
+        if let Some(watch) = &self.watch {
+            let look = matches!(key, Key::Up | Key::Down | Key::Left | Key::Right
+                                     | Key::Char('h' | 'j' | 'k' | 'l' | '0'..='9')
+                                     | Key::Char('f' | 'F' | 't' | 'o' | 'm' | 'M' | '?' | '|' | '\t' | 'q')
+                                     | Key::Char('G') | Key::Alt('w' | 'a' | 's' | 'd' | '-' | '+' | '='));
+            if !look {
+                self.message = Some(format!("Read-only, following {}", watch.path));
+                return true;
+            }
+        }
+
+        if let Some(goto) = &self.goto {
+            if key == Key::Esc {
+                self.message = Some(format!("Stopped at generation {} on the way to {}", self.game.generation, goto.target));
+                self.goto = None;
+                self.after_tick();
+            } else {
+                self.message = Some(format!("Going to generation {}, Esc stops", goto.target));
+            }
+            return true;
+        }
+
+        if matches!(self.screen, Screen::Board) {
+            if self.sandboxed && (key == self.quicksave.save_key || key == self.quicksave.load_key) {
+                self.message = Some(NO_FILES.to_string());
+                return true;
+            }
+            if key == self.quicksave.save_key {
+                let path = &self.quicksave.path;
+                self.message = Some(match checkpoint::save(path, &self.game, &self.rng) {
+                    Ok(()) => format!("Quicksaved to {}", path),
+                    Err(e) => format!("Cannot quicksave to {}: {}", path, e),
+                });
+                return true;
+            }
+            if key == self.quicksave.load_key {
+                self.quickload();
+                return true;
+            }
+            if let Key::Char(digit @ '0'..='9') = key {
+                // A lone 0 is not a count.
+                if digit != '0' || self.count.is_some() {
+                    let count = self.count.unwrap_or(0) * 10 + digit.to_digit(10).unwrap() as u64;
+                    self.count = Some(count.min(COUNT_MAX));
+                    return true;
+                }
+            }
+        }
+        let counted = self.count.take();
+        let count = counted.unwrap_or(1);
+        let by = count as isize;
+
+        match &mut self.screen {
+            Screen::Lexicon(br) => match br.handle_key(key, &self.panels.lexicon) {
+                BrowserAction::Close => {self.screen = Screen::Board;}
+                BrowserAction::Stamp(_) if self.panels.puzzle.is_some() => {
+                    self.message = Some("Stamping is disabled in puzzles".to_string());
+                    self.screen = Screen::Board;
+                }
+                BrowserAction::Stamp(p) => {
+                    self.game.set_ghost(Some(p));
+                    self.game.show_cursor = true;
+                    self.screen = Screen::Stamp;
+                }
+                BrowserAction::None => {}
+            },
+            Screen::Challenges | Screen::Notes => {self.screen = Screen::Board;}
+            Screen::Oversized(pattern) => {
+                match key {
+                    Key::Char('g') => {
+                        let width = self.game.width.max(pattern.width);
+                        let height = self.game.height.max(pattern.height);
+                        self.game.resize(width, height);
+                        place_centered(&mut self.game, pattern);
+                    }
+                    Key::Char('c') => {
+                        if let Some(rule) = pattern.rule {self.game.rule = rule;}
+                        self.game.clear();
+                        self.game.stamp(pattern, 0, 0);
+                    }
+                    Key::Char('e') => place_centered(&mut self.game, pattern),
+                    Key::Esc => {}
+                    _ => return true,
+                }
+                if key != Key::Esc {
+                    self.log.record(self.game.generation, "load", format!("{}x{} pattern", pattern.width, pattern.height));
+                    edited = true;
+                }
+                self.screen = Screen::Board;
+            }
+            Screen::Prompt(prompt, _) => match prompt.handle_key(key) {
+                PromptAction::Cancel => {
+                    // Back to the selection the soup was for.
+                    let selecting = matches!(self.screen, Screen::Prompt(_, PromptKind::Soup));
+                    self.screen = if selecting {Screen::Select} else {Screen::Board};
+                }
+                PromptAction::Submit(text) => {
+                    let Screen::Prompt(_, kind) = mem::replace(&mut self.screen, Screen::Board) else {unreachable!()};
+                    match kind {
+                        PromptKind::RegionRule(a, b) => {
+                            let area = Region::new(a, b, self.game.rule);
+                            if text.is_empty() {
+                                self.game.remove_regions(&area);
+                            } else {
+                                match Rule::parse(&text) {
+                                    Ok(rule) => self.game.add_region(Region { rule, ..area }),
+                                    Err(e) => {self.message = Some(e);}
+                                }
+                            }
+                        }
+                        PromptKind::ExportHtml => {
+                            let path = if text.is_empty() {"life.html"} else {&text};
+                            self.confirm_overwrite(path, Confirmed::ExportHtml(path.to_string()));
+                        }
+                        PromptKind::ImportImage => {edited = self.import(&text);}
+                        PromptKind::Note((row, col)) => {
+                            self.game.set_note(row, col, &text);
+                        }
+                        PromptKind::ExportEvents => {
+                            let path = if text.is_empty() {"events.jsonl"} else {&text};
+                            self.confirm_overwrite(path, Confirmed::ExportEvents(path.to_string()));
+                        }
+                        PromptKind::ExportRle => {
+                            let path = if text.is_empty() {"board.rle"} else {&text};
+                            self.confirm_overwrite(path, Confirmed::ExportRle(path.to_string()));
+                        }
+                        PromptKind::SaveState => {
+                            let path = if text.is_empty() {STATE_PATH_DEFAULT} else {&text};
+                            self.confirm_overwrite(path, Confirmed::SaveState(path.to_string()));
+                        }
+                        PromptKind::LoadState => {
+                            self.load_state(if text.is_empty() {STATE_PATH_DEFAULT} else {&text});
+                        }
+                        PromptKind::Rule => {self.set_rule(&text);}
+                        PromptKind::Reference if text.is_empty() => {
+                            self.game.set_reference(None);
+                        }
+                        PromptKind::Reference => {
+                            let (width, height) = (self.game.width, self.game.height);
+                            match read_pattern(&text, width, height, &self.image_options) {
+                                Ok((pattern, warnings)) => {
+                                    if let Some(first) = warnings.first() {
+                                        self.message = Some(format!("{} warnings, first: {}", warnings.len(), first));
+                                    }
+                                    self.game.set_reference(Some(&pattern));
+                                }
+                                Err(e) => {self.message = Some(e);}
+                            }
+                        }
+                        PromptKind::Timer if text.is_empty() => {
+                            self.timer.stop_after(None);
+                        }
+                        PromptKind::Jump => match text.parse::<u64>() {
+                            Ok(generations @ 1..=JUMP_MAX) => match self.game.jump(generations) {
+                                Ok(()) => {
+                                    self.log.record(self.game.generation, "edit", format!("jumped {} generations", generations));
+                                    self.after_tick();
+                                }
+                                Err(e) => {self.message = Some(e);}
+                            },
+                            _ => {self.message = Some(format!("Jumps are 1 to {} generations", JUMP_MAX));}
+                        },
+                        PromptKind::Command if matches!(text.as_str(), "q" | "quit") => return false,
+                        PromptKind::Command => {edited = self.command(&text);}
+                        PromptKind::Bookmark => {
+                            let name = if text.is_empty() {format!("generation {}", self.game.generation)} else {text};
+                            let bookmark = Bookmark {
+                                name: name.clone(),
+                                generation: self.game.generation,
+                                rule: self.game.rule,
+                                snapshot: self.game.snapshot(),
+                            };
+                            // A name bookmarks one board, the latest.
+                            self.bookmarks.retain(|b| b.name != name);
+                            self.bookmarks.push(bookmark);
+                            self.message = Some(format!("Bookmarked `{}`", name));
+                            self.screen = Screen::Bookmarks(BookmarksPanel::new());
+                        }
+                        PromptKind::Timer => match text.parse() {
+                            Ok(secs) => self.timer.stop_after(Some(Duration::from_secs(secs))),
+                            Err(_) => {self.message = Some(format!("`{}` is not a number of seconds", text));}
+                        },
+                        PromptKind::SaveView if !text.is_empty() => {
+                            self.game.save_view(&text);
+                            self.message = Some(format!("Saved view `{}`", text));
+                        }
+                        PromptKind::SaveView => {}
+                        PromptKind::Pattern(mut lines) => {
+                            let ended = text.is_empty();
+                            if !ended {lines.push(text);}
+                            if !ended && !rle_ended(&lines) {
+                                self.ask_for_pattern(lines);
+                            } else if !lines.is_empty() {
+                                self.paste_text(&lines.join("\n"));
+                            }
+                        }
+                        PromptKind::Soup => {
+                            let density = if text.is_empty() {Ok(self.density)} else {text.parse::<f64>()};
+                            match density {
+                                Ok(density) if (0.0..=1.0).contains(&density) => {
+                                    let seed = self.next_seed.take().unwrap_or_else(|| self.rng.next_u64());
+                                    if self.game.randomize_selection(density, &mut Rng::new(seed)).is_ok() {
+                                        self.log.record(self.game.generation, "edit",
+                                                        format!("random soup in the selection, density {}, seed {}", density, seed));
+                                        edited = true;
+                                    }
+                                    self.game.selection = None;
+                                }
+                                _ => {
+                                    self.message = Some(format!("`{}` is not a density from 0 to 1", text));
+                                    self.screen = Screen::Select;
+                                }
+                            }
+                        }
+                        PromptKind::RestoreView => {
+                            if let Err(e) = self.game.restore_view(&text) {self.message = Some(e);}
+                        }
+                        PromptKind::LoadCode => {
+                            match share::decode(&text) {
+                                Ok(universe) => {
+                                    self.replace_game(universe);
+                                    self.log.record(self.game.generation, "load", text.as_str());
+                                    edited = true;
+                                }
+                                Err(e) => {self.message = Some(e);}
+                            }
+                        }
+                    }
+                }
+                PromptAction::None => {}
+            },
+            Screen::Confirm(_, _) => {
+                let Screen::Confirm(_, action) = mem::replace(&mut self.screen, Screen::Board) else {unreachable!()};
+                if key == Key::Char('y') {
+                    edited = self.confirmed(action);
+                } else if matches!(action, Confirmed::ExportHtml(_)) {
+                    // Asks for another file name instead of dropping the recording.
+                    let prompt = Prompt::new("Save HTML recording to (life.html):");
+                    self.screen = Screen::Prompt(prompt, PromptKind::ExportHtml);
+                }
+            }
+            Screen::Timeline => match key {
+                Key::Left | Key::Right => {
+                    let (moved, end) = match key {
+                        Key::Left => (self.game.tick_back().is_ok(), "The earliest generation kept"),
+                        _ => (self.game.tick_forward().is_ok(), "The latest generation"),
+                    };
+                    if !moved {self.message = Some(end.to_string());}
+                }
+                Key::Home => while self.game.tick_back().is_ok() {},
+                Key::End => while self.game.tick_forward().is_ok() {},
+                Key::Char('\n') => {
+                    let later = self.game.later_len();
+                    self.game.forget_later();
+                    self.screen = Screen::Board;
+                    if later > 0 {
+                        self.log.record(self.game.generation, "edit", format!("went back to generation {}", self.game.generation));
+                        self.message = Some(format!("Carrying on from generation {}", self.game.generation));
+                        // Not an edit to undo, but the board is another.
+                        before = None;
+                        edited = true;
+                    }
+                }
+                Key::Esc => {
+                    while self.game.tick_forward().is_ok() {}
+                    self.screen = Screen::Board;
+                }
+                _ => {}
+            },
+            Screen::Select => match key {
+                Key::Up | Key::Char('k') => {self.game.move_cursor(-1, 0);}
+                Key::Down | Key::Char('j') => {self.game.move_cursor(1, 0);}
+                Key::Right | Key::Char('l') => {self.game.move_cursor(0, 1);}
+                Key::Left | Key::Char('h') => {self.game.move_cursor(0, -1);}
+                Key::Char('y' | 'x' | 'd') => {
+                    let cut = key != Key::Char('y');
+                    let copied = if cut {self.game.cut_selection()} else {self.game.copy_selection()};
+                    if let Some(p) = copied {
+                        self.message = Some(format!("{} {}x{}, {} live cells, Ctrl+P pastes", if cut {"Cut"} else {"Copied"},
+                                                    p.width, p.height, p.cells.len()));
+                    }
+                    self.game.selection = None;
+                    self.screen = Screen::Board;
+                    edited = cut;
+                }
+                Key::Char('c') => {
+                    if let Some(p) = self.game.selected_pattern() {self.copy_rle(&p);}
+                    self.game.selection = None;
+                    self.screen = Screen::Board;
+                }
+                Key::Char('m') => {
+                    // The board would move on under the lifted cells.
+                    self.game.is_running = false;
+                    let original = self.game.snapshot();
+                    let ((top, left), _) = self.game.selection_bounds().unwrap();
+                    let cells = self.game.clear_selection().unwrap();
+                    let base = self.game.snapshot();
+                    self.game.place(&cells, top, left);
+                    self.screen = Screen::Move(Grab { original, base, cells, at: (top, left) });
+                }
+                Key::Char('i') => {
+                    self.game.invert(self.game.selection_bounds());
+                    self.log.record(self.game.generation, "edit", "inverted the selection");
+                    edited = true;
+                }
+                Key::Char('s') => {
+                    let label = format!("Density of the soup in the selection (empty: {}):", self.density);
+                    self.screen = Screen::Prompt(Prompt::new(&label), PromptKind::Soup);
+                }
+                Key::Char('r' | 'R' | 'f' | 'F') => {
+                    let transform = match key {
+                        Key::Char('r') => Transform::RotateRight,
+                        Key::Char('R') => Transform::RotateLeft,
+                        Key::Char('f') => Transform::FlipHorizontal,
+                        _ => Transform::FlipVertical,
+                    };
+                    match self.game.transform_selection(transform) {
+                        Ok(()) => {edited = true;}
+                        Err(msg) => {self.message = Some(msg.to_string());}
+                    }
+                }
+                Key::Esc | Key::Ctrl('v') => {
+                    self.game.selection = None;
+                    self.screen = Screen::Board;
+                }
+                _ => {}
+            },
+            Screen::Move(grab) => match key {
+                Key::Up | Key::Down | Key::Left | Key::Right | Key::Char('h' | 'j' | 'k' | 'l') => {
+                    let (rows, cols) = match key {
+                        Key::Up | Key::Char('k') => (-by, 0),
+                        Key::Down | Key::Char('j') => (by, 0),
+                        Key::Left | Key::Char('h') => (0, -by),
+                        _ => (0, by),
+                    };
+                    // The cells stay whole on the board.
+                    let (height, width) = (self.game.height - grab.cells.height, self.game.width - grab.cells.width);
+                    let top = (grab.at.0 as isize + rows).clamp(0, height as isize) as usize;
+                    let left = (grab.at.1 as isize + cols).clamp(0, width as isize) as usize;
+                    grab.at = (top, left);
+                    self.game.restore(grab.base.clone());
+                    self.game.place(&grab.cells, top, left);
+                    self.game.select(&grab.cells, top, left);
+                }
+                Key::Char('\n') => {
+                    let Screen::Move(grab) = mem::replace(&mut self.screen, Screen::Board) else {unreachable!()};
+                    self.game.selection = None;
+                    // The move is undone in one go.
+                    before = Some(grab.original);
+                    edited = true;
+                }
+                Key::Esc => {
+                    let Screen::Move(grab) = mem::replace(&mut self.screen, Screen::Board) else {unreachable!()};
+                    self.game.restore(grab.original);
+                    self.game.selection = None;
+                }
+                _ => {}
+            },
+            Screen::Clipboard(answer) => match (key, &mut answer.text) {
+                (Key::Esc, _) => {self.screen = Screen::Board;}
+                (Key::Alt(']'), None) => {answer.text = Some(String::new());}
+                (Key::Char(c), Some(text)) => {text.push(c);}
+                // BEL or ST, `ESC \`, ends the answer.
+                (Key::Ctrl('g') | Key::Alt('\\'), Some(text)) => {
+                    match clipboard::parse_answer(text) {
+                        Some(text) => {
+                            self.screen = Screen::Board;
+                            self.paste_text(&text);
+                        }
+                        None => {
+                            self.message = Some("The clipboard holds no text, paste the pattern here instead".to_string());
+                            self.ask_for_pattern(Vec::new());
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Screen::Stamp => match key {
+                Key::Up | Key::Char('k') => {self.game.move_cursor(-by, 0);}
+                Key::Down | Key::Char('j') => {self.game.move_cursor(by, 0);}
+                Key::Right | Key::Char('l') => {self.game.move_cursor(0, by);}
+                Key::Left | Key::Char('h') => {self.game.move_cursor(0, -by);}
+                Key::Char('r' | 'R' | 'f' | 'F') => {
+                    let transform = match key {
+                        Key::Char('r') => Transform::RotateRight,
+                        Key::Char('R') => Transform::RotateLeft,
+                        Key::Char('f') => Transform::FlipHorizontal,
+                        _ => Transform::FlipVertical,
+                    };
+                    let aimed = self.game.ghost.as_ref().map(|p| p.transformed(transform));
+                    self.game.set_ghost(aimed);
+                }
+                Key::Char('\n') => {
+                    if let Some(p) = self.game.ghost.take() {
+                        self.game.stamp(&p, self.game.selected_cell.0, self.game.selected_cell.1);
+                        self.log.record(self.game.generation, "stamp", format!("{}x{} pattern", p.width, p.height));
+                        edited = true;
+                    }
+                    self.screen = Screen::Board;
+                }
+                Key::Esc => {
+                    self.game.set_ghost(None);
+                    self.screen = Screen::Board;
+                }
+                _ => {}
+            },
+            Screen::Draw(drawing) => match key {
+                Key::Up | Key::Down | Key::Left | Key::Right | Key::Char('h' | 'j' | 'k' | 'l') => {
+                    match key {
+                        Key::Up | Key::Char('k') => self.game.move_cursor(-by, 0),
+                        Key::Down | Key::Char('j') => self.game.move_cursor(by, 0),
+                        Key::Left | Key::Char('h') => self.game.move_cursor(0, -by),
+                        _ => self.game.move_cursor(0, by),
+                    }
+                    drawing.redraw(&mut self.game);
+                }
+                Key::Char('\t') | Key::BackTab => {
+                    drawing.shape = drawing.shape.cycle(if key == Key::BackTab {-1} else {1});
+                    drawing.redraw(&mut self.game);
+                }
+                Key::Char('\n') => {
+                    let Screen::Draw(drawing) = mem::replace(&mut self.screen, Screen::Board) else {unreachable!()};
+                    self.log.record(self.game.generation, "edit", format!("drew a {}", drawing.shape.name()));
+                    // The shape is undone in one go.
+                    before = Some(drawing.base);
+                    edited = true;
+                }
+                Key::Esc | Key::Ctrl('d') => {
+                    let Screen::Draw(drawing) = mem::replace(&mut self.screen, Screen::Board) else {unreachable!()};
+                    self.game.restore(drawing.base);
+                }
+                _ => {}
+            },
+            Screen::Bookmarks(panel) => match panel.handle_key(key, &self.bookmarks) {
+                BookmarksAction::Close => {self.screen = Screen::Board;}
+                BookmarksAction::Add => {
+                    let prompt = Prompt::new(&format!("Bookmark name (generation {}):", self.game.generation));
+                    self.screen = Screen::Prompt(prompt, PromptKind::Bookmark);
+                }
+                BookmarksAction::Go(at) => {
+                    let bookmark = &self.bookmarks[at];
+                    self.game.restore(bookmark.snapshot.clone());
+                    self.game.generation = bookmark.generation;
+                    self.game.rule = bookmark.rule;
+                    self.game.clear_history();
+                    self.game.is_running = false;
+                    self.log.record(self.game.generation, "load", format!("bookmark {}", bookmark.name));
+                    self.message = Some(format!("Back at `{}`, generation {}", bookmark.name, bookmark.generation));
+                    self.screen = Screen::Board;
+                    edited = true;
+                }
+                BookmarksAction::Remove(at) => {
+                    let bookmark = self.bookmarks.remove(at);
+                    self.message = Some(format!("Removed `{}`", bookmark.name));
+                }
+                BookmarksAction::None => {}
+            },
+            Screen::Puzzles(picker) => match picker.handle_key(key, &self.panels.levels) {
+                PickerAction::Close => {self.screen = Screen::Board;}
+                PickerAction::Start(level) => {
+                    self.replace_game(level.universe());
+                    // Mirrored cells would get around the puzzle's limits.
+                    self.game.symmetry = Symmetry::None;
+                    self.log.record(self.game.generation, "load", format!("puzzle {}", level.name));
+                    self.panels.puzzle = Some(PuzzleSession::new(*level));
+                    self.screen = Screen::Board;
+                }
+                PickerAction::None => {}
+            },
+            Screen::Settings(panel) => match panel.handle_key(key) {
+                SettingsAction::Close => {self.screen = Screen::Board;}
+                SettingsAction::Set(setting, text) => {edited = self.set_setting(setting, &text);}
+                SettingsAction::Cycle(Setting::Topology, _) if self.panels.puzzle.is_some() => {
+                    self.message = Some("Changing the edges is disabled in puzzles".to_string());
+                }
+                SettingsAction::Cycle(Setting::Topology, by) => {self.game.cycle_topology(by);}
+                SettingsAction::Cycle(Setting::Glyphs, by) => {self.game.set_glyphs(self.game.glyphs.cycle(by));}
+                SettingsAction::Cycle(Setting::Coloring, by) => {self.game.coloring = self.game.coloring.cycle(by);}
+                SettingsAction::Cycle(Setting::Theme, by) => {self.game.set_theme(self.game.theme.cycle(&self.themes, by));}
+                SettingsAction::Cycle(Setting::Symmetry, _) if self.panels.puzzle.is_some() => {
+                    self.message = Some("Symmetric editing is disabled in puzzles".to_string());
+                }
+                SettingsAction::Cycle(Setting::Symmetry, by) => {self.game.symmetry = self.game.symmetry.cycle(by);}
+                SettingsAction::Cycle(Setting::EdgePreview, _) => {self.game.edge_preview = !self.game.edge_preview;}
+                SettingsAction::Cycle(Setting::Render, by) => {
+                    self.game.set_render(self.game.render.cycle(by, self.graphics));
+                }
+                SettingsAction::Cycle(..) | SettingsAction::None => {}
+            },
+            Screen::Board => match key {
+                Key::Up | Key::Char('k') => {self.game.move_cursor(-by, 0);}
+                Key::Down | Key::Char('j') => {self.game.move_cursor(by, 0);}
+                Key::Right | Key::Char('l') => {self.game.move_cursor(0, by);}
+                Key::Left | Key::Char('h') => {self.game.move_cursor(0, -by);}
+                Key::Char('r') => {
+                    self.game.is_running = true;
+                }
+                Key::Char('s') => {
+                    self.game.is_running = false;
+                }
+                // Many generations run like :goto, in batches without drawing them.
+                Key::Char('n') if count > 1 && self.panels.puzzle.is_none() && self.layers.is_none() => {
+                    self.goto(self.game.generation + count);
+                }
+                Key::Char('n') => {
+                    for _ in 0..count {self.step();}
+                }
+                Key::Char('p') if self.panels.puzzle.is_some() => {
+                    self.message = Some("Stepping back is disabled in puzzles".to_string());
+                }
+                Key::Char('p') if count > 1 => {self.goto(self.game.generation.saturating_sub(count));}
+                Key::Char('p') => {
+                    let back = match &mut self.layers {
+                        Some(layers) => layers.tick_back(&mut self.game),
+                        None => self.game.tick_back().map(|_| ()),
+                    };
+                    match back {
+                        Ok(()) => {edited = true;}
+                        Err(msg) => {self.message = Some(msg.to_string());}
+                    };
+                }
+                Key::Char('c') => {
+                    let question = if self.panels.puzzle.is_some() {"Restart the puzzle?"} else {"Clear the board?"};
+                    self.screen = Screen::Confirm(question.to_string(), Confirmed::Clear);
+                }
+                Key::Char('d') if self.panels.puzzle.is_some() => {
+                    self.message = Some("Random soups are disabled in puzzles".to_string());
+                }
+                Key::Char('d') if self.game.population > 0 => {
+                    let question = "Replace the board with a random soup?".to_string();
+                    self.screen = Screen::Confirm(question, Confirmed::Randomize);
+                }
+                Key::Char('d') => {edited = self.confirmed(Confirmed::Randomize);}
+                Key::Char('f') => {
+                    match self.game.nearest_activity() {
+                        Some(cell) => {
+                            self.game.selected_cell = cell;
+                            self.game.show_cursor = true;
+                            self.game.center_view(cell.0, cell.1);
+                        }
+                        None => {self.message = Some("No live cells to jump to".to_string());}
+                    }
+                }
+                Key::Char('F') => {
+                    self.following = !self.following;
+                    if self.following {self.follow();}
+                }
+                Key::Char('m') => {
+                    self.game.marker = Some(self.game.selected_cell);
+                }
+                Key::Char('M') => {
+                    self.game.marker = None;
+                }
+                Key::Char('N') => {
+                    let prompt = Prompt::new("Note for this cell (empty removes it):");
+                    self.screen = Screen::Prompt(prompt, PromptKind::Note(self.game.selected_cell));
+                }
+                Key::Char('L') => {
+                    self.screen = Screen::Notes;
+                }
+                Key::Char('E' | 'S' | 'D' | 'x' | 'R' | 'i') if self.sandboxed => {
+                    self.message = Some(NO_FILES.to_string());
+                }
+                Key::Char('v') if self.sandboxed && self.panels.recording.is_some() => {
+                    self.message = Some(NO_FILES.to_string());
+                }
+                Key::Char('E') => {
+                    let prompt = Prompt::new("Save event log to (events.jsonl):");
+                    self.screen = Screen::Prompt(prompt, PromptKind::ExportEvents);
+                }
+                Key::Char('V') => {
+                    self.screen = Screen::Prompt(Prompt::new("Save view as:"), PromptKind::SaveView);
+                }
+                Key::Char('\'') if self.game.saved_views.is_empty() => {
+                    self.message = Some("No saved views yet, Shift+V saves one".to_string());
+                }
+                Key::Char('\'') => {
+                    let names: Vec<&str> = self.game.saved_views.keys().map(|n| n.as_str()).collect();
+                    let prompt = Prompt::new(&format!("Go to view ({}):", names.join(", ")));
+                    self.screen = Screen::Prompt(prompt, PromptKind::RestoreView);
+                }
+                Key::Char('B') if self.panels.puzzle.is_some() => {
+                    self.message = Some("Changing the rule is disabled in puzzles".to_string());
+                }
+                Key::Char('B') => {
+                    let prompt = Prompt::new(&format!("Rule (now {}, empty for B3/S23):", self.game.rule));
+                    self.screen = Screen::Prompt(prompt, PromptKind::Rule);
+                }
+                Key::Char('S') => {
+                    let prompt = Prompt::new(&format!("Save everything to ({}):", STATE_PATH_DEFAULT));
+                    self.screen = Screen::Prompt(prompt, PromptKind::SaveState);
+                }
+                Key::Char('D') if self.panels.puzzle.is_some() => {
+                    self.message = Some("Loading boards is disabled in puzzles".to_string());
+                }
+                Key::Char('D') => {
+                    let prompt = Prompt::new(&format!("Load a saved game from ({}):", STATE_PATH_DEFAULT));
+                    self.screen = Screen::Prompt(prompt, PromptKind::LoadState);
+                }
+                Key::Char('x') => {
+                    let prompt = Prompt::new("Save the board as RLE to (board.rle):");
+                    self.screen = Screen::Prompt(prompt, PromptKind::ExportRle);
+                }
+                Key::Char('R') => {
+                    let prompt = Prompt::new("Reference pattern file (empty removes it):");
+                    self.screen = Screen::Prompt(prompt, PromptKind::Reference);
+                }
+                Key::Char('T') => {
+                    let prompt = Prompt::new("Pause after running for how many seconds (empty turns the timer off):");
+                    self.screen = Screen::Prompt(prompt, PromptKind::Timer);
+                }
+                Key::Char('J') if self.panels.puzzle.is_some() => {
+                    self.message = Some("Jumping ahead is disabled in puzzles".to_string());
+                }
+                Key::Char('J') if self.layers.is_some() => {
+                    self.message = Some("Jumping ahead is not available in 3D mode".to_string());
+                }
+                Key::Char('J') => {
+                    self.screen = Screen::Prompt(Prompt::new("Jump ahead by how many generations:"), PromptKind::Jump);
+                }
+                Key::Char('I') if self.panels.puzzle.is_some() => {
+                    self.message = Some("Stepping back is disabled in puzzles".to_string());
+                }
+                Key::Char('I') if self.layers.is_some() => {
+                    self.message = Some("The timeline is not available in 3D mode".to_string());
+                }
+                Key::Char('I') if self.game.history_len() == 0 => {
+                    self.message = Some("No earlier generations to go through yet".to_string());
+                }
+                Key::Char('I') => {
+                    self.game.is_running = false;
+                    self.game.forget_later();
+                    self.screen = Screen::Timeline;
+                }
+                Key::Ctrl('v' | 'p') if self.panels.puzzle.is_some() => {
+                    self.message = Some("Copying and pasting are disabled in puzzles".to_string());
+                }
+                Key::Ctrl('v') => {
+                    self.game.selection = Some(self.game.selected_cell);
+                    self.game.show_cursor = true;
+                    self.screen = Screen::Select;
+                }
+                Key::Ctrl('p') => {
+                    match self.game.paste() {
+                        Ok(()) => {edited = true;}
+                        Err(msg) => {self.message = Some(msg.to_string());}
+                    }
+                }
+                Key::Ctrl('d') if self.panels.puzzle.is_some() => {
+                    self.message = Some("Drawing shapes is disabled in puzzles".to_string());
+                }
+                Key::Ctrl('d') => {
+                    // The board would move on under the shape.
+                    self.game.is_running = false;
+                    self.game.show_cursor = true;
+                    let drawing = Drawing { base: self.game.snapshot(), anchor: self.game.selected_cell, shape: Shape::Line };
+                    drawing.redraw(&mut self.game);
+                    self.screen = Screen::Draw(drawing);
+                }
+                Key::Ctrl('b') if self.panels.puzzle.is_some() => {
+                    self.message = Some("Bookmarks are disabled in puzzles".to_string());
+                }
+                Key::Ctrl('b') if self.layers.is_some() => {
+                    self.message = Some("Bookmarks are not available in 3D mode".to_string());
+                }
+                Key::Ctrl('b') => {
+                    self.screen = Screen::Bookmarks(BookmarksPanel::new());
+                }
+                Key::Char(':') => {
+                    self.screen = Screen::Prompt(Prompt::new(":"), PromptKind::Command);
+                }
+                Key::Char('[') | Key::Char(']') if self.layers.is_some() => {
+                    let by = if key == Key::Char(']') {1} else {-1};
+                    if let Some(layers) = &mut self.layers {
+                        if !layers.switch(&mut self.game, by) {
+                            self.message = Some("No more layers in that direction".to_string());
+                        }
+                    }
+                }
+                Key::Char('|') => {
+                    self.game.toggle_split();
+                }
+                Key::Char('\t') => {
+                    if !self.game.switch_pane() {self.message = Some("Only one pane, | splits the screen".to_string());}
+                }
+                Key::Char('?') => {
+                    self.game.should_write_help = !self.game.should_write_help;
+                }
+                Key::Char('t') => {
+                    self.game.show_cursor = !self.game.show_cursor;
+                }
+                Key::Char(' ') => {
+                    match self.panels.puzzle.as_mut().map(|p| p.try_edit()) {
+                        Some(Err(msg)) => {self.message = Some(msg.to_string());}
+                        _ => {
+                            self.game.toggle_selected_cell();
+                            edited = true;
+                        }
+                    }
+                }
+                Key::Char('w') => {
+                    match self.panels.puzzle.as_mut().map(|p| p.try_edit()) {
+                        Some(Err(msg)) => {self.message = Some(msg.to_string());}
+                        _ => {
+                            self.game.toggle_selected_wall();
+                            edited = true;
+                        }
+                    }
+                }
+                Key::Char('y') => {
+                    self.game.next_brush();
+                    if self.game.brush.is_none() {self.message = Some("Painting off".to_string());}
+                }
+                Key::Char('Y') => {
+                    match self.game.paint_selected_cell() {
+                        Ok(()) => {edited = true;}
+                        Err(msg) => {self.message = Some(msg.to_string());}
+                    }
+                }
+                Key::Char('C') if self.panels.puzzle.is_none() => {
+                    self.screen = Screen::Confirm("Remove all walls?".to_string(), Confirmed::ClearWalls);
+                }
+                Key::Char('O') if self.panels.puzzle.is_some() => {
+                    self.message = Some("Changing the edges is disabled in puzzles".to_string());
+                }
+                Key::Char('O') => {
+                    self.game.cycle_topology(1);
+                    self.message = Some(match self.game.topology {
+                        Topology::Bounded => "Cells beyond the edges are now dead",
+                        Topology::Torus => "The edges now wrap around",
+                        Topology::Unbounded => "The board is now a window onto an unbounded plane, move off it to move it",
+                    }.to_string());
+                }
+                Key::Char('W') => {
+                    self.game.walls_alive = !self.game.walls_alive;
+                    let state = if self.game.walls_alive {"alive"} else {"dead"};
+                    self.message = Some(format!("Walls now count as {} neighbours", state));
+                }
+                Key::Char('v') => {
+                    if self.panels.recording.is_none() {
+                        self.panels.recording = Some(Recording::start(&self.game));
+                    } else {
+                        let prompt = Prompt::new("Save HTML recording to (life.html):");
+                        self.screen = Screen::Prompt(prompt, PromptKind::ExportHtml);
+                    }
+                }
+                Key::Char('i') if self.panels.puzzle.is_some() => {
+                    self.message = Some("Importing images is disabled in puzzles".to_string());
+                }
+                Key::Char('i') => {
+                    self.screen = Screen::Prompt(Prompt::new("Import image or text file:"), PromptKind::ImportImage);
+                }
+                Key::Char('b') => {
+                    self.message = Some(share::encode(&self.game));
+                }
+                Key::Char('u') | Key::Ctrl('r') if self.panels.puzzle.is_some() => {
+                    self.message = Some("Undo is disabled in puzzles".to_string());
+                }
+                Key::Char('u') | Key::Ctrl('r') => {
+                    let now = self.game.snapshot();
+                    let (snapshot, verb) = match key {
+                        Key::Char('u') => (self.edits.undo(now), "undo"),
+                        _ => (self.edits.redo(now), "redo"),
+                    };
+                    match snapshot {
+                        Some(snapshot) => {
+                            self.game.restore(snapshot);
+                            // Undoing is not an edit to undo in turn.
+                            before = None;
+                            edited = true;
+                        }
+                        None => {self.message = Some(format!("Nothing to {}", verb));}
+                    }
+                }
+                Key::Char('U') if self.panels.puzzle.is_some() => {
+                    self.message = Some("Loading boards is disabled in puzzles".to_string());
+                }
+                Key::Char('U') => {
+                    self.screen = Screen::Prompt(Prompt::new("Board code:"), PromptKind::LoadCode);
+                }
+                Key::Char('o') => {
+                    self.game.photo_mode = !self.game.photo_mode;
+                }
+                Key::Char('g') => {
+                    match self.region_corner.take() {
+                        None => {
+                            self.region_corner = Some(self.game.selected_cell);
+                            self.message = Some("Region corner set, move to the opposite corner and press G".to_string());
+                        }
+                        Some(corner) => {
+                            let prompt = Prompt::new("Rule for region (empty removes regions):");
+                            self.screen = Screen::Prompt(prompt, PromptKind::RegionRule(corner, self.game.selected_cell));
+                        }
+                    }
+                }
+                Key::Char('z') => {
+                    self.screen = Screen::Puzzles(PuzzlePicker::new());
+                }
+                Key::Char('e') => {
+                    self.screen = Screen::Settings(SettingsPanel::new());
+                }
+                Key::Esc if counted.is_some() => {}
+                Key::Esc if self.panels.puzzle.is_some() => {
+                    self.panels.puzzle = None;
+                    self.game.is_running = false;
+                }
+                Key::Char('X') => {
+                    self.screen = Screen::Lexicon(LexiconBrowser::new(&self.panels.lexicon));
+                }
+                Key::Char('a') => {
+                    if self.panels.challenges.is_some() {
+                        self.screen = Screen::Challenges;
+                    } else {
+                        self.message = Some("Challenges are off, start with --challenges".to_string());
+                    }
+                }
+                Key::Alt('w') => {self.game.pan(-by, 0);}
+                Key::Alt('s') => {self.game.pan(by, 0);}
+                Key::Alt('a') => {self.game.pan(0, -by);}
+                Key::Alt('d') => {self.game.pan(0, by);}
+                Key::Alt(c @ ('-' | '+' | '=')) => {
+                    if !self.game.zoom_by(c == '-') {
+                        self.message = Some(format!("Zoom is already at {0}x{0} cells per dot", self.game.zoom));
+                    }
+                }
+                Key::Char('G') => {
+                    self.game.set_render(self.game.render.cycle(1, self.graphics));
+                    self.message = Some(match self.game.render {
+                        RenderMode::Auto => format!("Drawing the board in the first mode it fits in, now {}",
+                                                    self.game.drawn_as().name()),
+                        mode => format!("Drawing the board as {}", mode.name()),
+                    });
+                }
+                Key::Char('H') => {
+                    self.message = Some(match self.game.toggle_trails() {
+                        true => format!("Cells that died leave a trail for {} generations", self.game.trail_len),
+                        false => "Trails are off".to_string(),
+                    });
+                }
+                Key::Char('P') => {
+                    self.game.set_theme(self.game.theme.cycle(&self.themes, 1));
+                    self.message = Some(format!("Theme: {}", self.game.theme.name));
+                }
+                Key::Char('A') => {
+                    self.game.coloring = self.game.coloring.cycle(1);
+                    self.message = Some(format!("Colouring cells by {}", self.game.coloring.name()));
+                }
+                Key::Char('-') => {self.tick_millis += 50;}
+                Key::Char('+') => {self.tick_millis = max(self.tick_millis.saturating_sub(50), 50);}
+                Key::Char('q') => return false,
+                other => {
+                    self.message = Some(format!("Unexpected key: {:?}", other));
+                }
+            },
+        }
+
+        if edited {
+            if let Some(before) = before {self.edits.record(before);}
+            self.log.record(self.game.generation, "edit", format!("population {}", self.game.population));
+            if let Some(ch) = self.panels.challenges.as_mut() {ch.reset(&self.game);}
+            if let Some(c) = &self.checkpointer {
+                if let Err(e) = c.save(&self.game, &self.rng) {self.message = Some(e);}
+            }
+        }
+        true
+    }
+
+    /// Carries out a command typed after `:`. Returns whether the board
+    /// changed.
+    fn command(&mut self, text: &str) -> bool {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let puzzle = self.panels.puzzle.is_some();
+        let usage = match words.as_slice() {
+            [] => return false,
+            ["save" | "load", ..] if self.sandboxed => {
+                self.message = Some(NO_FILES.to_string());
+                return false;
+            }
+            ["save", path] if path.ends_with(".rle") => {
+                self.confirm_overwrite(path, Confirmed::ExportRle(path.to_string()));
+                return false;
+            }
+            ["save", path] => {
+                self.confirm_overwrite(path, Confirmed::SaveState(path.to_string()));
+                return false;
+            }
+            ["save", ..] => "save <file>, RLE for .rle and the whole game otherwise",
+            ["load", _] if puzzle => {
+                self.message = Some("Loading boards is disabled in puzzles".to_string());
+                return false;
+            }
+            ["load", path] if path.ends_with(".json") => {
+                self.load_state(path);
+                return false;
+            }
+            ["load", path] => return self.import(path),
+            ["load", ..] => "load <file>, a saved game (.json), pattern or image",
+            ["rule", ..] if puzzle => {
+                self.message = Some("Changing the rule is disabled in puzzles".to_string());
+                return false;
+            }
+            ["rule", rule] => {
+                self.set_rule(rule);
+                return false;
+            }
+            ["rule", ..] => "rule <rulestring>, e.g. B36/S23",
+            ["resize", ..] if puzzle => {
+                self.message = Some("The size is fixed in puzzles".to_string());
+                return false;
+            }
+            ["resize", ..] if self.layers.is_some() => {
+                self.message = Some("The size is fixed in 3D mode".to_string());
+                return false;
+            }
+            ["resize", width, height] => match (width.parse(), height.parse()) {
+                (Ok(width @ 1..), Ok(height @ 1..)) => {
+                    self.game.resize(width, height);
+                    self.message = Some(format!("The board is now {}x{}", width, height));
+                    return true;
+                }
+                _ => "resize <width> <height>, both above 0",
+            },
+            ["resize", ..] => "resize <width> <height>",
+            ["random", ..] if puzzle => {
+                self.message = Some("Random soups are disabled in puzzles".to_string());
+                return false;
+            }
+            ["random"] => return self.confirmed(Confirmed::Randomize),
+            ["random", density] => match density.parse::<f64>() {
+                Ok(density) if (0.0..=1.0).contains(&density) => {
+                    // Only this soup; later ones keep the density set.
+                    let density = mem::replace(&mut self.density, density);
+                    let edited = self.confirmed(Confirmed::Randomize);
+                    self.density = density;
+                    return edited;
+                }
+                _ => "random [density], from 0 to 1",
+            },
+            ["random", ..] => "random [density]",
+            ["step"] => {
+                self.goto(self.game.generation + 1);
+                return false;
+            }
+            ["step", generations] => match generations.parse::<u64>() {
+                Ok(generations @ 1..) => {
+                    self.goto(self.game.generation.saturating_add(generations));
+                    return false;
+                }
+                _ => "step [generations], above 0",
+            },
+            ["step", ..] => "step [generations]",
+            ["invert", ..] if puzzle => {
+                self.message = Some("Inverting is disabled in puzzles".to_string());
+                return false;
+            }
+            ["invert"] => {
+                self.game.invert(None);
+                self.log.record(self.game.generation, "edit", "inverted the board");
+                return true;
+            }
+            ["invert", ..] => "invert, the whole board",
+            ["shift", ..] if puzzle => {
+                self.message = Some("Shifting is disabled in puzzles".to_string());
+                return false;
+            }
+            ["shift", ..] if self.layers.is_some() => {
+                self.message = Some("Shifting is not available in 3D mode".to_string());
+                return false;
+            }
+            ["shift", way] | ["shift", way, _] => {
+                let by = words.get(2).map_or(Ok(1), |by| by.parse::<isize>());
+                let (rows, cols) = match *way {
+                    "up" => (-1, 0),
+                    "down" => (1, 0),
+                    "left" => (0, -1),
+                    "right" => (0, 1),
+                    _ => (0, 0),
+                };
+                match by {
+                    Ok(by @ 1..) if (rows, cols) != (0, 0) => {
+                        self.game.shift(rows * by, cols * by);
+                        self.log.record(self.game.generation, "edit", format!("shifted the board {} by {}", way, by));
+                        return true;
+                    }
+                    _ => "shift <up|down|left|right> [cells], above 0",
+                }
+            }
+            ["shift", ..] => "shift <up|down|left|right> [cells]",
+            ["symmetry", ..] if puzzle => {
+                self.message = Some("Symmetric editing is disabled in puzzles".to_string());
+                return false;
+            }
+            ["symmetry", name] => match Symmetry::parse(name) {
+                Ok(symmetry) => {
+                    self.game.symmetry = symmetry;
+                    self.message = Some(format!("Symmetric editing: {}", symmetry.name()));
+                    return false;
+                }
+                Err(e) => {
+                    self.message = Some(e);
+                    return false;
+                }
+            },
+            ["symmetry", ..] => "symmetry <none|horizontal|vertical|both|half-turn|4-fold|diagonal>",
+            ["paste", ..] if puzzle => {
+                self.message = Some("Copying and pasting are disabled in puzzles".to_string());
+                return false;
+            }
+            ["paste"] => {
+                self.to_terminal = Some(clipboard::QUERY.to_string());
+                self.screen = Screen::Clipboard(ClipboardAnswer { asked: Instant::now(), text: None });
+                return false;
+            }
+            ["paste", ..] => "paste, a pattern from the system clipboard",
+            ["copy"] => {
+                self.copy_rle(&self.game.to_pattern());
+                return false;
+            }
+            ["copy", ..] => "copy, the whole board or the selection with C while selecting",
+            ["rotate" | "flip", ..] if self.game.clipboard.is_none() => {
+                self.message = Some("Nothing to paste, select cells with Ctrl+V and copy them first".to_string());
+                return false;
+            }
+            ["rotate", way @ ..] if matches!(way, [] | ["right"] | ["left"]) => {
+                self.transform_clipboard(if way == ["left"] {Transform::RotateLeft} else {Transform::RotateRight});
+                return false;
+            }
+            ["rotate", ..] => "rotate [left|right], the clipboard",
+            ["flip", way @ ..] if matches!(way, [] | ["horizontal"] | ["vertical"]) => {
+                self.transform_clipboard(if way == ["vertical"] {Transform::FlipVertical} else {Transform::FlipHorizontal});
+                return false;
+            }
+            ["flip", ..] => "flip [horizontal|vertical], the clipboard",
+            ["goto", generation] => match generation.parse() {
+                Ok(generation) => {
+                    self.goto(generation);
+                    return false;
+                }
+                Err(_) => "goto <generation>",
+            },
+            ["goto", ..] => "goto <generation>",
+            [name, ..] => {
+                self.message = Some(format!("Unknown command `{}`, expected save, load, rule, resize, random, invert, \
+                                             shift, symmetry, step, goto, copy, paste, rotate, flip or q", name));
+                return false;
+            }
+        };
+        self.message = Some(format!("Usage: {}", usage));
+        false
+    }
+
+    /// Shows the pattern in `text`, RLE or plaintext, at the cursor to
+    /// stamp it.
+    fn paste_text(&mut self, text: &str) {
+        match Pattern::parse(text) {
+            Ok(p) if p.cells.is_empty() => {self.message = Some("The pattern has no live cells".to_string());}
+            Ok(p) => {
+                self.game.set_ghost(Some(p));
+                self.game.show_cursor = true;
+                self.screen = Screen::Stamp;
+            }
+            Err(d) => {self.message = Some(format!("Not a pattern: {}", d));}
+        }
+    }
+
+    /// Asks for the lines of a pattern to paste after `lines`.
+    fn ask_for_pattern(&mut self, lines: Vec<String>) {
+        let label = format!("Pattern to paste, RLE or plaintext, an empty line ends it ({} lines):", lines.len());
+        self.screen = Screen::Prompt(Prompt::new(&label), PromptKind::Pattern(lines));
+    }
+
+    /// Falls back on asking for the pattern when the terminal does not
+    /// answer with the clipboard in time. Returns whether it did.
+    fn clipboard_unanswered(&mut self) -> bool {
+        let Screen::Clipboard(answer) = &self.screen else {return false};
+        if answer.text.is_some() || answer.asked.elapsed() < CLIPBOARD_WAIT {return false;}
+        self.message = Some("The terminal does not share its clipboard, paste the pattern here instead".to_string());
+        self.ask_for_pattern(Vec::new());
+        true
+    }
+
+    /// Puts `pattern` on the system clipboard as RLE.
+    fn copy_rle(&mut self, pattern: &Pattern) {
+        self.to_terminal = Some(clipboard::copy(&pattern.to_rle(self.game.rule)));
+        self.message = Some(format!("Copied {}x{} as RLE to the system clipboard (if the terminal allows OSC 52)",
+                                    pattern.width, pattern.height));
+    }
+
+    fn transform_clipboard(&mut self, transform: Transform) {
+        if let Some(clipboard) = &mut self.game.clipboard {
+            *clipboard = clipboard.transformed(transform);
+            self.message = Some(format!("Clipboard {}, now {}x{}", transform.name(), clipboard.width, clipboard.height));
+        }
+    }
+
+    /// Puts the pattern or image at `path` in the middle of the board, or
+    /// asks what to do with it if it does not fit. Returns whether the
+    /// board changed.
+    fn import(&mut self, path: &str) -> bool {
+        let (width, height) = (self.game.width, self.game.height);
+        match read_pattern(path, width, height, &self.image_options) {
+            Ok((pattern, warnings)) => {
+                if let Some(first) = warnings.first() {
+                    self.message = Some(format!("{} warnings, first: {}", warnings.len(), first));
+                }
+                if pattern.width > width || pattern.height > height {
+                    self.screen = Screen::Oversized(pattern);
+                    return false;
+                }
+                place_centered(&mut self.game, &pattern);
+                self.log.record(self.game.generation, "load", path);
+                true
+            }
+            Err(e) => {
+                self.message = Some(e);
+                false
+            }
+        }
+    }
+
+    /// Carries on from the game saved at `path`.
+    fn load_state(&mut self, path: &str) {
+        match state::load(path) {
+            Ok(saved) => {
+                // Unlike other boards a saved state brings its own settings.
+                let (engine, topology, glyphs) = (saved.game.engine, saved.game.topology, saved.game.glyphs.clone());
+                let borders = (saved.game.borders, saved.game.pane_borders);
+                let (render, coloring, trail_len) = (saved.game.render, saved.game.coloring, saved.game.trail_len);
+                let theme = saved.theme.and_then(|name| Theme::find(&self.themes, &name).ok());
+                self.replace_game(saved.game);
+                self.game.engine = engine;
+                self.game.topology = topology;
+                self.game.set_glyphs(glyphs);
+                self.game.set_borders(borders);
+                self.game.coloring = coloring;
+                self.game.trail_len = trail_len;
+                if let Some(theme) = theme {self.game.set_theme(theme);}
+                if self.graphics.supports(render) {self.game.set_render(render);}
+                if let Some(millis) = saved.tick_millis {self.tick_millis = millis;}
+                self.log.record(self.game.generation, "load", format!("state {}", path));
+                self.message = Some(format!("Loaded {}, generation {}", path, self.game.generation));
+            }
+            Err(e) => {self.message = Some(e);}
+        }
+    }
+
+    /// Switches to the rule written as `text`, or Conway's for nothing.
+    fn set_rule(&mut self, text: &str) {
+        let rule = if text.is_empty() {Ok(Rule::conway())} else {Rule::parse(text)};
+        match rule {
+            Ok(rule) => {
+                self.game.rule = rule;
+                self.log.record(self.game.generation, "edit", format!("rule {}", rule));
+                self.message = Some(format!("Rule is now {}", rule));
+            }
+            Err(e) => {self.message = Some(e);}
+        }
+    }
+
+    /// Steps back through the history to an earlier `target` generation,
+    /// or starts running to a later one.
+    fn goto(&mut self, target: u64) {
+        if self.panels.puzzle.is_some() {
+            self.message = Some("Going to a generation is disabled in puzzles".to_string());
+            return;
+        }
+        if self.layers.is_some() {
+            self.message = Some("Going to a generation is not available in 3D mode".to_string());
+            return;
+        }
+        let generation = self.game.generation;
+        if target < generation {
+            let kept = self.game.history_len() as u64;
+            if generation - target > kept {
+                self.message = Some(format!("Only {} generations back are kept, to generation {}",
+                                            kept, generation - kept));
+                return;
+            }
+            while self.game.generation > target && self.game.tick_back().is_ok() {}
+            self.log.record(self.game.generation, "edit", format!("went back to generation {}", target));
+            self.message = Some(format!("Back at generation {}", self.game.generation));
+        } else if target > generation {
+            self.goto = Some(Goto { from: generation, target });
+        } else {
+            self.message = Some(format!("Already at generation {}", target));
+        }
+    }
+
+    /// Runs the board towards the `:goto` generation for a frame's time,
+    /// without drawing the generations in between.
+    fn run_goto(&mut self) {
+        let Some(goto) = &self.goto else {return};
+        let target = goto.target;
+        let started = Instant::now();
+        while self.game.generation < target && started.elapsed() < FRAME {self.game.tick();}
+        if self.game.generation >= target {
+            self.goto = None;
+            self.log.record(self.game.generation, "edit", format!("went to generation {}", target));
+            self.message = Some(format!("At generation {}", self.game.generation));
+            self.after_tick();
+        }
+    }
+
+    /// Applies a value typed on the settings screen. Returns whether the
+    /// board changed.
+    fn set_setting(&mut self, setting: Setting, text: &str) -> bool {
+        let number = text.parse::<usize>().ok().filter(|&n| n > 0);
+        let fixed = match setting {
+            Setting::Rule if self.panels.puzzle.is_some() => Some("The rule is fixed in puzzles"),
+            Setting::Width | Setting::Height if self.panels.puzzle.is_some() => Some("The size is fixed in puzzles"),
+            Setting::Width | Setting::Height if self.layers.is_some() => Some("The size is fixed in 3D mode"),
+            _ => None,
+        };
+        if let Some(reason) = fixed {
+            self.message = Some(reason.to_string());
+            return false;
+        }
+        match (setting, number) {
+            (Setting::TickMillis, Some(millis)) => {self.tick_millis = millis as u64;}
+            (Setting::TrailLen, _) => match text.parse::<u32>() {
+                Ok(len) => {self.game.trail_len = len;}
+                Err(_) => {self.message = Some(format!("`{}` is not a number of generations", text));}
+            },
+            (Setting::Density, _) => match text.parse::<f64>() {
+                Ok(density) if (0.0..=1.0).contains(&density) => {self.density = density;}
+                _ => {self.message = Some(format!("`{}` is not a density from 0 to 1", text));}
+            },
+            (Setting::Rule, _) => {
+                let rule = if text.is_empty() {Ok(Rule::conway())} else {Rule::parse(text)};
+                match rule {
+                    Ok(rule) => {
+                        self.game.rule = rule;
+                        self.log.record(self.game.generation, "edit", format!("rule {}", rule));
+                    }
+                    Err(e) => {self.message = Some(e);}
+                }
+            }
+            (Setting::Width, Some(width)) => {
+                self.game.resize(width, self.game.height);
+                return true;
+            }
+            (Setting::Height, Some(height)) => {
+                self.game.resize(self.game.width, height);
+                return true;
+            }
+            _ => {self.message = Some(format!("`{}` is not a number above 0", text));}
+        }
+        false
+    }
+
+    /// Asks before `action` replaces the existing file at `path` and
+    /// returns `true`, or saves right away to a new file.
+    fn confirm_overwrite(&mut self, path: &str, action: Confirmed) -> bool {
+        if Path::new(path).exists() {
+            self.screen = Screen::Confirm(format!("{} exists, overwrite it?", path), action);
+            return true;
+        }
+        self.confirmed(action);
+        false
+    }
+
+    /// Carries out a confirmed action. Returns whether the board changed.
+    fn confirmed(&mut self, action: Confirmed) -> bool {
+        match action {
+            Confirmed::Clear => {
+                match &mut self.panels.puzzle {
+                    Some(p) => {
+                        let level = p.level.clone();
+                        *p = PuzzleSession::new(level.clone());
+                        self.replace_game(level.universe());
+                    }
+                    None => {self.game.clear();}
+                }
+                true
+            }
+            Confirmed::Randomize => {
+                let seed = self.next_seed.take().unwrap_or_else(|| self.rng.next_u64());
+                self.game.randomize(self.density, &mut Rng::new(seed));
+                self.soup_seed = Some(seed);
+                self.log.record(self.game.generation, "edit",
+                                format!("random soup, density {}, seed {}", self.density, seed));
+                true
+            }
+            Confirmed::ClearWalls => {
+                self.game.clear_walls();
+                true
+            }
+            Confirmed::ExportHtml(path) => {
+                if let Some(rec) = self.panels.recording.take() {
+                    let success = format!("Saved {} generations to {}", rec.len(), path);
+                    self.jobs.push(Job::spawn(path.clone(), success, rec.len(),
+                                              move |progress| rec.save_html(&path, progress)));
+                }
+                false
+            }
+            Confirmed::SaveState(path) => {
+                let (game, tick_millis) = (self.game.clone(), self.tick_millis);
+                let success = format!("Saved the game at generation {} to {}", game.generation, path);
+                self.jobs.push(Job::spawn(path.clone(), success, 1, move |progress| {
+                    state::save(&path, &game, tick_millis)?;
+                    progress.add(1);
+                    Ok(())
+                }));
+                false
+            }
+            Confirmed::ExportRle(path) => {
+                let rle = self.game.to_rle();
+                let success = format!("Saved the board as RLE to {}", path);
+                self.jobs.push(Job::spawn(path.clone(), success, 1, move |progress| {
+                    fs::write(&path, rle)?;
+                    progress.add(1);
+                    Ok(())
+                }));
+                false
+            }
+            Confirmed::ExportEvents(path) => {
+                let log = self.log.clone();
+                let success = format!("Saved {} events to {}", log.len(), path);
+                self.jobs.push(Job::spawn(path.clone(), success, log.len(),
+                                          move |progress| log.save(&path, progress)));
+                false
+            }
+        }
+    }
+
+    fn quickload(&mut self) {
+        if self.panels.puzzle.is_some() {
+            self.message = Some("Loading boards is disabled in puzzles".to_string());
+            return;
+        }
+        let path = self.quicksave.path.clone();
+        match checkpoint::load(&path) {
+            Ok(c) => {
+                self.replace_game(c.game);
+                self.rng = c.rng;
+                self.log.record(self.game.generation, "load", format!("quicksave {}", path));
+                self.message = Some(format!("Quickloaded {}, generation {}", path, self.game.generation));
+            }
+            Err(e) => {self.message = Some(format!("Cannot quickload {}: {}", path, e));}
+        }
+    }
+
+    /// Advances the board, or every layer in 3D mode, by a generation.
+    fn step(&mut self) {
+        match &mut self.layers {
+            Some(layers) => layers.tick(&mut self.game),
+            None => self.game.tick(),
+        }
+        self.after_tick();
+    }
+
+    fn after_tick(&mut self) {
+        self.last_tick = Instant::now();
+        if let Some(c) = &self.checkpointer {
+            if let Err(e) = c.after_tick(&self.game, &self.rng) {self.message = Some(e);}
+        }
+        if self.following {self.follow();}
+        let game = &self.game;
+        let panels = &mut self.panels;
+        let log = &mut self.log;
+        let generation = game.generation;
+        let (births, deaths) = game.last_changes();
+        log.record(generation, "tick", format!("births {} deaths {} population {}",
+                                               births, deaths, game.population));
+        #[cfg(feature = "audio")]
+        if let Some(s) = &panels.sonifier {s.play(game, self.tick_millis);}
+        if let Some(ch) = panels.challenges.as_mut() {
+            let before = ch.completed().len();
+            ch.after_tick(game);
+            for c in &ch.completed()[before..] {
+                log.record(generation, "trigger", format!("challenge complete: {}", c.description()));
+            }
+        }
+        if let Some(p) = panels.puzzle.as_mut() {
+            let before = p.outcome();
+            p.after_tick(game);
+            if let (None, Some(outcome)) = (before, p.outcome()) {
+                log.record(generation, "trigger", format!("puzzle {}: {}", outcome, p.level.name));
+            }
+        }
+        if let Some(rec) = panels.recording.as_mut() {rec.capture(game);}
+        if let Some(midi) = panels.midi.as_mut() {midi.play(game);}
+    }
+
+    /// Switches to another board, keeping the cursor visible and the view
+    /// fitted to the terminal.
+    fn replace_game(&mut self, game: Universe) {
+        let old = mem::replace(&mut self.game, game);
+        self.game.show_cursor = true;
+        // The board no longer is the soup the seed made.
+        self.soup_seed = None;
+        self.game.engine = old.engine;
+        self.game.topology = old.topology;
+        self.game.glyphs = old.glyphs;
+        self.game.borders = old.borders;
+        self.game.pane_borders = old.pane_borders;
+        self.game.ascii = old.ascii;
+        self.game.zoom = old.zoom;
+        self.game.render = old.render;
+        self.game.coloring = old.coloring;
+        self.game.trail_len = old.trail_len;
+        self.game.theme = old.theme;
+        self.game.clipboard = old.clipboard;
+        self.game.symmetry = old.symmetry;
+        self.game.edge_preview = old.edge_preview;
+        if let Some((rows, cols)) = old.view_size {self.game.set_view_size(rows, cols);}
+        if self.layers.is_some() && (old.width, old.height) != (self.game.width, self.game.height) {
+            self.layers = None;
+            self.message = Some("3D mode is off, the new board has another size".to_string());
+        }
+    }
+
+    /// Loads the watched file again if it changed since the last look,
+    /// keeping the view. Returns whether the board was replaced.
+    fn reload_watched(&mut self) -> bool {
+        let Some(watch) = self.watch.as_mut() else {return false};
+        if !watch.changed() {return false;}
+
+        match checkpoint::load(&watch.path) {
+            Ok(c) => {
+                let old = mem::replace(&mut self.game, c.game);
+                self.game.show_cursor = old.show_cursor;
+                self.game.photo_mode = old.photo_mode;
+                self.game.should_write_help = old.should_write_help;
+                self.game.marker = old.marker;
+                self.game.glyphs = old.glyphs;
+                self.game.borders = old.borders;
+                self.game.pane_borders = old.pane_borders;
+                self.game.ascii = old.ascii;
+                self.game.zoom = old.zoom;
+                self.game.render = old.render;
+                self.game.coloring = old.coloring;
+                self.game.trail_len = old.trail_len;
+                self.game.theme = old.theme;
+                if let Some((rows, cols)) = old.view_size {self.game.set_view_size(rows, cols);}
+                if (old.width, old.height) == (self.game.width, self.game.height) {
+                    self.game.selected_cell = old.selected_cell;
+                    self.game.view = old.view;
+                    self.game.other_pane = old.other_pane;
+                    self.game.focus_right = old.focus_right;
+                }
+                self.message = Some(format!("Following {}, generation {}", watch.path, self.game.generation));
+            }
+            Err(e) => {self.message = Some(format!("Cannot read {}: {}", watch.path, e));}
+        }
+        true
+    }
+
+    /// Loads the themes and applies the configuration file again if they
+    /// changed since the last look. A file that does not read leaves the
+    /// settings as they were.
+    fn reload_settings(&mut self) -> bool {
+        let mut reloaded = false;
+        if let Some(path) = self.themes_watch.as_mut().and_then(|watch| watch.changed().then(|| watch.path.clone())) {
+            match theme::load(Some(Path::new(&path))) {
+                Ok(themes) => {
+                    // The theme in use takes its new colours, if it is still there.
+                    if let Ok(theme) = Theme::find(&themes, &self.game.theme.name) {self.game.set_theme(theme);}
+                    self.themes = themes;
+                    self.message = Some(format!("Reloaded {}", path));
+                }
+                Err(e) => {self.message = Some(format!("{}; the themes stay as they were", e));}
+            }
+            reloaded = true;
+        }
+        let changed = self.config_watch.as_mut()
+            .and_then(|(watch, overridden)| watch.changed().then(|| (watch.path.clone(), overridden.clone())));
+        if let Some((path, overridden)) = changed {
+            match self.read_config(&path, &overridden) {
+                Ok(configured) => {
+                    configured.into_iter().for_each(|setting| self.configure(setting));
+                    self.message = Some(format!("Reloaded {}", path));
+                }
+                Err(e) => {self.message = Some(format!("{}; the settings stay as they were", e));}
+            }
+            reloaded = true;
+        }
+        reloaded
+    }
+
+    /// The settings in the configuration file at `path`, but for the
+    /// `overridden` keys. The size is left alone, as the board is not
+    /// resized under the running game, and so are the rule and the edges
+    /// in puzzles.
+    fn read_config(&self, path: &str, overridden: &[&str]) -> Result<Vec<Configured>, String> {
+        let number = |key: &str, value: &str| value.parse::<usize>().ok().filter(|&n| n > 0)
+            .ok_or(format!("{}: `{}` must be a positive number", path, key));
+        let puzzle = self.panels.puzzle.is_some();
+        let mut configured = Vec::new();
+        for (key, value) in config::read(Path::new(path))? {
+            if overridden.contains(&key.as_str()) {continue;}
+            let in_file = |e: String| format!("{}: {}", path, e);
+            configured.push(match key.as_str() {
+                "tick_ms" => Configured::TickMillis(number(&key, &value)? as u64),
+                "rule" if !puzzle => Configured::Rule(Rule::parse(&value).map_err(in_file)?),
+                "topology" if !puzzle => Configured::Topology(Topology::parse(&value).map_err(in_file)?),
+                "theme" => Configured::Theme(Theme::find(&self.themes, &value).map_err(in_file)?),
+                "glyphs" => Configured::Glyphs(Glyphs::parse(&value).map_err(in_file)?),
+                "history" => Configured::HistoryLen(number(&key, &value)?),
+                "history_mb" => Configured::HistoryMb(number(&key, &value)?),
+                _ => continue,
+            });
+        }
+        Ok(configured)
+    }
+
+    fn configure(&mut self, setting: Configured) {
+        match setting {
+            Configured::TickMillis(millis) => {self.tick_millis = millis;}
+            Configured::Rule(rule) => {self.game.rule = rule;}
+            Configured::Topology(topology) => {self.game.set_topology(topology);}
+            Configured::Theme(theme) => {self.game.set_theme(theme);}
+            Configured::Glyphs(glyphs) => {self.game.set_glyphs(glyphs);}
+            Configured::HistoryLen(len) => {self.game.history.len_max = Some(len);}
+            Configured::HistoryMb(mb) => {self.game.history.budget = mb << 20;}
+        }
+    }
+
+    fn follow(&mut self) {
+        if let Some((row, col)) = self.game.centroid() {self.game.center_view(row, col);}
+    }
+
+    /// The keys that matter most right now, for the line below the board.
+    fn board_hints(&self) -> String {
+        let help = if self.game.should_write_help {"[?] hide help"} else {"[?] help"};
+        if self.watch.is_some() {
+            return format!("Read-only: [Arrows] look around  [F] find activity  {}  [Q] quit", help);
+        }
+        if let Some(count) = self.count {
+            return format!("Count {}: [Arrows/HJKL] move  [N] step  [P] back  [Shift+arrows] pan  [Esc] cancel", count);
+        }
+        let mut hints = vec!["[Arrows] move", "[Space] toggle"];
+        hints.extend(if self.game.is_running {["[S] stop", "[+/-] speed"]} else {["[R] run", "[N] step"]});
+        if self.region_corner.is_some() {hints.push("[G] opposite corner");}
+        if self.panels.recording.is_some() {hints.push("[V] stop recording");}
+        if self.layers.is_some() {hints.push("[[/]] layer");}
+        if self.game.other_pane.is_some() {hints.push("[Tab] other pane  [|] unsplit");}
+        if self.game.brush.is_some() {hints.push("[Y] colour  [Shift+Y] paint");}
+        hints.extend([help, "[Q] quit"]);
+        hints.join("  ")
+    }
+
+    fn redraw(&mut self, stdout: &mut dyn Write) {
+        self.last_frame = Instant::now();
+        let fade_millis = self.tick_millis.min(FADE_MILLIS_MAX) as f32;
+        let progress = self.last_tick.elapsed().as_millis() as f32 / fade_millis;
+        self.game.fade = if self.fade && progress < 1.0 {Some(progress)} else {None};
+
+        let state = if self.game.is_running {"running"} else {"paused"};
+        let title = format!("Game Of Life: generation {}, population {}, {}",
+                            self.game.generation, self.game.population, state);
+        if title != self.title {
+            write!(stdout, "\x1b]0;{}\x07", title).unwrap();
+            self.title = title;
+        }
+        if let Some(sequence) = self.to_terminal.take() {write!(stdout, "{}", sequence).unwrap();}
+
+        let mut screen = Vec::new();
+        self.render(&mut screen);
+        let size = termion::terminal_size().ok();
+        self.frame.draw(stdout, &String::from_utf8_lossy(&screen), size);
+        self.graphics.clear(stdout);
+        self.game.write_graphics(stdout);
+        stdout.flush().unwrap();
+    }
+
+    fn render(&self, stdout: &mut dyn Write) {
+        let game = &self.game;
+        let panels = &self.panels;
+        game.render(stdout);
+        if game.photo_mode {return;}
+        let (row, col) = game.selected_cell;
+        let seed = self.soup_seed.map(|seed| format!("  Seed {}", seed)).unwrap_or_default();
+        let symmetry = match game.symmetry {
+            Symmetry::None => String::new(),
+            symmetry => format!("  Symmetry {}", symmetry.name()),
+        };
+        let status = format!("Generation {}  Population {}  Cursor {},{}  {}  Tick {} ms{}{}",
+                             game.generation, game.population, col, row,
+                             if game.is_running {"Running"} else {"Paused"}, self.tick_millis, seed, symmetry);
+        write_in(stdout, game.theme.status, &status);
+        write!(stdout, "\n\r").unwrap();
+        if let Some(ch) = &panels.challenges {ch.render_banner(stdout);}
+        if let Some(rec) = &panels.recording {
+            write!(stdout, "Recording: {} generations (V to stop and export)\n\r", rec.len()).unwrap();
+        }
+        if let Some(layers) = &self.layers {
+            write!(stdout, "3D: layer {} of {}, rule {} ([ and ] change layer)\n\r",
+                   layers.current() + 1, layers.depth(), layers.rule).unwrap();
+        }
+        if self.timer.elapsed() > Duration::ZERO || self.timer.limit().is_some() {
+            write!(stdout, "Run time: {}", format_duration(self.timer.elapsed())).unwrap();
+            if let Some(limit) = self.timer.limit() {
+                write!(stdout, ", pauses at {}", format_duration(limit)).unwrap();
+            }
+            write!(stdout, "\n\r").unwrap();
+        }
+        for job in &self.jobs {
+            write!(stdout, "Saving {}: {}%\n\r", job.label, job.percent()).unwrap();
+        }
+        if let Some(goto) = &self.goto {
+            let percent = (game.generation - goto.from) * 100 / (goto.target - goto.from);
+            write!(stdout, "Going to generation {}: {}% (Esc stops)\n\r", goto.target, percent).unwrap();
+        }
+        if self.following {
+            write!(stdout, "Camera: following the live cells (Shift+F to stop)\n\r").unwrap();
+        }
+        match &self.screen {
+            Screen::Board => {
+                if let Some(p) = &panels.puzzle {p.render(stdout);}
+                write!(stdout, "{}\n\r", self.board_hints()).unwrap();
+            }
+            Screen::Lexicon(br) => br.render(stdout, &panels.lexicon, game.ascii),
+            Screen::Challenges => {
+                if let Some(ch) = &panels.challenges {ch.render(stdout);}
+            }
+            Screen::Notes => game.render_notes(stdout),
+            Screen::Oversized(pattern) => {
+                write!(stdout, "The pattern is {}x{}, larger than the {}x{} board:\n\r",
+                       pattern.width, pattern.height, game.width, game.height).unwrap();
+                pattern.render_preview(stdout, PREVIEW_ROWS, PREVIEW_COLS, game.ascii);
+                write!(stdout, "[G]row the board  [C]lip to the top left  c[E]nter-crop  [Esc] cancel\n\r").unwrap();
+            }
+            Screen::Puzzles(picker) => picker.render(stdout, &panels.levels, &panels.level_errors),
+            Screen::Settings(panel) => panel.render(stdout, game, self.tick_millis, self.density),
+            Screen::Prompt(prompt, _) => prompt.render(stdout),
+            Screen::Bookmarks(panel) => panel.render(stdout, &self.bookmarks),
+            Screen::Move(grab) => {
+                write!(stdout, "Moving {}x{} to {},{}: [Arrows/HJKL] move  [Enter] drop  [Esc] put back\n\r",
+                       grab.cells.width, grab.cells.height, grab.at.1, grab.at.0).unwrap();
+            }
+            Screen::Clipboard(_) => write!(stdout, "Reading the system clipboard... [Esc] stop\n\r").unwrap(),
+            Screen::Stamp => {
+                if let Some(ghost) = &game.ghost {
+                    write!(stdout, "Stamping {}x{} at {},{}: [Arrows/HJKL] move  [R/Shift+R] rotate  [F/Shift+F] flip  \
+                                    [Enter] stamp  [Esc] cancel\n\r",
+                           ghost.width, ghost.height, game.selected_cell.1, game.selected_cell.0).unwrap();
+                }
+            }
+            Screen::Draw(drawing) => {
+                write!(stdout, "Drawing a {} from {},{}: [Arrows/HJKL] move the end  [Tab/Shift+Tab] shape  \
+                                [Enter] draw  [Esc] cancel\n\r",
+                       drawing.shape.name(), drawing.anchor.1, drawing.anchor.0).unwrap();
+            }
+            Screen::Select => {
+                if let Some(((top, left), (bottom, right))) = game.selection_bounds() {
+                    write!(stdout, "Selecting {}x{}: [Arrows/HJKL] extend  [Y] copy  [X] cut  [M] move  \
+                                    [C] copy as RLE  [S] soup  [I] invert  [R/Shift+R] rotate  [F/Shift+F] flip  [Esc] cancel\n\r",
+                           right - left + 1, bottom - top + 1).unwrap();
+                }
+            }
+            Screen::Timeline => {
+                let earliest = game.generation - game.history_len() as u64;
+                let latest = game.generation + game.later_len() as u64;
+                let at = ((game.generation - earliest) * (TIMELINE_COLS as u64 - 1) / (latest - earliest).max(1)) as usize;
+                write!(stdout, "Timeline: generation {} of {} to {}  [{}#{}]\n\r", game.generation, earliest, latest,
+                       "-".repeat(at), "-".repeat(TIMELINE_COLS - 1 - at)).unwrap();
+                write!(stdout, "[Left/Right] step  [Home/End] earliest/latest  [Enter] carry on from here  [Esc] back to the latest\n\r").unwrap();
+            }
+            Screen::Confirm(question, _) => {
+                write!(stdout, "{} [y/N]\n\r", question).unwrap();
+                write!(stdout, "[Y] yes  [any other key] no\n\r").unwrap();
+            }
+        }
+        if let Some(msg) = &self.message {
+            write!(stdout, "{}", msg).unwrap();
+        }
+    }
+}
+
@@ -0,0 +1,162 @@
+use std::io::Write;
+
+use termion::event::Key;
+
+use crate::prompt::{Prompt, PromptAction};
+use crate::{Topology, Universe};
+
+
+/// What the settings screen can change.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Setting {
+    TickMillis,
+    Rule,
+    Topology,
+    Width,
+    Height,
+    Glyphs,
+    Render,
+    Coloring,
+    TrailLen,
+    Theme,
+    Density,
+    Symmetry,
+    EdgePreview,
+}
+
+
+const SETTINGS: [Setting; 13] = [
+    Setting::TickMillis,
+    Setting::Rule,
+    Setting::Topology,
+    Setting::Width,
+    Setting::Height,
+    Setting::Glyphs,
+    Setting::Render,
+    Setting::Coloring,
+    Setting::TrailLen,
+    Setting::Theme,
+    Setting::Density,
+    Setting::Symmetry,
+    Setting::EdgePreview,
+];
+
+
+impl Setting {
+    fn label(self) -> &'static str {
+        match self {
+            Setting::TickMillis => "Tick interval (ms)",
+            Setting::Rule => "Rule",
+            Setting::Topology => "Edges",
+            Setting::Width => "Width",
+            Setting::Height => "Height",
+            Setting::Glyphs => "Glyphs",
+            Setting::Render => "Render mode",
+            Setting::Coloring => "Cell colours",
+            Setting::TrailLen => "Trail (generations)",
+            Setting::Theme => "Theme",
+            Setting::Density => "Random soup density",
+            Setting::Symmetry => "Symmetric editing",
+            Setting::EdgePreview => "Torus edge preview",
+        }
+    }
+
+    /// Settings with a few fixed values are cycled through instead of typed.
+    fn is_choice(self) -> bool {
+        matches!(self, Setting::Topology | Setting::Glyphs | Setting::Render | Setting::Coloring | Setting::Theme
+                       | Setting::Symmetry | Setting::EdgePreview)
+    }
+}
+
+
+pub enum SettingsAction {
+    None,
+    Close,
+    /// Set the setting to the typed text.
+    Set(Setting, String),
+    /// Move the setting that many choices along.
+    Cycle(Setting, isize),
+}
+
+
+/// A list of settings, applied as soon as they are changed.
+pub struct SettingsPanel {
+    selected: usize,
+    editing: Option<Prompt>,
+}
+
+
+impl SettingsPanel {
+    pub fn new() -> SettingsPanel {
+        SettingsPanel { selected: 0, editing: None }
+    }
+
+    pub fn handle_key(&mut self, key: Key) -> SettingsAction {
+        let setting = SETTINGS[self.selected];
+        if let Some(prompt) = &mut self.editing {
+            return match prompt.handle_key(key) {
+                PromptAction::None => SettingsAction::None,
+                PromptAction::Cancel => {
+                    self.editing = None;
+                    SettingsAction::None
+                }
+                PromptAction::Submit(text) => {
+                    self.editing = None;
+                    SettingsAction::Set(setting, text)
+                }
+            };
+        }
+        match key {
+            Key::Esc | Key::Char('e') | Key::Char('q') => SettingsAction::Close,
+            Key::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                SettingsAction::None
+            }
+            Key::Down => {
+                self.selected = (self.selected + 1).min(SETTINGS.len() - 1);
+                SettingsAction::None
+            }
+            Key::Left if setting.is_choice() => SettingsAction::Cycle(setting, -1),
+            Key::Right | Key::Char('\n') if setting.is_choice() => SettingsAction::Cycle(setting, 1),
+            Key::Char('\n') => {
+                self.editing = Some(Prompt::new(&format!("{}:", setting.label())));
+                SettingsAction::None
+            }
+            _ => SettingsAction::None,
+        }
+    }
+
+    pub fn render(&self, stdout: &mut dyn Write, game: &Universe, tick_millis: u64, density: f64) {
+        write!(stdout, "Settings:\n\r").unwrap();
+        for (i, &setting) in SETTINGS.iter().enumerate() {
+            let value = match setting {
+                Setting::TickMillis => tick_millis.to_string(),
+                Setting::Rule => game.rule.to_string(),
+                Setting::Topology => match game.topology {
+                    Topology::Bounded => "dead beyond the edges",
+                    Topology::Torus => "wrap around (torus)",
+                    Topology::Unbounded => "unbounded plane",
+                }.to_string(),
+                Setting::Width => game.width.to_string(),
+                Setting::Height => game.height.to_string(),
+                Setting::Glyphs => format!("{} ({}{})", game.glyphs.name, game.glyphs.alive, game.glyphs.dead),
+                Setting::Render => game.render.name().to_string(),
+                Setting::Coloring => game.coloring.name().to_string(),
+                Setting::TrailLen => game.trail_len.to_string(),
+                Setting::Theme => game.theme.name.clone(),
+                Setting::Density => density.to_string(),
+                Setting::Symmetry => game.symmetry.name().to_string(),
+                Setting::EdgePreview => if game.edge_preview {"on"} else {"off"}.to_string(),
+            };
+            let marker = if i == self.selected {">"} else {" "};
+            write!(stdout, "{} {:<20} {}\n\r", marker, setting.label(), value).unwrap();
+        }
+        match &self.editing {
+            Some(prompt) => prompt.render(stdout),
+            None if SETTINGS[self.selected].is_choice() => {
+                write!(stdout, "[Up/Down] select  [Left/Right] change  [Esc] close\n\r").unwrap();
+            }
+            None => write!(stdout, "[Up/Down] select  [Enter] change  [Esc] close\n\r").unwrap(),
+        }
+    }
+}
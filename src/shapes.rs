@@ -0,0 +1,109 @@
+/// A cell of a shape by row and column, which may lie off the board.
+pub type Cell = (isize, isize);
+
+
+/// What is drawn between two anchor points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    Line,
+    /// The outline of the rectangle with the anchors at opposite corners.
+    Rectangle,
+    /// The rectangle filled in.
+    Box,
+    /// The circle around the first anchor through the second.
+    Circle,
+}
+
+
+impl Shape {
+    pub fn name(self) -> &'static str {
+        match self {
+            Shape::Line => "line",
+            Shape::Rectangle => "rectangle",
+            Shape::Box => "filled rectangle",
+            Shape::Circle => "circle",
+        }
+    }
+
+    /// The shape `by` places along line, rectangle, filled rectangle and
+    /// circle.
+    pub fn cycle(self, by: isize) -> Shape {
+        let shapes = [Shape::Line, Shape::Rectangle, Shape::Box, Shape::Circle];
+        let at = shapes.iter().position(|&s| s == self).unwrap() as isize;
+        shapes[(at + by).rem_euclid(shapes.len() as isize) as usize]
+    }
+
+    /// The cells of the shape between `from` and `to`, each once.
+    pub fn cells(self, from: Cell, to: Cell) -> Vec<Cell> {
+        let mut cells = match self {
+            Shape::Line => line(from, to),
+            Shape::Rectangle => {
+                let (top, bottom) = (from.0.min(to.0), from.0.max(to.0));
+                let (left, right) = (from.1.min(to.1), from.1.max(to.1));
+                let mut cells = line((top, left), (top, right));
+                cells.extend(line((bottom, left), (bottom, right)));
+                cells.extend(line((top, left), (bottom, left)));
+                cells.extend(line((top, right), (bottom, right)));
+                cells
+            }
+            Shape::Box => {
+                let (top, bottom) = (from.0.min(to.0), from.0.max(to.0));
+                let (left, right) = (from.1.min(to.1), from.1.max(to.1));
+                (top..=bottom).flat_map(|row| (left..=right).map(move |col| (row, col))).collect()
+            }
+            Shape::Circle => {
+                let (rows, cols) = ((to.0 - from.0) as f64, (to.1 - from.1) as f64);
+                circle(from, (rows * rows + cols * cols).sqrt().round() as isize)
+            }
+        };
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+    }
+}
+
+
+/// The cells of the straight line from `from` to `to`, both ends
+/// included, without gaps (Bresenham's).
+fn line(from: Cell, to: Cell) -> Vec<Cell> {
+    let (rows, cols) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (down, right) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let mut error = rows + cols;
+    let mut at = from;
+    let mut cells = vec![at];
+    while at != to {
+        let twice = 2 * error;
+        if twice >= cols {
+            error += cols;
+            at.0 += down;
+        }
+        if twice <= rows {
+            error += rows;
+            at.1 += right;
+        }
+        cells.push(at);
+    }
+    cells
+}
+
+
+/// The cells of the circle around `center` with `radius`, drawn an eighth
+/// at a time and mirrored (the midpoint algorithm).
+fn circle(center: Cell, radius: isize) -> Vec<Cell> {
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (radius, 0);
+    let mut error = 1 - radius;
+    while x >= y {
+        for (rows, cols) in [(y, x), (x, y), (x, -y), (y, -x), (-y, -x), (-x, -y), (-x, y), (-y, x)] {
+            cells.push((center.0 + rows, center.1 + cols));
+        }
+        y += 1;
+        if error < 0 {
+            error += 2 * y + 1;
+        } else {
+            x -= 1;
+            error += 2 * (y - x) + 1;
+        }
+    }
+    cells
+}
@@ -0,0 +1,195 @@
+use crate::rule::Rule;
+use crate::Universe;
+
+
+const PREFIX: &str = "life:";
+const VERSION: u8 = 1;
+/// Adds the cell notes between the rule and the cells.
+const VERSION_NOTES: u8 = 2;
+/// The most cells a decoded board may have, so a hostile code cannot ask
+/// for more memory than any real board needs.
+const CELLS_MAX: usize = 1 << 26;
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+
+/// Encodes the board size, rule, notes and cells as a `life:` link that
+/// fits in a chat message. Cells are stored as alternating runs of dead
+/// and live cells, so sparse boards stay short.
+pub fn encode(universe: &Universe) -> String {
+    let version = if universe.notes.is_empty() {VERSION} else {VERSION_NOTES};
+    let mut bytes = vec![version];
+    push_varint(&mut bytes, universe.width);
+    push_varint(&mut bytes, universe.height);
+    let rule = universe.rule.to_string();
+    push_varint(&mut bytes, rule.len());
+    bytes.extend(rule.as_bytes());
+    if version == VERSION_NOTES {
+        push_varint(&mut bytes, universe.notes.len());
+        for (&(row, col), text) in &universe.notes {
+            push_varint(&mut bytes, row);
+            push_varint(&mut bytes, col);
+            push_varint(&mut bytes, text.len());
+            bytes.extend(text.as_bytes());
+        }
+    }
+
+    let mut alive = false;
+    let mut run = 0;
+    for &cell in &universe.cells {
+        if cell != alive {
+            push_varint(&mut bytes, run);
+            alive = cell;
+            run = 0;
+        }
+        run += 1;
+    }
+    push_varint(&mut bytes, run);
+
+    format!("{}{}", PREFIX, to_base64(&bytes))
+}
+
+
+/// Creates a universe from a string made by `encode`. The `life:` prefix
+/// is optional.
+pub fn decode(text: &str) -> Result<Universe, String> {
+    let text = text.trim();
+    let text = text.strip_prefix(PREFIX).unwrap_or(text);
+    let bytes = from_base64(text)?;
+    let mut pos = 0;
+
+    let version = *bytes.first().ok_or("board code is empty")?;
+    if version != VERSION && version != VERSION_NOTES {
+        return Err("unsupported board code version".to_string());
+    }
+    pos += 1;
+    let width = read_varint(&bytes, &mut pos)?;
+    let height = read_varint(&bytes, &mut pos)?;
+    if width == 0 || height == 0 {
+        return Err("board code has an empty board".to_string());
+    }
+    if width.checked_mul(height).is_none_or(|cells| cells > CELLS_MAX) {
+        return Err(format!("board code has a {}x{} board, more than {} cells", width, height, CELLS_MAX));
+    }
+    let rule_len = read_varint(&bytes, &mut pos)?;
+    let rule = read_bytes(&bytes, &mut pos, rule_len)?;
+    let rule = Rule::parse(&String::from_utf8_lossy(rule))?;
+
+    let mut universe = Universe::new(width, height);
+    universe.rule = rule;
+    if version == VERSION_NOTES {
+        for _ in 0..read_varint(&bytes, &mut pos)? {
+            let row = read_varint(&bytes, &mut pos)?;
+            let col = read_varint(&bytes, &mut pos)?;
+            let len = read_varint(&bytes, &mut pos)?;
+            let text = read_bytes(&bytes, &mut pos, len)?;
+            if row >= height || col >= width {
+                return Err("board code has a note outside the board".to_string());
+            }
+            universe.set_note(row, col, &String::from_utf8_lossy(text));
+        }
+    }
+    let mut idx = 0;
+    let mut alive = false;
+    while pos < bytes.len() {
+        let run = read_varint(&bytes, &mut pos)?;
+        if run > universe.cells.len() - idx {
+            return Err("board code has more cells than the board".to_string());
+        }
+        if alive {
+            universe.cells[idx..idx + run].iter_mut().for_each(|c| *c = true);
+        }
+        idx += run;
+        alive = !alive;
+    }
+    universe.count_population();
+
+    Ok(universe)
+}
+
+
+fn push_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<usize, String> {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes.get(*pos).ok_or("board code is truncated")?;
+        *pos += 1;
+        if shift >= usize::BITS {return Err("board code has an invalid number".to_string());}
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {return Ok(value);}
+        shift += 7;
+    }
+}
+
+
+fn read_bytes<'a>(bytes: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8], String> {
+    let end = pos.checked_add(len).ok_or("board code is truncated")?;
+    let read = bytes.get(*pos..end).ok_or("board code is truncated")?;
+    *pos = end;
+    Ok(read)
+}
+
+
+/// URL-safe base64 without padding.
+fn to_base64(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+
+fn from_base64(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in text.trim_end_matches('=').bytes() {
+        let value = BASE64.iter().position(|&b| b == c)
+            .ok_or(format!("invalid character `{}` in board code", c as char))?;
+        acc = acc << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Ok(bytes)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut universe = Universe::new(5, 4);
+        universe.set_cells(&[(0, 1), (2, 3), (3, 4)]);
+        let decoded = decode(&encode(&universe)).unwrap();
+        assert_eq!((decoded.width, decoded.height), (5, 4));
+        assert_eq!(decoded.cells, universe.cells);
+    }
+
+    #[test]
+    fn rejects_hostile_codes() {
+        // A size whose cell count overflows.
+        assert!(decode("life:AYCAgICAIICAgICAIAZCMy9TMjMA").is_err());
+        // A rule length that overflows the position.
+        assert!(decode("life:AQMD____________AQ").is_err());
+        // An empty board.
+        assert!(decode("life:AQAABkIzL1MyMwA").is_err());
+    }
+}
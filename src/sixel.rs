@@ -0,0 +1,52 @@
+use std::fmt::Write;
+
+use crate::render::Picture;
+
+
+/// Encodes `picture` as a sixel sequence. Pixels are square and every one
+/// is drawn, so the image fully covers one drawn before it.
+pub fn encode(picture: &Picture) -> String {
+    let Picture { pixels, width, height, palette } = picture;
+    let (width, height) = (*width, *height);
+    let mut out = format!("\x1bPq\"1;1;{};{}", width, height);
+    for (i, &(r, g, b)) in palette.iter().enumerate() {
+        let percent = |c: u8| c as usize * 100 / 255;
+        write!(out, "#{};2;{};{};{}", i, percent(r), percent(g), percent(b)).unwrap();
+    }
+    // Each sixel character is a column of six pixels, the top one in the
+    // lowest bit.
+    for band in (0..height).step_by(6) {
+        let rows = band..(band + 6).min(height);
+        let mut used = vec![false; palette.len()];
+        for row in rows.clone() {
+            for &color in &pixels[row * width..(row + 1) * width] {used[color as usize] = true;}
+        }
+        let mut first = true;
+        for color in (0..palette.len()).filter(|&c| used[c]) {
+            // Back to the start of the band for every colour after the first.
+            if !first {out.push('$');}
+            first = false;
+            write!(out, "#{}", color).unwrap();
+            let column = |col: usize| {
+                rows.clone().enumerate()
+                    .filter(|&(_, row)| pixels[row * width + col] as usize == color)
+                    .fold(0, |bits, (bit, _)| bits | 1 << bit)
+            };
+            let mut col = 0;
+            while col < width {
+                let bits = column(col);
+                let run = (col..width).take_while(|&c| column(c) == bits).count();
+                let ch = char::from(63 + bits as u8);
+                if run > 3 {
+                    write!(out, "!{}{}", run, ch).unwrap();
+                } else {
+                    for _ in 0..run {out.push(ch);}
+                }
+                col += run;
+            }
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
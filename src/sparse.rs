@@ -0,0 +1,29 @@
+use std::collections::{HashMap, HashSet};
+
+
+/// A cell of an unbounded plane, by row and column.
+pub type Cell = (isize, isize);
+
+
+/// The next generation of the live cells of an unbounded plane. Only cells
+/// next to a live one can change, so a step costs the population rather
+/// than the area; rules with B0 therefore only give birth next to live
+/// cells. `also_counted` are cells counted as live neighbours that never
+/// change, and `next` decides a cell from whether it is alive and its
+/// live neighbours.
+pub fn step(live: &HashSet<Cell>,
+            also_counted: impl Iterator<Item = Cell>,
+            next: impl Fn(Cell, bool, usize) -> bool) -> HashSet<Cell> {
+    let mut counts: HashMap<Cell, usize> = live.iter().map(|&cell| (cell, 0)).collect();
+    for (row, col) in live.iter().copied().chain(also_counted) {
+        for r in row - 1..=row + 1 {
+            for c in col - 1..=col + 1 {
+                if (r, c) != (row, col) {*counts.entry((r, c)).or_insert(0) += 1;}
+            }
+        }
+    }
+    counts.into_iter()
+        .filter(|&(cell, count)| next(cell, live.contains(&cell), count))
+        .map(|(cell, _)| cell)
+        .collect()
+}
@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+
+use crate::glyphs::{Borders, Glyphs};
+use crate::json::{self, Json};
+use crate::render::RenderMode;
+use crate::rule::{Region, Rule};
+use crate::{Coloring, Engine, Topology, Universe, PAINT_COLORS};
+
+
+const FORMAT: &str = "game-of-life state";
+const VERSION: usize = 1;
+/// How many generations of history are written at most.
+const SAVED_HISTORY_MAX: usize = 100;
+
+
+/// A board saved with its history and settings, to carry on later.
+pub struct SavedState {
+    pub game: Universe,
+    pub tick_millis: Option<u64>,
+    /// The name of the theme, looked up among those the session knows.
+    pub theme: Option<String>,
+}
+
+
+/// Saves everything needed to pick the game up again as JSON: size,
+/// generation, rule and other settings, regions, notes, the cells with
+/// their paint and walls, live cells beyond an unbounded board, and the
+/// latest `SAVED_HISTORY_MAX` generations of history. Boards are written as one
+/// string per row, `O` for a live or wall cell and `.` otherwise; paint
+/// colours are palette indices.
+///
+/// Like checkpoints the file is written next to `path` and renamed over it.
+pub fn save(path: &str, game: &Universe, tick_millis: u64) -> io::Result<()> {
+    let partial = format!("{}.partial", path);
+    fs::write(&partial, to_json(game, tick_millis))?;
+    fs::rename(&partial, path)
+}
+
+
+/// The state `save` writes.
+pub fn to_json(game: &Universe, tick_millis: u64) -> String {
+    let mut fields = vec![
+        ("format", json::string(FORMAT)),
+        ("version", VERSION.to_string()),
+        ("width", game.width.to_string()),
+        ("height", game.height.to_string()),
+        ("generation", game.generation.to_string()),
+        ("rule", json::string(&game.rule.to_string())),
+        ("topology", json::string(game.topology.name())),
+        ("engine", json::string(game.engine.name())),
+        ("glyphs", json::string(&game.glyphs.name)),
+        ("borders", json::string(&(game.borders.spec() + &game.pane_borders.spec()))),
+        ("render", json::string(game.render.name())),
+        ("coloring", json::string(game.coloring.name())),
+        ("theme", json::string(&game.theme.name)),
+        ("trail_len", game.trail_len.to_string()),
+        ("walls_alive", game.walls_alive.to_string()),
+        ("tick_millis", tick_millis.to_string()),
+        ("cursor", format!("{{\"row\": {}, \"col\": {}}}", game.selected_cell.0, game.selected_cell.1)),
+    ];
+    let regions: Vec<String> = game.regions.iter()
+        .map(|r| json::string(&format!("{},{},{},{}:{}", r.top, r.left, r.bottom, r.right, r.rule)))
+        .collect();
+    fields.push(("regions", format!("[{}]", regions.join(", "))));
+    let notes: Vec<String> = game.notes.iter()
+        .map(|(&(row, col), text)| format!("{{\"row\": {}, \"col\": {}, \"text\": {}}}", row, col, json::string(text)))
+        .collect();
+    fields.push(("notes", format!("[{}]", notes.join(", "))));
+    fields.push(("cells", board_rows(game, &game.cells, "    ")));
+    if game.colors.iter().any(Option::is_some) {
+        let rows = (0..game.height).map(|row| {
+            let colors = &game.colors[row * game.width..(row + 1) * game.width];
+            json::string(&colors.iter().map(|c| c.map_or('.', |c| (b'0' + c) as char)).collect::<String>())
+        });
+        fields.push(("colors", format!("[\n    {}\n  ]", rows.collect::<Vec<_>>().join(",\n    "))));
+    }
+    fields.push(("walls", board_rows(game, &game.walls, "    ")));
+    if game.topology == Topology::Unbounded {
+        fields.push(("origin", format!("{{\"row\": {}, \"col\": {}}}", game.origin.0, game.origin.1)));
+        let mut beyond: Vec<_> = game.beyond.iter().collect();
+        beyond.sort();
+        let cells: Vec<String> = beyond.iter().map(|(row, col)| format!("[{}, {}]", row, col)).collect();
+        fields.push(("beyond", format!("[{}]", cells.join(", "))));
+    }
+    if let Some(len) = game.history.len_max {fields.push(("history_len", len.to_string()));}
+    let history: Vec<String> = game.history.boards(&game.cells, SAVED_HISTORY_MAX).iter()
+        .map(|cells| board_rows(game, cells, "      "))
+        .collect();
+    fields.push(("history", format!("[\n    {}\n  ]", history.join(",\n    "))));
+
+    let body: Vec<String> = fields.iter().map(|(key, value)| format!("  {}: {}", json::string(key), value)).collect();
+    format!("{{\n{}\n}}\n", body.join(",\n"))
+}
+
+
+fn board_rows(game: &Universe, cells: &[bool], indent: &str) -> String {
+    let rows: Vec<String> = cells.chunks(game.width)
+        .map(|row| json::string(&row.iter().map(|&alive| if alive {'O'} else {'.'}).collect::<String>()))
+        .collect();
+    format!("[\n{}{}\n{}]", indent, rows.join(&format!(",\n{}", indent)), &indent[2..])
+}
+
+
+pub fn load(path: &str) -> Result<SavedState, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let doc = Json::parse(&text).map_err(|d| d.in_file(path))?;
+    let invalid = |message: String| format!("{}: {}", path, message);
+    if doc.get("format").and_then(Json::as_str) != Some(FORMAT) {
+        return Err(invalid(format!("not a saved state, expected \"format\": \"{}\"", FORMAT)));
+    }
+    if doc.get("version").and_then(Json::as_usize) != Some(VERSION) {
+        return Err(invalid(format!("unsupported version, expected {}", VERSION)));
+    }
+
+    let number = |key: &str| doc.get(key).map(|v| v.as_usize().ok_or(invalid(format!("`{}` must be a whole number", key))));
+    let text_field = |key: &str| doc.get(key).map(|v| v.as_str().ok_or(invalid(format!("`{}` must be a string", key))));
+    let (width, height) = match (number("width").transpose()?, number("height").transpose()?) {
+        (Some(w), Some(h)) if w > 0 && h > 0 => (w, h),
+        _ => return Err(invalid("`width` and `height` must be set and above 0".to_string())),
+    };
+    let board = |value: &Json, what: &str| -> Result<Vec<char>, String> {
+        let err = || invalid(format!("`{}` must be {} strings of {} characters", what, height, width));
+        let rows = value.as_array().filter(|rows| rows.len() == height).ok_or_else(err)?;
+        let mut cells = Vec::with_capacity(width * height);
+        for row in rows {
+            let row = row.as_str().filter(|r| r.chars().count() == width).ok_or_else(err)?;
+            cells.extend(row.chars());
+        }
+        Ok(cells)
+    };
+    let alive = |cells: Vec<char>| cells.into_iter().map(|c| c != '.').collect::<Vec<bool>>();
+
+    let mut game = Universe::new(width, height);
+    game.cells = alive(board(doc.get("cells").ok_or(invalid("`cells` is missing".to_string()))?, "cells")?);
+    if let Some(walls) = doc.get("walls") {game.walls = alive(board(walls, "walls")?);}
+    if let Some(colors) = doc.get("colors") {
+        let colors = board(colors, "colors")?.into_iter().map(|c| match c.to_digit(10) {
+            Some(d) if (d as usize) < PAINT_COLORS.len() => Ok(Some(d as u8)),
+            None if c == '.' => Ok(None),
+            _ => Err(invalid(format!("unknown paint colour `{}`", c))),
+        });
+        game.colors = colors.collect::<Result<_, _>>()?;
+    }
+    // Paint only sticks to live cells, walls are never alive.
+    for i in 0..width * height {
+        if game.walls[i] {game.cells[i] = false;}
+        if !game.cells[i] {game.colors[i] = None;}
+    }
+    game.count_population();
+
+    if let Some(generation) = number("generation").transpose()? {game.generation = generation as u64;}
+    if let Some(rule) = text_field("rule").transpose()? {game.rule = Rule::parse(rule).map_err(invalid)?;}
+    if let Some(topology) = text_field("topology").transpose()? {game.topology = Topology::parse(topology).map_err(invalid)?;}
+    if let Some(engine) = text_field("engine").transpose()? {game.engine = Engine::parse(engine).map_err(invalid)?;}
+    if let Some(glyphs) = text_field("glyphs").transpose()? {game.glyphs = Glyphs::parse(glyphs).map_err(invalid)?;}
+    if let Some(borders) = text_field("borders").transpose()? {
+        (game.borders, game.pane_borders) = Borders::parse(borders).map_err(invalid)?;
+    }
+    if let Some(render) = text_field("render").transpose()? {game.render = RenderMode::parse(render).map_err(invalid)?;}
+    if let Some(coloring) = text_field("coloring").transpose()? {game.coloring = Coloring::parse(coloring).map_err(invalid)?;}
+    if let Some(walls_alive) = doc.get("walls_alive") {
+        game.walls_alive = walls_alive.as_bool().ok_or(invalid("`walls_alive` must be true or false".to_string()))?;
+    }
+    if let Some(len) = number("history_len").transpose()? {game.history.len_max = Some(len.max(1));}
+    if let Some(len) = number("trail_len").transpose()? {game.trail_len = u32::try_from(len).unwrap_or(u32::MAX);}
+    if let Some(origin) = doc.get("origin") {
+        let coordinate = |key: &str| match origin.get(key) {
+            Some(Json::Number(n)) if n.fract() == 0.0 => Ok(*n as isize),
+            _ => Err(invalid("`origin` must have a whole `row` and `col`".to_string())),
+        };
+        game.origin = (coordinate("row")?, coordinate("col")?);
+    }
+    for cell in doc.get("beyond").and_then(Json::as_array).unwrap_or_default() {
+        match cell.as_array() {
+            Some([Json::Number(row), Json::Number(col)]) if row.fract() == 0.0 && col.fract() == 0.0
+                && !(*row >= 0.0 && *col >= 0.0 && *row < height as f64 && *col < width as f64) => {
+                game.beyond.insert((*row as isize, *col as isize));
+            }
+            _ => return Err(invalid("`beyond` must be [row, column] pairs off the board".to_string())),
+        }
+    }
+    if let Some(cursor) = doc.get("cursor") {
+        let coordinate = |key: &str| cursor.get(key).and_then(Json::as_usize);
+        match (coordinate("row"), coordinate("col")) {
+            (Some(row), Some(col)) if row < height && col < width => {game.selected_cell = (row, col);}
+            _ => return Err(invalid("`cursor` must have a `row` and `col` on the board".to_string())),
+        }
+    }
+    for region in doc.get("regions").and_then(Json::as_array).unwrap_or_default() {
+        let text = region.as_str().ok_or(invalid("`regions` must be strings".to_string()))?;
+        game.add_region(Region::parse(text).map_err(invalid)?);
+    }
+    for note in doc.get("notes").and_then(Json::as_array).unwrap_or_default() {
+        let field = |key: &str| note.get(key).and_then(Json::as_usize);
+        match (field("row"), field("col"), note.get("text").and_then(Json::as_str)) {
+            (Some(row), Some(col), Some(text)) if row < height && col < width => {
+                game.notes.insert((row, col), text.to_string());
+            }
+            _ => return Err(invalid("every note needs a `row` and `col` on the board and a `text`".to_string())),
+        }
+    }
+    let history = doc.get("history").and_then(Json::as_array).unwrap_or_default();
+    let mut boards = history.iter()
+        .map(|cells| board(cells, "history").map(alive))
+        .collect::<Result<Vec<_>, _>>()?;
+    boards.push(game.cells.clone());
+    // Paint is not kept for past generations.
+    let unpainted = vec![None; width * height];
+    for pair in boards.windows(2) {
+        game.history.push(pair[0].clone(), &pair[1], &unpainted, &unpainted, HashSet::new());
+    }
+
+    let tick_millis = number("tick_millis").transpose()?.map(|millis| millis.max(1) as u64);
+    let theme = text_field("theme").transpose()?.map(str::to_string);
+    Ok(SavedState { game, tick_millis, theme })
+}
@@ -0,0 +1,91 @@
+/// Which cells a cell toggled by hand is mirrored to, so the board stays
+/// symmetric while editing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Symmetry {
+    None,
+    /// Left and right mirror each other.
+    Horizontal,
+    /// Top and bottom mirror each other.
+    Vertical,
+    /// Mirrored both left to right and upside down.
+    Both,
+    /// The same after half a turn about the middle of the board.
+    HalfTurn,
+    /// The same after a quarter turn about the middle of the board.
+    FourFold,
+    /// Mirrored across the diagonal from the top left corner.
+    Diagonal,
+}
+
+
+const SYMMETRIES: [Symmetry; 7] = [
+    Symmetry::None,
+    Symmetry::Horizontal,
+    Symmetry::Vertical,
+    Symmetry::Both,
+    Symmetry::HalfTurn,
+    Symmetry::FourFold,
+    Symmetry::Diagonal,
+];
+
+
+impl Symmetry {
+    pub fn parse(name: &str) -> Result<Symmetry, String> {
+        SYMMETRIES.iter().copied().find(|symmetry| symmetry.name() == name).ok_or_else(|| {
+            let names: Vec<&str> = SYMMETRIES.iter().map(|symmetry| symmetry.name()).collect();
+            format!("unknown symmetry `{}`, expected {}", name, names.join(", "))
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Symmetry::None => "none",
+            Symmetry::Horizontal => "horizontal",
+            Symmetry::Vertical => "vertical",
+            Symmetry::Both => "both",
+            Symmetry::HalfTurn => "half-turn",
+            Symmetry::FourFold => "4-fold",
+            Symmetry::Diagonal => "diagonal",
+        }
+    }
+
+    /// The symmetry `by` places further along `SYMMETRIES`, wrapping around.
+    pub fn cycle(self, by: isize) -> Symmetry {
+        let at = SYMMETRIES.iter().position(|&symmetry| symmetry == self).unwrap() as isize;
+        SYMMETRIES[(at + by).rem_euclid(SYMMETRIES.len() as isize) as usize]
+    }
+
+    /// The cells that mirror the one at `row`, `col` on a board `width` by
+    /// `height`, itself included and each once. Images that fall off the
+    /// board, or between cells on quarter turns, are left out.
+    pub fn images(self, row: usize, col: usize, width: usize, height: usize) -> Vec<(usize, usize)> {
+        let (flip_row, flip_col) = (height - 1 - row, width - 1 - col);
+        let mut images = match self {
+            Symmetry::None => vec![(row, col)],
+            Symmetry::Horizontal => vec![(row, col), (row, flip_col)],
+            Symmetry::Vertical => vec![(row, col), (flip_row, col)],
+            Symmetry::Both => vec![(row, col), (row, flip_col), (flip_row, col), (flip_row, flip_col)],
+            Symmetry::HalfTurn => vec![(row, col), (flip_row, flip_col)],
+            Symmetry::FourFold => {
+                // Twice the offsets from the middle, to stay whole on boards
+                // with an even side.
+                let (rows, cols) = (2 * row as isize - (height as isize - 1), 2 * col as isize - (width as isize - 1));
+                let mut images = Vec::new();
+                for (rows, cols) in [(rows, cols), (cols, -rows), (-rows, -cols), (-cols, rows)] {
+                    let (row, col) = (rows + height as isize - 1, cols + width as isize - 1);
+                    if row % 2 != 0 || col % 2 != 0 {continue;}
+                    let (row, col) = (row / 2, col / 2);
+                    if (0..height as isize).contains(&row) && (0..width as isize).contains(&col) {
+                        images.push((row as usize, col as usize));
+                    }
+                }
+                images
+            }
+            Symmetry::Diagonal if col < height && row < width => vec![(row, col), (col, row)],
+            Symmetry::Diagonal => vec![(row, col)],
+        };
+        images.sort_unstable();
+        images.dedup();
+        images
+    }
+}
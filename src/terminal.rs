@@ -0,0 +1,92 @@
+use std::fs;
+use std::io::{self, Write};
+use std::mem::MaybeUninit;
+use std::os::fd::AsRawFd;
+use std::panic;
+use std::process;
+use std::thread;
+
+use termion::screen::ToMainScreen;
+
+
+/// Mouse reporting modes `MouseTerminal` turns on.
+const MOUSE_OFF: &str = "\x1b[?1006l\x1b[?1015l\x1b[?1002l\x1b[?1000l";
+/// How long to wait for the terminal to answer a query.
+const ANSWER_TIMEOUT_MILLIS: i32 = 500;
+
+
+/// Makes a panic put the terminal back the way it was found before the
+/// panic message is printed: cooked mode, mouse reporting off, the cursor
+/// shown and the user's screen back. Must be called before raw mode is
+/// entered, as the terminal settings at the time are the ones restored.
+///
+/// Only panics of the main thread, which draws the game, are fatal; the
+/// process exits right after the message so the screen is not switched
+/// again while unwinding. Other threads panic as before.
+pub fn restore_on_panic() {
+    let mut original = MaybeUninit::<libc::termios>::uninit();
+    // Not a terminal, nothing to restore.
+    if unsafe {libc::tcgetattr(libc::STDOUT_FILENO, original.as_mut_ptr())} != 0 {return;}
+    let original = unsafe {original.assume_init()};
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().name() != Some("main") {
+            default_hook(info);
+            return;
+        }
+        unsafe {libc::tcsetattr(libc::STDOUT_FILENO, libc::TCSANOW, &original);}
+        let mut stdout = io::stdout();
+        let _ = write!(stdout, "{}{}{}", MOUSE_OFF, termion::cursor::Show, ToMainScreen);
+        let _ = stdout.flush();
+        default_hook(info);
+        process::exit(101);
+    }));
+}
+
+
+/// Sends `questions` to the terminal and returns the answers to them and
+/// then to a query for its primary device attributes, which every
+/// terminal answers after those before it; nothing when it does not
+/// answer in time. Reads standard input itself, so must be called in raw
+/// mode before anything else reads from it.
+pub fn ask(out: &mut dyn Write, questions: &str) -> String {
+    if write!(out, "{}\x1b[c", questions).and_then(|_| out.flush()).is_err() {return String::new();}
+    // The last answer looks like ESC [ ? 62 ; 4 ; 22 c.
+    let mut answer = Vec::new();
+    let mut stdin = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+    while answer.last() != Some(&b'c') || !answer.windows(3).any(|w| w == b"\x1b[?") {
+        if unsafe {libc::poll(&mut stdin, 1, ANSWER_TIMEOUT_MILLIS)} <= 0 {return String::new();}
+        let mut byte = 0u8;
+        if unsafe {libc::read(libc::STDIN_FILENO, (&mut byte as *mut u8).cast(), 1)} != 1 {return String::new();}
+        answer.push(byte);
+    }
+    String::from_utf8_lossy(&answer).into_owned()
+}
+
+
+/// The primary device attributes in the answers `ask` got, e.g. 4 when
+/// the terminal shows sixel graphics.
+pub fn device_attributes(answer: &str) -> Vec<u32> {
+    let Some(start) = answer.rfind("\x1b[?") else {return Vec::new()};
+    answer[start + 3..answer.len() - 1].split(';').filter_map(|n| n.parse().ok()).collect()
+}
+
+
+/// The size of a character on the terminal in pixels, as width and
+/// height, if the terminal tells.
+pub fn char_pixels() -> Option<(usize, usize)> {
+    let mut size = MaybeUninit::<libc::winsize>::uninit();
+    if unsafe {libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, size.as_mut_ptr())} != 0 {return None;}
+    let size = unsafe {size.assume_init()};
+    if size.ws_xpixel == 0 || size.ws_ypixel == 0 || size.ws_col == 0 || size.ws_row == 0 {return None;}
+    Some(((size.ws_xpixel / size.ws_col) as usize, (size.ws_ypixel / size.ws_row) as usize))
+}
+
+
+/// Points standard input at the controlling terminal, for keys to be read
+/// from after a pattern was piped in.
+pub fn reopen_stdin() -> io::Result<()> {
+    let tty = fs::File::open("/dev/tty")?;
+    if unsafe {libc::dup2(tty.as_raw_fd(), libc::STDIN_FILENO)} < 0 {return Err(io::Error::last_os_error());}
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::glyphs::{Borders, Glyphs};
+use crate::json::Json;
+use crate::toml;
+
+
+pub type Rgb = (u8, u8, u8);
+
+
+/// The colours, and optionally glyphs, the board and the lines around it
+/// are drawn with. Colours left out are the terminal's own.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub alive: Option<Rgb>,
+    pub dead: Option<Rgb>,
+    pub cursor: Option<Rgb>,
+    pub wall: Option<Rgb>,
+    pub border: Option<Rgb>,
+    pub status: Option<Rgb>,
+    /// The glyph set switched to along with the theme, if any.
+    pub glyphs: Option<Glyphs>,
+    /// The borders of the focused pane and the other one, if any.
+    pub borders: Option<(Borders, Borders)>,
+}
+
+
+impl Theme {
+    fn named(name: &str) -> Theme {
+        Theme { name: name.to_string(), alive: None, dead: None, cursor: None, wall: None, border: None, status: None, glyphs: None, borders: None }
+    }
+
+    /// The theme with `name` among `themes`.
+    pub fn find(themes: &[Theme], name: &str) -> Result<Theme, String> {
+        themes.iter().find(|theme| theme.name == name).cloned().ok_or_else(|| {
+            let names: Vec<&str> = themes.iter().map(|theme| theme.name.as_str()).collect();
+            format!("unknown theme `{}`, expected {}", name, names.join(", "))
+        })
+    }
+
+    /// The theme `by` places further along `themes` than this one,
+    /// wrapping around.
+    pub fn cycle(&self, themes: &[Theme], by: isize) -> Theme {
+        let at = themes.iter().position(|theme| theme.name == self.name).unwrap_or(0) as isize;
+        themes[(at + by).rem_euclid(themes.len() as isize) as usize].clone()
+    }
+}
+
+
+impl Default for Theme {
+    /// The terminal's own colours.
+    fn default() -> Theme {
+        Theme::named("dark")
+    }
+}
+
+
+/// The themes that come with the game, the default first.
+pub fn builtin() -> Vec<Theme> {
+    vec![
+        Theme::default(),
+        Theme {
+            alive: Some((40, 40, 40)),
+            dead: Some((235, 235, 230)),
+            cursor: Some((30, 100, 200)),
+            wall: Some((150, 150, 150)),
+            border: Some((120, 120, 120)),
+            status: Some((60, 60, 60)),
+            ..Theme::named("light")
+        },
+        Theme {
+            alive: Some((181, 137, 0)),
+            dead: Some((7, 54, 66)),
+            cursor: Some((211, 54, 130)),
+            wall: Some((88, 110, 117)),
+            border: Some((88, 110, 117)),
+            status: Some((42, 161, 152)),
+            ..Theme::named("solarized")
+        },
+        Theme {
+            alive: Some((0, 255, 70)),
+            dead: Some((0, 40, 10)),
+            cursor: Some((200, 255, 200)),
+            wall: Some((0, 110, 40)),
+            border: Some((0, 150, 50)),
+            status: Some((0, 200, 60)),
+            glyphs: Some(Glyphs::parse("ascii").unwrap()),
+            ..Theme::named("matrix")
+        },
+    ]
+}
+
+
+/// Where the user's themes are looked for when no file is given.
+pub fn config_path() -> Option<PathBuf> {
+    Some(config::dir()?.join("themes.toml"))
+}
+
+
+/// The themes file to read: `path`, or `config_path` if it exists.
+pub fn path(path: Option<&str>) -> Option<PathBuf> {
+    match path {
+        Some(path) => Some(PathBuf::from(path)),
+        None => config_path().filter(|path| path.exists()),
+    }
+}
+
+
+/// The built-in themes followed by those of the TOML file at `path`, if
+/// any. A theme in the file with the name of a built-in one replaces it. Themes are tables under `themes`:
+///
+/// ```toml
+/// [themes.ocean]
+/// alive = "#3fa7d6"
+/// dead = "#0b1e2d"
+/// glyphs = "dots"
+/// borders = "++++-|"
+/// ```
+///
+/// `glyphs` is a set as `Glyphs::parse` reads it and `borders` as
+/// `Borders::parse` does.
+pub fn load(path: Option<&Path>) -> Result<Vec<Theme>, String> {
+    let mut themes = builtin();
+    let Some(path) = path else {return Ok(themes)};
+    let shown = path.display().to_string();
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", shown, e))?;
+    let doc = toml::parse(&text).map_err(|d| d.in_file(&shown))?;
+    let Some(Json::Object(tables)) = doc.get("themes") else {return Ok(themes)};
+    for (name, table) in tables {
+        let theme = parse_theme(name, table).map_err(|e| format!("{}: theme `{}`: {}", shown, name, e))?;
+        match themes.iter().position(|t| t.name == theme.name) {
+            Some(at) => themes[at] = theme,
+            None => themes.push(theme),
+        }
+    }
+    Ok(themes)
+}
+
+
+fn parse_theme(name: &str, table: &Json) -> Result<Theme, String> {
+    let Json::Object(fields) = table else {return Err("expected a table".to_string())};
+    let mut theme = Theme::named(name);
+    for (key, value) in fields {
+        let text = value.as_str().ok_or(format!("`{}` must be a string", key))?;
+        let slot = match key.as_str() {
+            "alive" => &mut theme.alive,
+            "dead" => &mut theme.dead,
+            "cursor" => &mut theme.cursor,
+            "wall" => &mut theme.wall,
+            "border" => &mut theme.border,
+            "status" => &mut theme.status,
+            "glyphs" => {
+                theme.glyphs = Some(Glyphs::parse(text)?);
+                continue;
+            }
+            "borders" => {
+                theme.borders = Some(Borders::parse(text)?);
+                continue;
+            }
+            _ => return Err(format!("unknown key `{}`", key)),
+        };
+        *slot = Some(parse_color(text).ok_or(format!("`{}` must be a colour like \"#3fa7d6\"", key))?);
+    }
+    Ok(theme)
+}
+
+
+/// A colour written as `#rrggbb`.
+fn parse_color(text: &str) -> Option<Rgb> {
+    let hex = text.strip_prefix('#').filter(|hex| hex.len() == 6 && hex.is_ascii())?;
+    let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
@@ -0,0 +1,57 @@
+use std::time::{Duration, Instant};
+
+
+/// Wall-clock time the simulation has spent running, with an optional
+/// limit after which it should pause, for timeboxed unattended runs.
+pub struct RunTimer {
+    elapsed: Duration,
+    checked: Instant,
+    /// Running time at which to pause.
+    limit: Option<Duration>,
+}
+
+
+impl RunTimer {
+    /// A timer that stops the run after `limit` of running time.
+    pub fn new(limit: Option<Duration>) -> RunTimer {
+        RunTimer { elapsed: Duration::ZERO, checked: Instant::now(), limit }
+    }
+
+    /// Adds the time since the last call if the simulation was running.
+    /// Returns `true` once when the limit is reached; the limit is then
+    /// cleared so the run can be resumed.
+    pub fn update(&mut self, running: bool) -> bool {
+        let now = Instant::now();
+        if running {self.elapsed += now - self.checked;}
+        self.checked = now;
+        if self.limit.is_some_and(|limit| self.elapsed >= limit) {
+            self.limit = None;
+            return true;
+        }
+        false
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn limit(&self) -> Option<Duration> {
+        self.limit
+    }
+
+    /// Pauses after `duration` more running time, or never with `None`.
+    pub fn stop_after(&mut self, duration: Option<Duration>) {
+        self.limit = duration.map(|d| self.elapsed + d);
+    }
+}
+
+
+/// Formats a duration as `m:ss`, or `h:mm:ss` from an hour on.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
@@ -0,0 +1,183 @@
+use crate::diagnostic::Diagnostic;
+use crate::json::Json;
+
+
+/// Parses the part of TOML that configuration files need into nested
+/// JSON objects: `[table]` and `[table.sub]` headers, `key = value` lines
+/// with bare, quoted or dotted keys, and strings, numbers, booleans and
+/// one-line arrays of them as values. Comments start with `#`.
+pub fn parse(text: &str) -> Result<Json, Diagnostic> {
+    let mut root = Vec::new();
+    let mut table = Vec::new();
+    for (i, text) in text.lines().enumerate() {
+        let mut line = Line { chars: text.chars().collect(), pos: 0, number: i + 1 };
+        line.skip_blanks();
+        match line.peek() {
+            None | Some('#') => continue,
+            Some('[') => {
+                line.pos += 1;
+                table = line.key()?;
+                line.expect(']')?;
+                line.object_at(&mut root, &table)?;
+            }
+            _ => {
+                let start = line.pos;
+                let mut key = line.key()?;
+                line.expect('=')?;
+                let value = line.value()?;
+                let name = key.pop().unwrap();
+                let object = line.object_at(&mut root, &[table.clone(), key].concat())?;
+                if object.iter().any(|(k, _)| *k == name) {
+                    line.pos = start;
+                    return Err(line.error(format!("`{}` is set twice", name)));
+                }
+                object.push((name, value));
+            }
+        }
+        line.skip_blanks();
+        if line.peek().is_some_and(|c| c != '#') {return Err(line.error("expected the end of the line"));}
+    }
+    Ok(Json::Object(root))
+}
+
+
+struct Line {
+    chars: Vec<char>,
+    pos: usize,
+    number: usize,
+}
+
+
+impl Line {
+    fn error(&self, message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(self.number, self.pos + 1, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_blanks(&mut self) {
+        while self.peek().is_some_and(|c| c == ' ' || c == '\t') {self.pos += 1;}
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Diagnostic> {
+        self.skip_blanks();
+        if self.peek() != Some(c) {return Err(self.error(format!("expected `{}`", c)));}
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// The parts of a key, e.g. `colors."dead cells"`.
+    fn key(&mut self) -> Result<Vec<String>, Diagnostic> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_blanks();
+            let part = match self.peek() {
+                Some('"' | '\'') => self.string()?,
+                _ => {
+                    let start = self.pos;
+                    while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {self.pos += 1;}
+                    if self.pos == start {return Err(self.error("expected a key"));}
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+            parts.push(part);
+            self.skip_blanks();
+            if self.peek() != Some('.') {return Ok(parts);}
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, Diagnostic> {
+        self.skip_blanks();
+        match self.peek() {
+            Some('"' | '\'') => self.string().map(Json::String),
+            Some('[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_blanks();
+                    if self.peek() == Some(']') {break;}
+                    items.push(self.value()?);
+                    self.skip_blanks();
+                    if self.peek() != Some(',') {break;}
+                    self.pos += 1;
+                }
+                self.expect(']')?;
+                Ok(Json::Array(items))
+            }
+            _ => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_alphanumeric() || "+-._".contains(c)) {self.pos += 1;}
+                let word: String = self.chars[start..self.pos].iter().collect();
+                match word.as_str() {
+                    "true" => Ok(Json::Bool(true)),
+                    "false" => Ok(Json::Bool(false)),
+                    _ => word.replace('_', "").parse().map(Json::Number).map_err(|_| {
+                        self.pos = start;
+                        self.error("expected a string, number, boolean or array")
+                    }),
+                }
+            }
+        }
+    }
+
+    /// A basic string in double quotes, with escapes, or a literal one in
+    /// single quotes.
+    fn string(&mut self) -> Result<String, Diagnostic> {
+        let quote = self.peek().unwrap();
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some('\\') if quote == '"' => {
+                    self.pos += 1;
+                    let escaped = match self.peek() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some(c @ ('"' | '\\')) => c,
+                        Some('u') => {
+                            let hex: String = self.chars.iter().skip(self.pos + 1).take(4).collect();
+                            let c = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+                                .ok_or_else(|| self.error("expected four hex digits after `\\u`"))?;
+                            self.pos += 4;
+                            c
+                        }
+                        _ => return Err(self.error("unknown escape")),
+                    };
+                    out.push(escaped);
+                    self.pos += 1;
+                }
+                Some(c) => {
+                    out.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    /// The fields of the table at `path` under `root`, made if missing.
+    fn object_at<'a>(&self, root: &'a mut Vec<(String, Json)>, path: &[String]) -> Result<&'a mut Vec<(String, Json)>, Diagnostic> {
+        let mut fields = root;
+        for part in path {
+            let at = match fields.iter().position(|(k, _)| k == part) {
+                Some(at) => at,
+                None => {
+                    fields.push((part.clone(), Json::Object(Vec::new())));
+                    fields.len() - 1
+                }
+            };
+            fields = match &mut fields[at].1 {
+                Json::Object(inner) => inner,
+                _ => return Err(self.error(format!("`{}` is not a table", part))),
+            };
+        }
+        Ok(fields)
+    }
+}
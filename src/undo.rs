@@ -0,0 +1,65 @@
+use std::collections::VecDeque;
+
+
+/// How many edits can be undone.
+const EDITS_MAX: usize = 100;
+
+
+/// The cells, paint and walls of the board, with the size they are for.
+#[derive(Clone)]
+pub struct Snapshot {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<bool>,
+    pub colors: Vec<Option<u8>>,
+    pub walls: Vec<bool>,
+}
+
+
+/// The boards as they were before the latest edits, and as they were
+/// before those undone, apart from the generations the simulation steps
+/// through.
+pub struct Edits {
+    undo: VecDeque<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+
+impl Edits {
+    pub fn new() -> Edits {
+        Edits { undo: VecDeque::new(), redo: Vec::new() }
+    }
+
+    /// Remembers the board `before` an edit; what was undone can no longer
+    /// be redone.
+    pub fn record(&mut self, before: Snapshot) {
+        if self.undo.len() == EDITS_MAX {self.undo.pop_front();}
+        self.undo.push_back(before);
+        self.redo.clear();
+    }
+
+    /// The board before the latest edit, to be replaced by `now`, which
+    /// `redo` brings back.
+    pub fn undo(&mut self, now: Snapshot) -> Option<Snapshot> {
+        let before = self.undo.pop_back()?;
+        self.redo.push(now);
+        Some(before)
+    }
+
+    /// The board the latest undo replaced, `now` going back on the undo
+    /// stack.
+    pub fn redo(&mut self, now: Snapshot) -> Option<Snapshot> {
+        let after = self.redo.pop()?;
+        self.undo.push_back(now);
+        Some(after)
+    }
+
+    pub fn count(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Forgets the edits after the first `len`, to undo several in one go.
+    pub fn truncate(&mut self, len: usize) {
+        self.undo.truncate(len);
+    }
+}